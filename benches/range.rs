@@ -1,16 +1,24 @@
+use rand::{rngs::SmallRng, seq::SliceRandom, SeedableRng};
+
 use simdify::SimdVec;
 
 pub trait GenRange: Sized {
     fn gen_range(size: usize) -> SimdVec<Self>;
+
+    // Not every bench which includes this module needs shuffled data.
+    #[allow(dead_code)]
+    fn gen_shuffled(size: usize) -> SimdVec<Self> {
+        let mut vec = Self::gen_range(size);
+        vec.shuffle(&mut SmallRng::from_entropy());
+        vec
+    }
 }
 
 impl GenRange for i8 {
     fn gen_range(size: usize) -> SimdVec<Self> {
-        let mut i = i8::min_value();
         let mut vec = SimdVec::new();
-        for _ in 0..size {
+        for i in (i8::MIN..=i8::MAX).take(size) {
             vec.push(i);
-            i += 1;
         }
         vec
     }
@@ -18,11 +26,9 @@ impl GenRange for i8 {
 
 impl GenRange for i32 {
     fn gen_range(size: usize) -> SimdVec<Self> {
-        let mut i = i32::min_value();
         let mut vec = SimdVec::new();
-        for _ in 0..size {
+        for i in (i32::MIN..=i32::MAX).take(size) {
             vec.push(i);
-            i += 1;
         }
         vec
     }
@@ -30,11 +36,29 @@ impl GenRange for i32 {
 
 impl GenRange for i64 {
     fn gen_range(size: usize) -> SimdVec<Self> {
-        let mut i = i64::min_value();
         let mut vec = SimdVec::new();
-        for _ in 0..size {
+        for i in (i64::MIN..=i64::MAX).take(size) {
+            vec.push(i);
+        }
+        vec
+    }
+}
+
+impl GenRange for u32 {
+    fn gen_range(size: usize) -> SimdVec<Self> {
+        let mut vec = SimdVec::new();
+        for i in (u32::MIN..=u32::MAX).take(size) {
+            vec.push(i);
+        }
+        vec
+    }
+}
+
+impl GenRange for u64 {
+    fn gen_range(size: usize) -> SimdVec<Self> {
+        let mut vec = SimdVec::new();
+        for i in (u64::MIN..=u64::MAX).take(size) {
             vec.push(i);
-            i += 1;
         }
         vec
     }
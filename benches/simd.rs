@@ -11,7 +11,9 @@ use rand::{rngs::SmallRng, Rng, SeedableRng};
 mod range;
 use range::GenRange;
 
-use simdify::{DefaultZero, SimdArrayOps, SimdOps, SimdVec};
+use simdify::{
+    DefaultZero, RadixKey, SimdArrayOps, SimdEliasFano, SimdLearnedIndex, SimdOps, SimdVec, SortKey,
+};
 
 fn simdify_k_ary_search<Int>(size: usize, b: &mut Bencher)
 where
@@ -77,3 +79,245 @@ fn simdify_k_ary_search_i64_10_000_000(b: &mut Bencher) {
 fn simdify_k_ary_search_i64_1_000_000_000(b: &mut Bencher) {
     simdify_k_ary_search::<i64>(1_000_000_000, b)
 }
+
+fn simdify_learned_index<Int>(size: usize, b: &mut Bencher)
+where
+    Int: Ord + Copy + DefaultZero + GenRange + Into<i128> + SimdOps<__m256i> + SimdOps<__m128i>,
+{
+    let mut gen = SmallRng::from_entropy();
+    let keys = Int::gen_range(size);
+    let index = gen.gen_range(0, keys.len());
+    let key = keys[index];
+    let learned = SimdLearnedIndex::new(keys, 64);
+    b.iter(|| {
+        assert_eq!(Ok(index), learned.search(key));
+    })
+}
+
+#[bench]
+fn simdify_learned_index_i32_10(b: &mut Bencher) {
+    simdify_learned_index::<i32>(10, b)
+}
+#[bench]
+fn simdify_learned_index_i32_1000(b: &mut Bencher) {
+    simdify_learned_index::<i32>(1000, b)
+}
+#[bench]
+fn simdify_learned_index_i32_100_000(b: &mut Bencher) {
+    simdify_learned_index::<i32>(100_000, b)
+}
+#[bench]
+fn simdify_learned_index_i32_10_000_000(b: &mut Bencher) {
+    simdify_learned_index::<i32>(10_000_000, b)
+}
+#[bench]
+fn simdify_learned_index_i32_1_000_000_000(b: &mut Bencher) {
+    simdify_learned_index::<i32>(1_000_000_000, b)
+}
+
+#[bench]
+fn simdify_learned_index_i64_10(b: &mut Bencher) {
+    simdify_learned_index::<i64>(10, b)
+}
+#[bench]
+fn simdify_learned_index_i64_1000(b: &mut Bencher) {
+    simdify_learned_index::<i64>(1000, b)
+}
+#[bench]
+fn simdify_learned_index_i64_100_000(b: &mut Bencher) {
+    simdify_learned_index::<i64>(100_000, b)
+}
+#[bench]
+fn simdify_learned_index_i64_10_000_000(b: &mut Bencher) {
+    simdify_learned_index::<i64>(10_000_000, b)
+}
+
+/// Sorted, sparse offsets, the case Elias–Fano is meant for.
+fn sparse_offsets(size: usize) -> SimdVec<u64> {
+    let mut vec = SimdVec::with_capacity(size);
+    vec.extend((0..size as u64).map(|index| index * 1_000 + index % 7));
+    vec
+}
+
+fn simdify_elias_fano_lower_bound(size: usize, b: &mut Bencher) {
+    let mut gen = SmallRng::from_entropy();
+    let offsets = sparse_offsets(size);
+    let index = gen.gen_range(0, offsets.len());
+    let key = offsets[index];
+    let encoded = SimdEliasFano::from(&offsets);
+    b.iter(|| {
+        assert_eq!(index, encoded.lower_bound(key));
+    })
+}
+
+fn simdify_elias_fano_search_baseline(size: usize, b: &mut Bencher) {
+    let mut gen = SmallRng::from_entropy();
+    let offsets = sparse_offsets(size);
+    let index = gen.gen_range(0, offsets.len());
+    let key = offsets[index];
+    b.iter(|| {
+        assert_eq!(Ok(index), offsets.search(key));
+    })
+}
+
+#[bench]
+fn simdify_elias_fano_lower_bound_1000(b: &mut Bencher) {
+    simdify_elias_fano_lower_bound(1000, b)
+}
+#[bench]
+fn simdify_elias_fano_lower_bound_100_000(b: &mut Bencher) {
+    simdify_elias_fano_lower_bound(100_000, b)
+}
+#[bench]
+fn simdify_elias_fano_lower_bound_10_000_000(b: &mut Bencher) {
+    simdify_elias_fano_lower_bound(10_000_000, b)
+}
+
+#[bench]
+fn simdify_elias_fano_search_baseline_1000(b: &mut Bencher) {
+    simdify_elias_fano_search_baseline(1000, b)
+}
+#[bench]
+fn simdify_elias_fano_search_baseline_100_000(b: &mut Bencher) {
+    simdify_elias_fano_search_baseline(100_000, b)
+}
+#[bench]
+fn simdify_elias_fano_search_baseline_10_000_000(b: &mut Bencher) {
+    simdify_elias_fano_search_baseline(10_000_000, b)
+}
+
+fn simdify_sort<Int>(size: usize, b: &mut Bencher)
+where
+    Int: DefaultZero + GenRange + SortKey,
+{
+    let keys = Int::gen_shuffled(size);
+    b.iter(|| {
+        let mut keys = keys.clone();
+        keys.sort();
+        keys
+    })
+}
+
+#[bench]
+fn simdify_sort_i8_16(b: &mut Bencher) {
+    simdify_sort::<i8>(16, b)
+}
+#[bench]
+fn simdify_sort_i8_256(b: &mut Bencher) {
+    simdify_sort::<i8>(256, b)
+}
+
+#[bench]
+fn simdify_sort_i32_10(b: &mut Bencher) {
+    simdify_sort::<i32>(10, b)
+}
+#[bench]
+fn simdify_sort_i32_1_000(b: &mut Bencher) {
+    simdify_sort::<i32>(1_000, b)
+}
+#[bench]
+fn simdify_sort_i32_100_000(b: &mut Bencher) {
+    simdify_sort::<i32>(100_000, b)
+}
+#[bench]
+fn simdify_sort_i32_10_000_000(b: &mut Bencher) {
+    simdify_sort::<i32>(10_000_000, b)
+}
+
+#[bench]
+fn simdify_sort_i64_10(b: &mut Bencher) {
+    simdify_sort::<i64>(10, b)
+}
+#[bench]
+fn simdify_sort_i64_1_000(b: &mut Bencher) {
+    simdify_sort::<i64>(1_000, b)
+}
+#[bench]
+fn simdify_sort_i64_100_000(b: &mut Bencher) {
+    simdify_sort::<i64>(100_000, b)
+}
+#[bench]
+fn simdify_sort_i64_10_000_000(b: &mut Bencher) {
+    simdify_sort::<i64>(10_000_000, b)
+}
+
+fn simdify_radix_sort<Int>(size: usize, b: &mut Bencher)
+where
+    Int: Ord + Copy + DefaultZero + GenRange + RadixKey,
+{
+    let keys = Int::gen_shuffled(size);
+    let mut buffer = SimdVec::new();
+    b.iter(|| {
+        let mut keys = keys.clone();
+        keys.radix_sort_with_buffer(&mut buffer);
+        keys
+    })
+}
+
+#[bench]
+fn simdify_radix_sort_i32_10(b: &mut Bencher) {
+    simdify_radix_sort::<i32>(10, b)
+}
+#[bench]
+fn simdify_radix_sort_i32_1_000(b: &mut Bencher) {
+    simdify_radix_sort::<i32>(1_000, b)
+}
+#[bench]
+fn simdify_radix_sort_i32_100_000(b: &mut Bencher) {
+    simdify_radix_sort::<i32>(100_000, b)
+}
+#[bench]
+fn simdify_radix_sort_i32_10_000_000(b: &mut Bencher) {
+    simdify_radix_sort::<i32>(10_000_000, b)
+}
+
+#[bench]
+fn simdify_radix_sort_i64_10(b: &mut Bencher) {
+    simdify_radix_sort::<i64>(10, b)
+}
+#[bench]
+fn simdify_radix_sort_i64_1_000(b: &mut Bencher) {
+    simdify_radix_sort::<i64>(1_000, b)
+}
+#[bench]
+fn simdify_radix_sort_i64_100_000(b: &mut Bencher) {
+    simdify_radix_sort::<i64>(100_000, b)
+}
+#[bench]
+fn simdify_radix_sort_i64_10_000_000(b: &mut Bencher) {
+    simdify_radix_sort::<i64>(10_000_000, b)
+}
+
+#[bench]
+fn simdify_radix_sort_u32_10(b: &mut Bencher) {
+    simdify_radix_sort::<u32>(10, b)
+}
+#[bench]
+fn simdify_radix_sort_u32_1_000(b: &mut Bencher) {
+    simdify_radix_sort::<u32>(1_000, b)
+}
+#[bench]
+fn simdify_radix_sort_u32_100_000(b: &mut Bencher) {
+    simdify_radix_sort::<u32>(100_000, b)
+}
+#[bench]
+fn simdify_radix_sort_u32_10_000_000(b: &mut Bencher) {
+    simdify_radix_sort::<u32>(10_000_000, b)
+}
+
+#[bench]
+fn simdify_radix_sort_u64_10(b: &mut Bencher) {
+    simdify_radix_sort::<u64>(10, b)
+}
+#[bench]
+fn simdify_radix_sort_u64_1_000(b: &mut Bencher) {
+    simdify_radix_sort::<u64>(1_000, b)
+}
+#[bench]
+fn simdify_radix_sort_u64_100_000(b: &mut Bencher) {
+    simdify_radix_sort::<u64>(100_000, b)
+}
+#[bench]
+fn simdify_radix_sort_u64_10_000_000(b: &mut Bencher) {
+    simdify_radix_sort::<u64>(10_000_000, b)
+}
@@ -74,3 +74,58 @@ fn std_binary_search_i64_10_000_000(b: &mut Bencher) {
 fn std_binary_search_i64_1_000_000_000(b: &mut Bencher) {
     std_binary_search::<i64>(1_000_000_000, b)
 }
+
+fn std_sort_unstable<Int>(size: usize, b: &mut Bencher)
+where
+    Int: Ord + Copy + DefaultZero + GenRange,
+{
+    let keys = Int::gen_shuffled(size);
+    b.iter(|| {
+        let mut keys = keys.clone();
+        keys.sort_unstable();
+        keys
+    })
+}
+
+#[bench]
+fn std_sort_unstable_i8_16(b: &mut Bencher) {
+    std_sort_unstable::<i8>(16, b)
+}
+#[bench]
+fn std_sort_unstable_i8_256(b: &mut Bencher) {
+    std_sort_unstable::<i8>(256, b)
+}
+
+#[bench]
+fn std_sort_unstable_i32_10(b: &mut Bencher) {
+    std_sort_unstable::<i32>(10, b)
+}
+#[bench]
+fn std_sort_unstable_i32_1_000(b: &mut Bencher) {
+    std_sort_unstable::<i32>(1_000, b)
+}
+#[bench]
+fn std_sort_unstable_i32_100_000(b: &mut Bencher) {
+    std_sort_unstable::<i32>(100_000, b)
+}
+#[bench]
+fn std_sort_unstable_i32_10_000_000(b: &mut Bencher) {
+    std_sort_unstable::<i32>(10_000_000, b)
+}
+
+#[bench]
+fn std_sort_unstable_i64_10(b: &mut Bencher) {
+    std_sort_unstable::<i64>(10, b)
+}
+#[bench]
+fn std_sort_unstable_i64_1_000(b: &mut Bencher) {
+    std_sort_unstable::<i64>(1_000, b)
+}
+#[bench]
+fn std_sort_unstable_i64_100_000(b: &mut Bencher) {
+    std_sort_unstable::<i64>(100_000, b)
+}
+#[bench]
+fn std_sort_unstable_i64_10_000_000(b: &mut Bencher) {
+    std_sort_unstable::<i64>(10_000_000, b)
+}
@@ -7,7 +7,7 @@ use std::ops::{Deref, DerefMut};
 
 use generic_array::{ArrayLength, GenericArray};
 
-use crate::{DefaultZero, SimdArrayOps};
+use crate::{DefaultZero, SimdArrayOps, SimdOps, SortKey};
 
 /// A fixed capacity stack allocated SIMD aligned vector.
 ///
@@ -89,6 +89,38 @@ where
     }
 }
 
+impl<A, N> SimdArray<A, N>
+where
+    N: ArrayLength<arch::__m256i>,
+    A: SortKey,
+{
+    /// Sort the array.
+    ///
+    /// This uses in-register bitonic sorting networks followed by vectorised
+    /// merging of the sorted runs when AVX2 is available, and falls back to
+    /// `sort_unstable` otherwise, and for types without a network, such as
+    /// 128-bit integers. The sort is not stable.
+    pub fn sort(&mut self) {
+        crate::sort::sort::<A>(&mut self.data, self.size)
+    }
+}
+
+impl<A, N> SimdArray<A, N>
+where
+    N: ArrayLength<arch::__m256i>,
+    A: Ord + Copy + SimdOps<arch::__m256i> + SimdOps<arch::__m128i>,
+{
+    /// Count the elements of a sorted array which are less than `key`,
+    /// without branching.
+    ///
+    /// Every chunk is compared with the key and the comparison bitmaps are
+    /// counted, so this beats `rank` for arrays of one or two chunks, where
+    /// bisection costs more in mispredicted branches than it saves.
+    pub fn rank_branchless(&self, key: A) -> usize {
+        crate::array_ops::rank_branchless(self, key)
+    }
+}
+
 impl<A, N> SimdArrayOps<A> for SimdArray<A, N>
 where
     A: Ord,
@@ -109,6 +141,19 @@ where
     }
 }
 
+impl<A, N> Clone for SimdArray<A, N>
+where
+    N: ArrayLength<arch::__m256i>,
+{
+    fn clone(&self) -> Self {
+        SimdArray {
+            phantom: PhantomData,
+            size: self.size,
+            data: self.data.clone(),
+        }
+    }
+}
+
 impl<A, N> Deref for SimdArray<A, N>
 where
     N: ArrayLength<arch::__m256i>,
@@ -1,9 +1,43 @@
 use std::arch::x86_64::{self as arch, __m128i, __m256i};
-use std::ops::Deref;
+use std::ops::{Deref, Range};
 
 use bitmaps::Bitmap;
 
-use crate::simd_ops::{SimdOps, SimdRegister};
+use crate::compact::{self, Filter};
+use crate::kernels::{self, ArithOp, CmpOp};
+use crate::set_ops;
+use crate::simd_arith::SimdArith;
+use crate::simd_ops::{has_sse_compare, SimdOps, SimdRegister};
+use crate::{DefaultZero, SimdBitVec, SimdVec};
+
+pub(crate) use self::sealed::KeyDistance;
+
+mod sealed {
+    /// Integers whose distance apart can be measured without overflow.
+    ///
+    /// This is sealed: it's implemented for the primitive integer types.
+    pub trait KeyDistance: Copy {
+        /// Get the distance from `self` up to `other`, which must be no
+        /// less than `self`.
+        fn distance_to(self, other: Self) -> u128;
+    }
+
+    // Both casts are sign preserving, so the wrapped difference is exact.
+    macro_rules! key_distance {
+        ($wide:ty; $($t:ty),*) => {
+            $(
+                impl KeyDistance for $t {
+                    fn distance_to(self, other: $t) -> u128 {
+                        (other as $wide).wrapping_sub(self as $wide) as u128
+                    }
+                }
+            )*
+        };
+    }
+
+    key_distance!(i128; i8, i16, i32, i64, i128, isize);
+    key_distance!(u128; u8, u16, u32, u64, u128, usize);
+}
 
 /// SIMD optimised array operations.
 pub trait SimdArrayOps<A>: Deref<Target = [A]>
@@ -20,13 +54,585 @@ where
     {
         if is_x86_feature_detected!("avx2") {
             unsafe { self.k_ary_search::<__m256i>(key) }
-        } else if is_x86_feature_detected!("sse2") {
+        } else if has_sse_compare::<A>() {
             unsafe { self.k_ary_search::<__m128i>(key) }
         } else {
             self.deref().binary_search(&key)
         }
     }
 
+    /// Search for a key, starting from a hint of where it is likely to be.
+    ///
+    /// Whole chunks at exponentially growing distances from `hint`, in the
+    /// direction of the key, are compared against it until one brackets it,
+    /// and the bracketed range is then searched with `k_ary_search_range`.
+    /// This beats `search` when successive keys lie close together. A hint
+    /// past the end of the array is treated as the end.
+    fn search_from(&self, hint: usize, key: A) -> Result<usize, usize>
+    where
+        A: Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        let hint = std::cmp::min(hint, self.len());
+        if hint < self.len() && self[hint] <= key {
+            self.gallop_search(key, hint)
+        } else if is_x86_feature_detected!("avx2") {
+            unsafe { gallop_backward::<__m256i, _, _>(self, key, hint) }
+        } else if has_sse_compare::<A>() {
+            unsafe { gallop_backward::<__m128i, _, _>(self, key, hint) }
+        } else {
+            gallop_backward_scalar(self, key, hint)
+        }
+    }
+
+    /// Count the elements of a sorted array which are less than `key`.
+    ///
+    /// Chunks are bisected until one holds the transition past `key`, and
+    /// the rank is then the popcount of that chunk's comparison bitmap.
+    fn rank(&self, key: A) -> usize
+    where
+        A: Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        rank(self, key, false)
+    }
+
+    /// Count the elements of a sorted array which lie within `range`.
+    fn count_range(&self, range: Range<A>) -> usize
+    where
+        A: Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        if range.start >= range.end {
+            0
+        } else {
+            self.rank(range.end) - self.rank(range.start)
+        }
+    }
+
+    /// Get the element of a sorted array with `rank` elements before it.
+    ///
+    /// Returns `None` if `rank` is not less than the length of the array.
+    fn select(&self, rank: usize) -> Option<A>
+    where
+        A: Copy,
+    {
+        self.deref().get(rank).copied()
+    }
+
+    /// Find the largest element of a sorted array which is less than `key`.
+    ///
+    /// Returns the index and the element, choosing the last of any
+    /// duplicates, or `None` if every element is at least `key`.
+    fn predecessor(&self, key: A) -> Option<(usize, A)>
+    where
+        A: Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        let index = rank(self, key, false).checked_sub(1)?;
+        Some((index, self[index]))
+    }
+
+    /// Find the smallest element of a sorted array which is greater than
+    /// `key`.
+    ///
+    /// Returns the index and the element, choosing the first of any
+    /// duplicates, or `None` if every element is at most `key`.
+    fn successor(&self, key: A) -> Option<(usize, A)>
+    where
+        A: Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        let index = rank(self, key, true);
+        self.select(index).map(|value| (index, value))
+    }
+
+    /// Find the largest element of a sorted array which is not greater than
+    /// `key`.
+    ///
+    /// Returns the index and the element, choosing the last of any
+    /// duplicates, or `None` if every element is greater than `key`.
+    fn floor(&self, key: A) -> Option<(usize, A)>
+    where
+        A: Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        let index = rank(self, key, true).checked_sub(1)?;
+        Some((index, self[index]))
+    }
+
+    /// Find the smallest element of a sorted array which is not less than
+    /// `key`.
+    ///
+    /// Returns the index and the element, choosing the first of any
+    /// duplicates, or `None` if every element is less than `key`.
+    fn ceil(&self, key: A) -> Option<(usize, A)>
+    where
+        A: Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        let index = rank(self, key, false);
+        self.select(index).map(|value| (index, value))
+    }
+
+    /// Find the element of a sorted array closest to `key`.
+    ///
+    /// This is whichever of `floor` and `ceil` is closer, preferring `floor`
+    /// on a tie. Returns `None` if the array is empty.
+    fn nearest(&self, key: A) -> Option<(usize, A)>
+    where
+        A: KeyDistance + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        match (self.floor(key), self.ceil(key)) {
+            (Some(below), Some(above)) => {
+                if below.1.distance_to(key) <= key.distance_to(above.1) {
+                    Some(below)
+                } else {
+                    Some(above)
+                }
+            }
+            (below, above) => below.or(above),
+        }
+    }
+
+    /// Search for a key by interpolating its position between the smallest
+    /// and largest elements.
+    ///
+    /// Each probe estimates the chunk holding the key from its value and
+    /// confirms it with a SIMD compare of that chunk. On nearly uniform data
+    /// this needs far fewer probes than `search`. If the estimates keep
+    /// missing, the remaining range is searched with `k_ary_search_range`,
+    /// so skewed data costs no more than a logarithmic search.
+    ///
+    /// Available for the primitive integer types.
+    fn interpolation_search(&self, key: A) -> Result<usize, usize>
+    where
+        A: KeyDistance + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        if is_x86_feature_detected!("avx2") {
+            unsafe { interpolate::<__m256i, _, _>(self, key) }
+        } else if has_sse_compare::<A>() {
+            unsafe { interpolate::<__m128i, _, _>(self, key) }
+        } else {
+            self.deref().binary_search(&key)
+        }
+    }
+
+    /// Search for a key among the elements from index `from` onwards,
+    /// galloping forwards a chunk at a time.
+    ///
+    /// The result is an index into the whole array, never less than `from`.
+    ///
+    /// Panics if `from` is out of bounds.
+    fn gallop_search(&self, key: A, from: usize) -> Result<usize, usize>
+    where
+        A: Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        if from > self.len() {
+            panic!(
+                "SimdArrayOps::gallop_search: index {} out of bounds for length {}",
+                from,
+                self.len()
+            )
+        }
+        if is_x86_feature_detected!("avx2") {
+            unsafe { gallop_forward::<__m256i, _, _>(self, key, from) }
+        } else if has_sse_compare::<A>() {
+            unsafe { gallop_forward::<__m128i, _, _>(self, key, from) }
+        } else {
+            gallop_forward_scalar(self, key, from)
+        }
+    }
+
+    /// Add two arrays element by element.
+    ///
+    /// Overflowing elements wrap around.
+    ///
+    /// Panics if the arrays differ in length.
+    fn add_vec<T>(&self, other: &T) -> SimdVec<A>
+    where
+        T: SimdArrayOps<A> + ?Sized,
+        A: Copy + DefaultZero + SimdArith<__m256i> + SimdArith<__m128i>,
+    {
+        kernels::arith_vec(self, other, ArithOp::Add)
+    }
+
+    /// Add a value to every element, wrapping on overflow.
+    fn add_scalar(&self, value: A) -> SimdVec<A>
+    where
+        A: Copy + DefaultZero + SimdArith<__m256i> + SimdArith<__m128i>,
+    {
+        kernels::arith_scalar(self, value, ArithOp::Add)
+    }
+
+    /// Subtract an array from this one element by element.
+    ///
+    /// Overflowing elements wrap around.
+    ///
+    /// Panics if the arrays differ in length.
+    fn sub_vec<T>(&self, other: &T) -> SimdVec<A>
+    where
+        T: SimdArrayOps<A> + ?Sized,
+        A: Copy + DefaultZero + SimdArith<__m256i> + SimdArith<__m128i>,
+    {
+        kernels::arith_vec(self, other, ArithOp::Sub)
+    }
+
+    /// Subtract a value from every element, wrapping on overflow.
+    fn sub_scalar(&self, value: A) -> SimdVec<A>
+    where
+        A: Copy + DefaultZero + SimdArith<__m256i> + SimdArith<__m128i>,
+    {
+        kernels::arith_scalar(self, value, ArithOp::Sub)
+    }
+
+    /// Take the element by element minimum of two arrays.
+    ///
+    /// The result holds the smaller of each pair of elements.
+    ///
+    /// Panics if the arrays differ in length.
+    fn min_vec<T>(&self, other: &T) -> SimdVec<A>
+    where
+        T: SimdArrayOps<A> + ?Sized,
+        A: Copy + DefaultZero + SimdArith<__m256i> + SimdArith<__m128i>,
+    {
+        kernels::arith_vec(self, other, ArithOp::Min)
+    }
+
+    /// Clamp every element to at most `value`.
+    fn min_scalar(&self, value: A) -> SimdVec<A>
+    where
+        A: Copy + DefaultZero + SimdArith<__m256i> + SimdArith<__m128i>,
+    {
+        kernels::arith_scalar(self, value, ArithOp::Min)
+    }
+
+    /// Take the element by element maximum of two arrays.
+    ///
+    /// The result holds the larger of each pair of elements.
+    ///
+    /// Panics if the arrays differ in length.
+    fn max_vec<T>(&self, other: &T) -> SimdVec<A>
+    where
+        T: SimdArrayOps<A> + ?Sized,
+        A: Copy + DefaultZero + SimdArith<__m256i> + SimdArith<__m128i>,
+    {
+        kernels::arith_vec(self, other, ArithOp::Max)
+    }
+
+    /// Clamp every element to at least `value`.
+    fn max_scalar(&self, value: A) -> SimdVec<A>
+    where
+        A: Copy + DefaultZero + SimdArith<__m256i> + SimdArith<__m128i>,
+    {
+        kernels::arith_scalar(self, value, ArithOp::Max)
+    }
+
+    /// Add two arrays element by element.
+    ///
+    /// Overflowing elements saturate at the numeric bounds.
+    ///
+    /// Panics if the arrays differ in length.
+    fn saturating_add_vec<T>(&self, other: &T) -> SimdVec<A>
+    where
+        T: SimdArrayOps<A> + ?Sized,
+        A: Copy + DefaultZero + SimdArith<__m256i> + SimdArith<__m128i>,
+    {
+        kernels::arith_vec(self, other, ArithOp::SaturatingAdd)
+    }
+
+    /// Add a value to every element, saturating at the numeric bounds.
+    fn saturating_add_scalar(&self, value: A) -> SimdVec<A>
+    where
+        A: Copy + DefaultZero + SimdArith<__m256i> + SimdArith<__m128i>,
+    {
+        kernels::arith_scalar(self, value, ArithOp::SaturatingAdd)
+    }
+
+    /// Subtract an array from this one element by element.
+    ///
+    /// Overflowing elements saturate at the numeric bounds.
+    ///
+    /// Panics if the arrays differ in length.
+    fn saturating_sub_vec<T>(&self, other: &T) -> SimdVec<A>
+    where
+        T: SimdArrayOps<A> + ?Sized,
+        A: Copy + DefaultZero + SimdArith<__m256i> + SimdArith<__m128i>,
+    {
+        kernels::arith_vec(self, other, ArithOp::SaturatingSub)
+    }
+
+    /// Subtract a value from every element, saturating at the numeric bounds.
+    fn saturating_sub_scalar(&self, value: A) -> SimdVec<A>
+    where
+        A: Copy + DefaultZero + SimdArith<__m256i> + SimdArith<__m128i>,
+    {
+        kernels::arith_scalar(self, value, ArithOp::SaturatingSub)
+    }
+
+    /// Take the absolute value of every element.
+    ///
+    /// Like `wrapping_abs`, the minimum value of the type maps to itself.
+    fn abs(&self) -> SimdVec<A>
+    where
+        A: Copy + DefaultZero + SimdArith<__m256i> + SimdArith<__m128i>,
+    {
+        kernels::abs(self)
+    }
+
+    /// Compare two arrays element by element, selecting the elements of this
+    /// array which are equal to the other's.
+    ///
+    /// Panics if the arrays differ in length.
+    fn eq_vec<T>(&self, other: &T) -> SimdBitVec
+    where
+        T: SimdArrayOps<A> + ?Sized,
+        A: Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        kernels::compare_vec(self, other, CmpOp::Eq)
+    }
+
+    /// Select the elements which are equal to `value`.
+    fn eq_scalar(&self, value: A) -> SimdBitVec
+    where
+        A: Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        kernels::compare_scalar(self, value, CmpOp::Eq)
+    }
+
+    /// Compare two arrays element by element, selecting the elements of this
+    /// array which are not equal to the other's.
+    ///
+    /// Panics if the arrays differ in length.
+    fn ne_vec<T>(&self, other: &T) -> SimdBitVec
+    where
+        T: SimdArrayOps<A> + ?Sized,
+        A: Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        kernels::compare_vec(self, other, CmpOp::Ne)
+    }
+
+    /// Select the elements which are not equal to `value`.
+    fn ne_scalar(&self, value: A) -> SimdBitVec
+    where
+        A: Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        kernels::compare_scalar(self, value, CmpOp::Ne)
+    }
+
+    /// Compare two arrays element by element, selecting the elements of this
+    /// array which are less than the other's.
+    ///
+    /// Panics if the arrays differ in length.
+    fn lt_vec<T>(&self, other: &T) -> SimdBitVec
+    where
+        T: SimdArrayOps<A> + ?Sized,
+        A: Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        kernels::compare_vec(self, other, CmpOp::Lt)
+    }
+
+    /// Select the elements which are less than `value`.
+    fn lt_scalar(&self, value: A) -> SimdBitVec
+    where
+        A: Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        kernels::compare_scalar(self, value, CmpOp::Lt)
+    }
+
+    /// Compare two arrays element by element, selecting the elements of this
+    /// array which are less than or equal to the other's.
+    ///
+    /// Panics if the arrays differ in length.
+    fn le_vec<T>(&self, other: &T) -> SimdBitVec
+    where
+        T: SimdArrayOps<A> + ?Sized,
+        A: Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        kernels::compare_vec(self, other, CmpOp::Le)
+    }
+
+    /// Select the elements which are less than or equal to `value`.
+    fn le_scalar(&self, value: A) -> SimdBitVec
+    where
+        A: Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        kernels::compare_scalar(self, value, CmpOp::Le)
+    }
+
+    /// Compare two arrays element by element, selecting the elements of this
+    /// array which are greater than the other's.
+    ///
+    /// Panics if the arrays differ in length.
+    fn gt_vec<T>(&self, other: &T) -> SimdBitVec
+    where
+        T: SimdArrayOps<A> + ?Sized,
+        A: Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        kernels::compare_vec(self, other, CmpOp::Gt)
+    }
+
+    /// Select the elements which are greater than `value`.
+    fn gt_scalar(&self, value: A) -> SimdBitVec
+    where
+        A: Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        kernels::compare_scalar(self, value, CmpOp::Gt)
+    }
+
+    /// Compare two arrays element by element, selecting the elements of this
+    /// array which are greater than or equal to the other's.
+    ///
+    /// Panics if the arrays differ in length.
+    fn ge_vec<T>(&self, other: &T) -> SimdBitVec
+    where
+        T: SimdArrayOps<A> + ?Sized,
+        A: Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        kernels::compare_vec(self, other, CmpOp::Ge)
+    }
+
+    /// Select the elements which are greater than or equal to `value`.
+    fn ge_scalar(&self, value: A) -> SimdBitVec
+    where
+        A: Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        kernels::compare_scalar(self, value, CmpOp::Ge)
+    }
+
+    /// Collect the elements whose bits are set in a selection bitmap, as
+    /// produced by comparisons like `lt_scalar`, into a new vector.
+    ///
+    /// Elements are left-packed a 32-byte block at a time using shuffle
+    /// tables when AVX2 is available.
+    ///
+    /// Panics if the selection differs in length from the array.
+    fn compact(&self, selection: &SimdBitVec) -> SimdVec<A>
+    where
+        A: Copy + DefaultZero,
+    {
+        compact::compact(self, selection)
+    }
+
+    /// Collect the elements equal to `value` into a new vector.
+    fn filter_eq(&self, value: A) -> SimdVec<A>
+    where
+        A: Copy + DefaultZero + SimdOps<__m256i>,
+    {
+        compact::filter(self, Filter::Eq(value))
+    }
+
+    /// Collect the elements greater than `value` into a new vector.
+    fn filter_gt(&self, value: A) -> SimdVec<A>
+    where
+        A: Copy + DefaultZero + SimdOps<__m256i>,
+    {
+        compact::filter(self, Filter::Gt(value))
+    }
+
+    /// Collect the elements within `range` into a new vector.
+    fn filter_range(&self, range: Range<A>) -> SimdVec<A>
+    where
+        A: Copy + DefaultZero + SimdOps<__m256i>,
+    {
+        compact::filter(self, Filter::Range(range))
+    }
+
+    /// Collect the elements common to both arrays.
+    ///
+    /// Both arrays must be sorted and free of duplicates, otherwise the
+    /// result is unspecified.
+    fn intersect<T>(&self, other: &T) -> SimdVec<A>
+    where
+        T: SimdArrayOps<A> + ?Sized,
+        A: Copy + DefaultZero + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        set_ops::intersect(self, other)
+    }
+
+    /// Count the elements common to both arrays, without collecting them.
+    ///
+    /// Both arrays must be sorted and free of duplicates, otherwise the
+    /// result is unspecified.
+    fn intersect_count<T>(&self, other: &T) -> usize
+    where
+        T: SimdArrayOps<A> + ?Sized,
+        A: Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        set_ops::intersect_count(self, other)
+    }
+
+    /// Collect the elements in either array.
+    ///
+    /// Both arrays must be sorted and free of duplicates, otherwise the
+    /// result is unspecified.
+    fn union<T>(&self, other: &T) -> SimdVec<A>
+    where
+        T: SimdArrayOps<A> + ?Sized,
+        A: Copy + DefaultZero + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        set_ops::union(self, other)
+    }
+
+    /// Count the elements in either array, without collecting them.
+    ///
+    /// Both arrays must be sorted and free of duplicates, otherwise the
+    /// result is unspecified.
+    fn union_count<T>(&self, other: &T) -> usize
+    where
+        T: SimdArrayOps<A> + ?Sized,
+        A: Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        set_ops::union_count(self, other)
+    }
+
+    /// Collect the elements of this array which are not in `other`.
+    ///
+    /// Both arrays must be sorted and free of duplicates, otherwise the
+    /// result is unspecified.
+    fn difference<T>(&self, other: &T) -> SimdVec<A>
+    where
+        T: SimdArrayOps<A> + ?Sized,
+        A: Copy + DefaultZero + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        set_ops::difference(self, other)
+    }
+
+    /// Count the elements of this array which are not in `other`, without collecting them.
+    ///
+    /// Both arrays must be sorted and free of duplicates, otherwise the
+    /// result is unspecified.
+    fn difference_count<T>(&self, other: &T) -> usize
+    where
+        T: SimdArrayOps<A> + ?Sized,
+        A: Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        set_ops::difference_count(self, other)
+    }
+
+    /// Collect the elements in exactly one of the arrays.
+    ///
+    /// Both arrays must be sorted and free of duplicates, otherwise the
+    /// result is unspecified.
+    fn symmetric_difference<T>(&self, other: &T) -> SimdVec<A>
+    where
+        T: SimdArrayOps<A> + ?Sized,
+        A: Copy + DefaultZero + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        set_ops::symmetric_difference(self, other)
+    }
+
+    /// Count the elements in exactly one of the arrays, without collecting them.
+    ///
+    /// Both arrays must be sorted and free of duplicates, otherwise the
+    /// result is unspecified.
+    fn symmetric_difference_count<T>(&self, other: &T) -> usize
+    where
+        T: SimdArrayOps<A> + ?Sized,
+        A: Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    {
+        set_ops::symmetric_difference_count(self, other)
+    }
+
+    /// Load the chunk starting at `index` into a register.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support the instruction set for `R`, and `index` must be
+    /// in bounds and a multiple of `A::ALIGNMENT`.
     #[inline]
     unsafe fn load<R>(&self, index: usize) -> R
     where
@@ -48,6 +654,10 @@ where
     /// The algorithm is described in ['k-Ary Search
     /// on Modern Processors,'
     /// 2009](https://event.cwi.nl/damon2009/DaMoN09-KarySearch.pdf).
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support the instruction set for `R`.
     unsafe fn k_ary_search<R>(&self, key: A) -> Result<usize, usize>
     where
         R: SimdRegister,
@@ -105,4 +715,327 @@ where
             return Err(index);
         }
     }
+
+    /// k-ary search for a key within a range of a sorted array.
+    ///
+    /// Whole registers are compared against the key, bisecting over the
+    /// registers which overlap `range`. Returns an index into the whole
+    /// array, as `search` does, lying within `range`.
+    ///
+    /// Panics if `range` is out of bounds.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support the instruction set for `R`.
+    unsafe fn k_ary_search_range<R>(&self, key: A, range: Range<usize>) -> Result<usize, usize>
+    where
+        R: SimdRegister,
+        A: Copy + SimdOps<R>,
+    {
+        if range.start > range.end || range.end > self.len() {
+            panic!(
+                "SimdArrayOps::k_ary_search_range: range {:?} out of bounds for length {}",
+                range,
+                self.len()
+            )
+        }
+        if range.start == range.end {
+            return Err(range.start);
+        }
+        let keys = A::set(key);
+        let mut low = range.start / A::ALIGNMENT;
+        let mut high = range.end.div_ceil(A::ALIGNMENT);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let pos = middle * A::ALIGNMENT;
+            let data = self.load(pos);
+            let valid = lanes_within::<R, A>(pos, &range);
+            let eq = A::cmp_eq(data, keys) & valid;
+            if !eq.is_empty() {
+                return Ok(pos + eq.first_index().unwrap() / A::BITS_PER_CMP);
+            }
+            let greater = A::cmp_gt(data, keys) & valid;
+            if greater.is_empty() {
+                low = middle + 1;
+            } else if greater == valid {
+                high = middle;
+            } else {
+                return Err(pos + greater.first_index().unwrap() / A::BITS_PER_CMP);
+            }
+        }
+        Err((low * A::ALIGNMENT).clamp(range.start, range.end))
+    }
+}
+
+/// Get the lanes of the chunk starting at `pos` whose indices lie within
+/// `range`.
+pub(crate) fn lanes_within<R, A>(pos: usize, range: &Range<usize>) -> Bitmap<R::MovemaskSize>
+where
+    R: SimdRegister,
+    A: SimdOps<R>,
+{
+    let mut lanes = !Bitmap::new();
+    if range.end < pos + A::ALIGNMENT {
+        lanes &= Bitmap::mask(range.end.saturating_sub(pos) * A::BITS_PER_CMP);
+    }
+    if range.start > pos {
+        lanes &= !Bitmap::mask((range.start - pos) * A::BITS_PER_CMP);
+    }
+    lanes
+}
+
+/// Count the elements of a sorted array less than `key`, or not greater than
+/// it if `inclusive` is set.
+pub(crate) fn rank<A, S>(source: &S, key: A, inclusive: bool) -> usize
+where
+    A: Ord + Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    S: SimdArrayOps<A> + ?Sized,
+{
+    rank_in(source, key, inclusive, 0..source.len())
+}
+
+/// Find the index in `range` of a sorted array before which every element is
+/// less than `key`, or not greater than it if `inclusive` is set.
+pub(crate) fn rank_in<A, S>(source: &S, key: A, inclusive: bool, range: Range<usize>) -> usize
+where
+    A: Ord + Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    S: SimdArrayOps<A> + ?Sized,
+{
+    if is_x86_feature_detected!("avx2") {
+        unsafe { rank_with::<__m256i, _, _>(source, key, inclusive, range) }
+    } else if has_sse_compare::<A>() {
+        unsafe { rank_with::<__m128i, _, _>(source, key, inclusive, range) }
+    } else {
+        let start = range.start;
+        let before = |item: &A| if inclusive { *item <= key } else { *item < key };
+        start + source[range].partition_point(before)
+    }
+}
+
+unsafe fn rank_with<R, A, S>(source: &S, key: A, inclusive: bool, range: Range<usize>) -> usize
+where
+    R: SimdRegister,
+    A: Ord + Copy + SimdOps<R>,
+    S: SimdArrayOps<A> + ?Sized,
+{
+    let keys = A::set(key);
+    let mut low = range.start / A::ALIGNMENT;
+    let mut high = range.end.div_ceil(A::ALIGNMENT);
+    while low < high {
+        let middle = low + (high - low) / 2;
+        let pos = middle * A::ALIGNMENT;
+        let data = source.load(pos);
+        let valid = lanes_within::<R, A>(pos, &range);
+        let before = if inclusive {
+            !A::cmp_gt(data, keys)
+        } else {
+            A::cmp_gt(keys, data)
+        } & valid;
+        if before == valid {
+            low = middle + 1;
+        } else if before.is_empty() {
+            high = middle;
+        } else {
+            return std::cmp::max(pos, range.start) + before.len() / A::BITS_PER_CMP;
+        }
+    }
+    (low * A::ALIGNMENT).clamp(range.start, range.end)
+}
+
+/// Count the elements of a sorted array less than `key` by comparing every
+/// chunk, without any data dependent branches.
+pub(crate) fn rank_branchless<A, S>(source: &S, key: A) -> usize
+where
+    A: Ord + Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    S: SimdArrayOps<A> + ?Sized,
+{
+    if is_x86_feature_detected!("avx2") {
+        unsafe { rank_branchless_with::<__m256i, _, _>(source, key) }
+    } else if has_sse_compare::<A>() {
+        unsafe { rank_branchless_with::<__m128i, _, _>(source, key) }
+    } else {
+        source.iter().filter(|item| **item < key).count()
+    }
+}
+
+unsafe fn rank_branchless_with<R, A, S>(source: &S, key: A) -> usize
+where
+    R: SimdRegister,
+    A: Ord + Copy + SimdOps<R>,
+    S: SimdArrayOps<A> + ?Sized,
+{
+    let keys = A::set(key);
+    let mut count = 0;
+    for (index, register) in R::from_m256i(source.data_m256()).iter().enumerate() {
+        let remaining = std::cmp::min(
+            source.len().saturating_sub(index * A::ALIGNMENT),
+            A::ALIGNMENT,
+        );
+        let valid = (1u64 << (remaining * A::BITS_PER_CMP)) - 1;
+        let less = u64::from(R::mask_value(A::cmp_gt(keys, register.load())));
+        count += (less & valid).count_ones() as usize;
+    }
+    count / A::BITS_PER_CMP
+}
+
+/// The number of interpolation probes to make before falling back to a k-ary
+/// search of the remaining range.
+const INTERPOLATION_PROBES: usize = 4;
+
+unsafe fn interpolate<R, A, S>(source: &S, key: A) -> Result<usize, usize>
+where
+    R: SimdRegister,
+    A: Ord + KeyDistance + SimdOps<R>,
+    S: SimdArrayOps<A> + ?Sized,
+{
+    let keys = A::set(key);
+    let mut low = 0;
+    let mut high = source.len();
+    for _ in 0..INTERPOLATION_PROBES {
+        if low >= high {
+            return Err(low);
+        }
+        let (first, last) = (source[low], source[high - 1]);
+        if key < first {
+            return Err(low);
+        }
+        if key > last {
+            return Err(high);
+        }
+        let estimate = if first == last {
+            low
+        } else {
+            // Scale the distances down until the product fits in a `u128`.
+            let mut distance = first.distance_to(key);
+            let mut range = first.distance_to(last);
+            let shift = (128 - range.leading_zeros()).saturating_sub(64);
+            distance >>= shift;
+            range >>= shift;
+            let offset = distance * (high - 1 - low) as u128 / range;
+            low + offset as usize
+        };
+        let pos = estimate - estimate % A::ALIGNMENT;
+        let data = source.load(pos);
+        let valid = lanes_within::<R, A>(pos, &(low..high));
+        let eq = A::cmp_eq(data, keys) & valid;
+        if !eq.is_empty() {
+            return Ok(pos + eq.first_index().unwrap() / A::BITS_PER_CMP);
+        }
+        let greater = A::cmp_gt(data, keys) & valid;
+        if greater.is_empty() {
+            low = std::cmp::min(pos + A::ALIGNMENT, high);
+        } else if greater == valid {
+            high = std::cmp::max(pos, low);
+        } else {
+            return Err(pos + greater.first_index().unwrap() / A::BITS_PER_CMP);
+        }
+    }
+    source.k_ary_search_range::<R>(key, low..high)
+}
+
+/// Probe chunks at exponentially growing distances after `from` until one
+/// holds an element greater than the key, then search the bracketed range.
+unsafe fn gallop_forward<R, A, S>(source: &S, key: A, from: usize) -> Result<usize, usize>
+where
+    R: SimdRegister,
+    A: Ord + Copy + SimdOps<R>,
+    S: SimdArrayOps<A> + ?Sized,
+{
+    let len = source.len();
+    let keys = A::set(key);
+    let mut low = from;
+    let mut high = len;
+    let mut chunk = from / A::ALIGNMENT;
+    let mut step = 1;
+    while chunk * A::ALIGNMENT < len {
+        let pos = chunk * A::ALIGNMENT;
+        let greater = A::cmp_gt(source.load(pos), keys) & lanes_within::<R, A>(pos, &(from..len));
+        if let Some(lane) = greater.first_index() {
+            high = pos + lane / A::BITS_PER_CMP;
+            break;
+        }
+        low = std::cmp::max(low, pos);
+        chunk += step;
+        step *= 2;
+    }
+    source.k_ary_search_range::<R>(key, low..high)
+}
+
+/// Gallop forwards from `from` an element at a time, then binary search the
+/// bracketed range, for CPUs without the compares `gallop_forward` needs.
+pub(crate) fn gallop_forward_scalar<A>(source: &[A], key: A, from: usize) -> Result<usize, usize>
+where
+    A: Ord,
+{
+    let (mut low, mut high) = (from, from);
+    let mut step = 1;
+    while high < source.len() && source[high] <= key {
+        low = high;
+        high += step;
+        step *= 2;
+    }
+    let high = std::cmp::min(high, source.len());
+    match source[low..high].binary_search(&key) {
+        Ok(index) => Ok(low + index),
+        Err(index) => Err(low + index),
+    }
+}
+
+/// Probe chunks at exponentially growing distances before `hint` until one
+/// holds an element less than the key, then search the bracketed range.
+///
+/// Every element from `hint` onwards must be greater than the key.
+unsafe fn gallop_backward<R, A, S>(source: &S, key: A, hint: usize) -> Result<usize, usize>
+where
+    R: SimdRegister,
+    A: Ord + Copy + SimdOps<R>,
+    S: SimdArrayOps<A> + ?Sized,
+{
+    if hint == 0 {
+        return Err(0);
+    }
+    let keys = A::set(key);
+    let mut low = 0;
+    let mut high = hint;
+    let mut chunk = (hint - 1) / A::ALIGNMENT;
+    let mut step = 1;
+    loop {
+        let pos = chunk * A::ALIGNMENT;
+        let less = A::cmp_gt(keys, source.load(pos)) & lanes_within::<R, A>(pos, &(0..hint));
+        if !less.is_empty() {
+            low = pos;
+            break;
+        }
+        high = std::cmp::min(high, pos + A::ALIGNMENT);
+        if chunk == 0 {
+            break;
+        }
+        chunk = chunk.saturating_sub(step);
+        step *= 2;
+    }
+    source.k_ary_search_range::<R>(key, low..high)
+}
+
+/// Gallop backwards from `hint` an element at a time, then binary search the
+/// bracketed range, for CPUs without the compares `gallop_backward` needs.
+///
+/// Every element from `hint` onwards must be greater than the key.
+pub(crate) fn gallop_backward_scalar<A>(source: &[A], key: A, hint: usize) -> Result<usize, usize>
+where
+    A: Ord,
+{
+    let (mut low, mut high) = (hint, hint);
+    let mut step = 1;
+    while low > 0 {
+        low = low.saturating_sub(step);
+        if source[low] < key {
+            break;
+        }
+        high = low + 1;
+        step *= 2;
+    }
+    match source[low..high].binary_search(&key) {
+        Ok(index) => Ok(low + index),
+        Err(index) => Err(low + index),
+    }
 }
@@ -0,0 +1,684 @@
+use std::arch::x86_64::__m128i;
+use std::fmt::{Debug, Error, Formatter};
+use std::ops::{Bound, RangeBounds};
+
+use bitmaps::Bitmap;
+
+use crate::{SimdOps, SimdRegister};
+
+type Child<V> = Box<Node<V>>;
+
+/// A node with up to 4 children, found by a linear scan.
+struct Node4<V> {
+    keys: [u8; 4],
+    children: [Option<Child<V>>; 4],
+    len: usize,
+}
+
+#[derive(Default)]
+#[repr(align(16))]
+struct Keys16([u8; 16]);
+
+impl Keys16 {
+    fn register(&self) -> __m128i {
+        // The alignment makes this a valid aligned load.
+        unsafe { *(self.0.as_ptr() as *const __m128i) }
+    }
+}
+
+/// A node with up to 16 children, found with a single SIMD compare.
+struct Node16<V> {
+    keys: Keys16,
+    children: [Option<Child<V>>; 16],
+    len: usize,
+}
+
+/// A node with up to 48 children, found through a 256 entry index.
+struct Node48<V> {
+    /// One plus the slot of each byte's child, or zero if it has none.
+    index: [u8; 256],
+    children: Vec<Option<Child<V>>>,
+    len: usize,
+}
+
+/// A node with a child slot for every byte.
+struct Node256<V> {
+    children: Vec<Option<Child<V>>>,
+    len: usize,
+}
+
+enum Children<V> {
+    Node4(Node4<V>),
+    Node16(Node16<V>),
+    Node48(Node48<V>),
+    Node256(Node256<V>),
+}
+
+impl<V> Node16<V> {
+    fn lanes(&self) -> Bitmap<<__m128i as SimdRegister>::MovemaskSize> {
+        if self.len == 16 {
+            !Bitmap::new()
+        } else {
+            Bitmap::mask(self.len)
+        }
+    }
+
+    fn find(&self, byte: u8) -> Option<usize> {
+        // SSE2 is part of the x86_64 baseline.
+        unsafe {
+            let keys = self.keys.register();
+            let eq =
+                <i8 as SimdOps<__m128i>>::cmp_eq(keys, <i8 as SimdOps<__m128i>>::set(byte as i8));
+            (eq & self.lanes()).first_index()
+        }
+    }
+
+    /// Find where `byte` belongs among the sorted keys.
+    fn position(&self, byte: u8) -> usize {
+        unsafe {
+            let keys = self.keys.register();
+            let less = <u8 as SimdOps<__m128i>>::cmp_gt(<u8 as SimdOps<__m128i>>::set(byte), keys);
+            (less & self.lanes()).len()
+        }
+    }
+}
+
+impl<V> Children<V> {
+    fn new() -> Self {
+        Children::Node4(Node4 {
+            keys: [0; 4],
+            children: Default::default(),
+            len: 0,
+        })
+    }
+
+    /// Build the smallest node which holds `entries`, given in byte order.
+    fn from_entries(entries: Vec<(u8, Child<V>)>) -> Self {
+        let len = entries.len();
+        match len {
+            0..=4 => {
+                let mut node = Node4 {
+                    keys: [0; 4],
+                    children: Default::default(),
+                    len,
+                };
+                for (index, (byte, child)) in entries.into_iter().enumerate() {
+                    node.keys[index] = byte;
+                    node.children[index] = Some(child);
+                }
+                Children::Node4(node)
+            }
+            5..=16 => {
+                let mut node = Node16 {
+                    keys: Keys16::default(),
+                    children: Default::default(),
+                    len,
+                };
+                for (index, (byte, child)) in entries.into_iter().enumerate() {
+                    node.keys.0[index] = byte;
+                    node.children[index] = Some(child);
+                }
+                Children::Node16(node)
+            }
+            17..=48 => {
+                let mut node = Node48 {
+                    index: [0; 256],
+                    children: Vec::with_capacity(48),
+                    len,
+                };
+                for (byte, child) in entries {
+                    node.children.push(Some(child));
+                    node.index[byte as usize] = node.children.len() as u8;
+                }
+                node.children.resize_with(48, || None);
+                Children::Node48(node)
+            }
+            _ => {
+                let mut node = Node256 {
+                    children: Vec::with_capacity(256),
+                    len,
+                };
+                node.children.resize_with(256, || None);
+                for (byte, child) in entries {
+                    node.children[byte as usize] = Some(child);
+                }
+                Children::Node256(node)
+            }
+        }
+    }
+
+    /// Remove every child, in byte order.
+    fn take_entries(&mut self) -> Vec<(u8, Child<V>)> {
+        let mut entries = Vec::with_capacity(self.len());
+        match self {
+            Children::Node4(node) => {
+                for index in 0..node.len {
+                    entries.push((node.keys[index], node.children[index].take().unwrap()));
+                }
+            }
+            Children::Node16(node) => {
+                for index in 0..node.len {
+                    entries.push((node.keys.0[index], node.children[index].take().unwrap()));
+                }
+            }
+            Children::Node48(node) => {
+                for byte in 0..256 {
+                    if node.index[byte] != 0 {
+                        let slot = node.index[byte] as usize - 1;
+                        entries.push((byte as u8, node.children[slot].take().unwrap()));
+                    }
+                }
+            }
+            Children::Node256(node) => {
+                for (byte, child) in node.children.iter_mut().enumerate() {
+                    if let Some(child) = child.take() {
+                        entries.push((byte as u8, child));
+                    }
+                }
+            }
+        }
+        *self = Children::new();
+        entries
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Children::Node4(node) => node.len,
+            Children::Node16(node) => node.len,
+            Children::Node48(node) => node.len,
+            Children::Node256(node) => node.len,
+        }
+    }
+
+    fn is_full(&self) -> bool {
+        match self {
+            Children::Node4(node) => node.len == 4,
+            Children::Node16(node) => node.len == 16,
+            Children::Node48(node) => node.len == 48,
+            Children::Node256(_) => false,
+        }
+    }
+
+    /// Test if the node has few enough children to move to a smaller type.
+    fn is_sparse(&self) -> bool {
+        match self {
+            Children::Node4(_) => false,
+            Children::Node16(node) => node.len <= 3,
+            Children::Node48(node) => node.len <= 12,
+            Children::Node256(node) => node.len <= 40,
+        }
+    }
+
+    fn get(&self, byte: u8) -> Option<&Child<V>> {
+        match self {
+            Children::Node4(node) => {
+                let index = node.keys[..node.len].iter().position(|key| *key == byte)?;
+                node.children[index].as_ref()
+            }
+            Children::Node16(node) => node.children[node.find(byte)?].as_ref(),
+            Children::Node48(node) => match node.index[byte as usize] {
+                0 => None,
+                slot => node.children[slot as usize - 1].as_ref(),
+            },
+            Children::Node256(node) => node.children[byte as usize].as_ref(),
+        }
+    }
+
+    fn get_mut(&mut self, byte: u8) -> Option<&mut Child<V>> {
+        match self {
+            Children::Node4(node) => {
+                let index = node.keys[..node.len].iter().position(|key| *key == byte)?;
+                node.children[index].as_mut()
+            }
+            Children::Node16(node) => {
+                let index = node.find(byte)?;
+                node.children[index].as_mut()
+            }
+            Children::Node48(node) => match node.index[byte as usize] {
+                0 => None,
+                slot => node.children[slot as usize - 1].as_mut(),
+            },
+            Children::Node256(node) => node.children[byte as usize].as_mut(),
+        }
+    }
+
+    /// Add a child for a byte which doesn't have one yet.
+    fn add(&mut self, byte: u8, child: Child<V>) {
+        if self.is_full() {
+            let mut entries = self.take_entries();
+            let index = entries.partition_point(|(key, _)| *key < byte);
+            entries.insert(index, (byte, child));
+            *self = Children::from_entries(entries);
+            return;
+        }
+        match self {
+            Children::Node4(node) => {
+                let index = node.keys[..node.len].partition_point(|key| *key < byte);
+                node.keys.copy_within(index..node.len, index + 1);
+                node.children[index..=node.len].rotate_right(1);
+                node.keys[index] = byte;
+                node.children[index] = Some(child);
+                node.len += 1;
+            }
+            Children::Node16(node) => {
+                let index = node.position(byte);
+                node.keys.0.copy_within(index..node.len, index + 1);
+                node.children[index..=node.len].rotate_right(1);
+                node.keys.0[index] = byte;
+                node.children[index] = Some(child);
+                node.len += 1;
+            }
+            Children::Node48(node) => {
+                let slot = node.children.iter().position(Option::is_none).unwrap();
+                node.children[slot] = Some(child);
+                node.index[byte as usize] = slot as u8 + 1;
+                node.len += 1;
+            }
+            Children::Node256(node) => {
+                node.children[byte as usize] = Some(child);
+                node.len += 1;
+            }
+        }
+    }
+
+    /// Remove the child for a byte, if it has one.
+    fn remove(&mut self, byte: u8) -> Option<Child<V>> {
+        let child = match self {
+            Children::Node4(node) => {
+                let index = node.keys[..node.len].iter().position(|key| *key == byte)?;
+                let child = node.children[index].take();
+                node.keys.copy_within(index + 1..node.len, index);
+                node.children[index..node.len].rotate_left(1);
+                node.len -= 1;
+                child
+            }
+            Children::Node16(node) => {
+                let index = node.find(byte)?;
+                let child = node.children[index].take();
+                node.keys.0.copy_within(index + 1..node.len, index);
+                node.children[index..node.len].rotate_left(1);
+                node.len -= 1;
+                child
+            }
+            Children::Node48(node) => {
+                let slot = std::mem::replace(&mut node.index[byte as usize], 0);
+                if slot == 0 {
+                    return None;
+                }
+                node.len -= 1;
+                node.children[slot as usize - 1].take()
+            }
+            Children::Node256(node) => {
+                let child = node.children[byte as usize].take()?;
+                node.len -= 1;
+                Some(child)
+            }
+        };
+        if self.is_sparse() {
+            let entries = self.take_entries();
+            *self = Children::from_entries(entries);
+        }
+        child
+    }
+
+    /// Find the first child at or after `cursor`, in byte order.
+    ///
+    /// Returns the cursor just past the child, along with its byte. A cursor
+    /// of zero starts from the first child.
+    fn next_child(&self, cursor: usize) -> Option<(usize, u8, &Node<V>)> {
+        match self {
+            Children::Node4(node) if cursor < node.len => Some((
+                cursor + 1,
+                node.keys[cursor],
+                node.children[cursor].as_ref().unwrap(),
+            )),
+            Children::Node16(node) if cursor < node.len => Some((
+                cursor + 1,
+                node.keys.0[cursor],
+                node.children[cursor].as_ref().unwrap(),
+            )),
+            Children::Node4(_) | Children::Node16(_) => None,
+            Children::Node48(node) => {
+                let byte = (cursor..256).find(|byte| node.index[*byte] != 0)?;
+                let slot = node.index[byte] as usize - 1;
+                Some((byte + 1, byte as u8, node.children[slot].as_ref().unwrap()))
+            }
+            Children::Node256(node) => {
+                let byte = (cursor..256).find(|byte| node.children[*byte].is_some())?;
+                Some((byte + 1, byte as u8, node.children[byte].as_ref().unwrap()))
+            }
+        }
+    }
+}
+
+/// A tree node. Every key below it starts with the path to the node,
+/// followed by `prefix`.
+struct Node<V> {
+    prefix: Vec<u8>,
+    value: Option<V>,
+    children: Children<V>,
+}
+
+impl<V> Node<V> {
+    fn new(prefix: &[u8], value: Option<V>) -> Self {
+        Node {
+            prefix: prefix.to_vec(),
+            value,
+            children: Children::new(),
+        }
+    }
+
+    fn get(&self, key: &[u8]) -> Option<&V> {
+        let rest = key.strip_prefix(self.prefix.as_slice())?;
+        match rest.split_first() {
+            None => self.value.as_ref(),
+            Some((byte, rest)) => self.children.get(*byte)?.get(rest),
+        }
+    }
+
+    fn get_mut(&mut self, key: &[u8]) -> Option<&mut V> {
+        let rest = key.strip_prefix(self.prefix.as_slice())?;
+        match rest.split_first() {
+            None => self.value.as_mut(),
+            Some((byte, rest)) => self.children.get_mut(*byte)?.get_mut(rest),
+        }
+    }
+
+    fn insert(&mut self, key: &[u8], value: V) -> Option<V> {
+        let common = self
+            .prefix
+            .iter()
+            .zip(key)
+            .take_while(|(left, right)| left == right)
+            .count();
+        if common < self.prefix.len() {
+            // Split the prefix, moving this node's contents down a level.
+            let edge = self.prefix[common];
+            let mut lower = Node::new(&self.prefix[common + 1..], self.value.take());
+            lower.children = std::mem::replace(&mut self.children, Children::new());
+            self.prefix.truncate(common);
+            self.children.add(edge, Box::new(lower));
+        }
+        match key[common..].split_first() {
+            None => self.value.replace(value),
+            Some((byte, rest)) => match self.children.get_mut(*byte) {
+                Some(child) => child.insert(rest, value),
+                None => {
+                    self.children
+                        .add(*byte, Box::new(Node::new(rest, Some(value))));
+                    None
+                }
+            },
+        }
+    }
+
+    fn remove(&mut self, key: &[u8]) -> Option<V> {
+        let rest = key.strip_prefix(self.prefix.as_slice())?;
+        let (byte, rest) = match rest.split_first() {
+            None => return self.value.take(),
+            Some((byte, rest)) => (*byte, rest),
+        };
+        let child = self.children.get_mut(byte)?;
+        let removed = child.remove(rest)?;
+        if child.value.is_none() {
+            match child.children.len() {
+                0 => {
+                    self.children.remove(byte);
+                }
+                1 => {
+                    // Merge the child with its only child.
+                    let (edge, grandchild) = child.children.take_entries().pop().unwrap();
+                    let Node {
+                        prefix,
+                        value,
+                        children,
+                    } = *grandchild;
+                    child.prefix.push(edge);
+                    child.prefix.extend(prefix);
+                    child.value = value;
+                    child.children = children;
+                }
+                _ => {}
+            }
+        }
+        Some(removed)
+    }
+}
+
+/// An adaptive radix tree mapping byte strings to values.
+///
+/// Inner nodes grow and shrink between four sizes, holding 4, 16, 48 or 256
+/// children, as described in ['The Adaptive Radix Tree: ARTful Indexing for
+/// Main-Memory Databases,'
+/// 2013](https://db.in.tum.de/~leis/papers/ART.pdf). A 16 child node finds a
+/// child with one `SimdOps::cmp_eq` over its key bytes. Common key prefixes
+/// are stored once, in the node where the keys diverge.
+///
+/// Entries are kept in byte-wise key order, the same order as a
+/// `BTreeMap<Vec<u8>, V>`.
+pub struct SimdArt<V> {
+    root: Node<V>,
+    len: usize,
+}
+
+impl<V> SimdArt<V> {
+    /// Construct an empty tree.
+    pub fn new() -> Self {
+        SimdArt {
+            root: Node::new(&[], None),
+            len: 0,
+        }
+    }
+
+    /// Get the number of entries in the tree.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Test if the tree is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the value for a key.
+    pub fn get<K>(&self, key: &K) -> Option<&V>
+    where
+        K: AsRef<[u8]> + ?Sized,
+    {
+        self.root.get(key.as_ref())
+    }
+
+    /// Get the value for a key mutably.
+    pub fn get_mut<K>(&mut self, key: &K) -> Option<&mut V>
+    where
+        K: AsRef<[u8]> + ?Sized,
+    {
+        self.root.get_mut(key.as_ref())
+    }
+
+    /// Test if the tree has a value for a key.
+    pub fn contains_key<K>(&self, key: &K) -> bool
+    where
+        K: AsRef<[u8]> + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Insert a value for a key.
+    ///
+    /// Returns the previous value for the key, if there was one.
+    pub fn insert<K>(&mut self, key: &K, value: V) -> Option<V>
+    where
+        K: AsRef<[u8]> + ?Sized,
+    {
+        let previous = self.root.insert(key.as_ref(), value);
+        if previous.is_none() {
+            self.len += 1;
+        }
+        previous
+    }
+
+    /// Remove the value for a key.
+    ///
+    /// Returns the removed value, if there was one.
+    pub fn remove<K>(&mut self, key: &K) -> Option<V>
+    where
+        K: AsRef<[u8]> + ?Sized,
+    {
+        let removed = self.root.remove(key.as_ref());
+        if removed.is_some() {
+            self.len -= 1;
+        }
+        removed
+    }
+
+    /// Iterate over the entries in key order.
+    pub fn iter(&self) -> impl Iterator<Item = (Vec<u8>, &V)> + '_ {
+        self.scan(Bound::Unbounded, Bound::Unbounded)
+    }
+
+    /// Iterate over the entries whose keys start with `prefix`, in key
+    /// order.
+    pub fn prefix_iter<K>(&self, prefix: &K) -> impl Iterator<Item = (Vec<u8>, &V)> + '_
+    where
+        K: AsRef<[u8]> + ?Sized,
+    {
+        let prefix = prefix.as_ref();
+        // The keys past the prefix start at its successor: the prefix with
+        // trailing 0xff bytes dropped and its last byte incremented.
+        let mut successor = prefix.to_vec();
+        while successor.last() == Some(&0xff) {
+            successor.pop();
+        }
+        let upper = match successor.last_mut() {
+            Some(byte) => {
+                *byte += 1;
+                Bound::Excluded(successor)
+            }
+            None => Bound::Unbounded,
+        };
+        self.scan(Bound::Included(prefix.to_vec()), upper)
+    }
+
+    /// Iterate over the entries whose keys lie within `range`, in key order.
+    pub fn range<'k, R>(&self, range: R) -> impl Iterator<Item = (Vec<u8>, &V)> + '_
+    where
+        R: RangeBounds<&'k [u8]>,
+    {
+        self.scan(
+            range.start_bound().map(|key| key.to_vec()),
+            range.end_bound().map(|key| key.to_vec()),
+        )
+    }
+
+    fn scan(&self, lower: Bound<Vec<u8>>, upper: Bound<Vec<u8>>) -> Scan<'_, V> {
+        Scan {
+            root: Some(&self.root),
+            stack: Vec::new(),
+            path: Vec::new(),
+            lower,
+            upper,
+        }
+    }
+}
+
+impl<V> Default for SimdArt<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> Debug for SimdArt<V>
+where
+    V: Debug,
+{
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+/// A lazy walk over the entries within a pair of bounds, in key order.
+struct Scan<'a, V> {
+    /// The root, until the first call to `next`.
+    root: Option<&'a Node<V>>,
+    /// Each node on the path, with the cursor of its next child and the
+    /// length of `path` before the node was entered.
+    stack: Vec<(&'a Node<V>, usize, usize)>,
+    /// The key up to the top node of the stack.
+    path: Vec<u8>,
+    lower: Bound<Vec<u8>>,
+    upper: Bound<Vec<u8>>,
+}
+
+impl<'a, V> Scan<'a, V> {
+    /// Enter a node whose edge byte, if any, is already on `path`, and
+    /// return its entry if it has one within the bounds.
+    ///
+    /// `start` is the length of `path` before the edge byte.
+    fn enter(&mut self, node: &'a Node<V>, start: usize) -> Option<(Vec<u8>, &'a V)> {
+        self.path.extend_from_slice(&node.prefix);
+        let path = self.path.as_slice();
+        // Every key below here starts with `path`, so is no less than it.
+        let past_upper = match &self.upper {
+            Bound::Included(upper) => path > upper.as_slice(),
+            Bound::Excluded(upper) => path >= upper.as_slice(),
+            Bound::Unbounded => false,
+        };
+        if past_upper {
+            // So is every key after it.
+            self.stack.clear();
+            return None;
+        }
+        // If the lower bound doesn't start with `path`, every key below here
+        // is on the same side of it as `path` is.
+        let before_lower = match &self.lower {
+            Bound::Included(lower) | Bound::Excluded(lower) => {
+                path < lower.as_slice() && !lower.starts_with(path)
+            }
+            Bound::Unbounded => false,
+        };
+        if before_lower {
+            self.path.truncate(start);
+            return None;
+        }
+        let above_lower = match &self.lower {
+            Bound::Included(lower) => path >= lower.as_slice(),
+            Bound::Excluded(lower) => path > lower.as_slice(),
+            Bound::Unbounded => true,
+        };
+        self.stack.push((node, 0, start));
+        match &node.value {
+            Some(value) if above_lower => Some((self.path.clone(), value)),
+            _ => None,
+        }
+    }
+}
+
+impl<'a, V> Iterator for Scan<'a, V> {
+    type Item = (Vec<u8>, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(root) = self.root.take() {
+            if let Some(entry) = self.enter(root, 0) {
+                return Some(entry);
+            }
+        }
+        while let Some((node, cursor, start)) = self.stack.last_mut() {
+            match node.children.next_child(*cursor) {
+                Some((next, byte, child)) => {
+                    *cursor = next;
+                    let start = self.path.len();
+                    self.path.push(byte);
+                    if let Some(entry) = self.enter(child, start) {
+                        return Some(entry);
+                    }
+                }
+                None => {
+                    self.path.truncate(*start);
+                    self.stack.pop();
+                }
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,520 @@
+use std::arch::x86_64 as arch;
+use std::fmt::{Debug, Error, Formatter};
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Range};
+
+use crate::{DefaultZero, SimdVec};
+
+const BLOCK_BITS: usize = 256;
+
+/// How many blocks `select` counts at a time before narrowing down.
+const SELECT_STRIDE: usize = 8;
+
+/// Count the set bits in a run of blocks, four bits at a time through a
+/// byte shuffle lookup table, as described in ['Faster Population Counts
+/// Using AVX2 Instructions,' Muła, Kurz and Lemire,
+/// 2016](https://arxiv.org/abs/1611.07612).
+#[target_feature(enable = "avx2")]
+unsafe fn popcount_avx2(blocks: &[arch::__m256i]) -> usize {
+    #[rustfmt::skip]
+    let lookup = arch::_mm256_setr_epi8(
+        0, 1, 1, 2, 1, 2, 2, 3, 1, 2, 2, 3, 2, 3, 3, 4,
+        0, 1, 1, 2, 1, 2, 2, 3, 1, 2, 2, 3, 2, 3, 3, 4,
+    );
+    let low_nibbles = arch::_mm256_set1_epi8(0x0f);
+    let zero = arch::_mm256_setzero_si256();
+    let mut total = zero;
+    for block in blocks {
+        let low = arch::_mm256_and_si256(*block, low_nibbles);
+        let high = arch::_mm256_and_si256(arch::_mm256_srli_epi16(*block, 4), low_nibbles);
+        let counts = arch::_mm256_add_epi8(
+            arch::_mm256_shuffle_epi8(lookup, low),
+            arch::_mm256_shuffle_epi8(lookup, high),
+        );
+        // Sum each 8 byte counts into a 64-bit lane.
+        total = arch::_mm256_add_epi64(total, arch::_mm256_sad_epu8(counts, zero));
+    }
+    let lanes: [u64; 4] = std::mem::transmute(total);
+    lanes.iter().sum::<u64>() as usize
+}
+
+/// Count the set bits in a run of blocks.
+pub(crate) fn popcount(blocks: &[arch::__m256i]) -> usize {
+    if is_x86_feature_detected!("avx2") {
+        unsafe { popcount_avx2(blocks) }
+    } else {
+        let words =
+            unsafe { std::slice::from_raw_parts(blocks.as_ptr() as *const u64, blocks.len() * 4) };
+        words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+}
+
+/// Find the index of the `n`th set bit of a word, counting from zero.
+fn select_in_word(mut word: u64, n: usize) -> usize {
+    for _ in 0..n {
+        word &= word - 1;
+    }
+    word.trailing_zeros() as usize
+}
+
+#[derive(Clone, Copy)]
+enum BitOp {
+    And,
+    Or,
+    Xor,
+    AndNot,
+}
+
+impl BitOp {
+    fn name(self) -> &'static str {
+        match self {
+            BitOp::And => "and",
+            BitOp::Or => "or",
+            BitOp::Xor => "xor",
+            BitOp::AndNot => "andnot",
+        }
+    }
+
+    fn scalar(self, left: u64, right: u64) -> u64 {
+        match self {
+            BitOp::And => left & right,
+            BitOp::Or => left | right,
+            BitOp::Xor => left ^ right,
+            BitOp::AndNot => left & !right,
+        }
+    }
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn apply_avx2(self, left: &mut [arch::__m256i], right: &[arch::__m256i]) {
+        for (left, right) in left.iter_mut().zip(right) {
+            *left = match self {
+                BitOp::And => arch::_mm256_and_si256(*left, *right),
+                BitOp::Or => arch::_mm256_or_si256(*left, *right),
+                BitOp::Xor => arch::_mm256_xor_si256(*left, *right),
+                BitOp::AndNot => arch::_mm256_andnot_si256(*right, *left),
+            };
+        }
+    }
+}
+
+/// A heap allocated SIMD aligned bit vector.
+///
+/// Bits are packed one per element into 32-byte blocks, in the same layout as
+/// a `SimdVec<u64>`. Any bits past the end of the vector are kept zeroed.
+///
+/// This is the selection vector produced by the `SimdArrayOps` comparisons
+/// and consumed by `compact`. Selections can be combined a block at a time
+/// with `and`, `or`, `xor` and `andnot`, and counted and indexed with
+/// `count_ones`, `rank` and `select`.
+#[derive(Clone)]
+pub struct SimdBitVec {
+    size: usize,
+    vec: Vec<arch::__m256i>,
+}
+
+impl SimdBitVec {
+    /// Construct an empty bit vector.
+    pub fn new() -> Self {
+        SimdBitVec {
+            size: 0,
+            vec: Vec::new(),
+        }
+    }
+
+    /// Construct a bit vector of `len` bits, all set to `false`.
+    pub fn zeroed(len: usize) -> Self {
+        SimdBitVec {
+            size: len,
+            vec: vec![arch::__m256i::default_zero(); len.div_ceil(BLOCK_BITS)],
+        }
+    }
+
+    /// Get the number of bits in the vector.
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    /// Test if the vector is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the bit at a given index.
+    ///
+    /// Returns `None` if the index is out of bounds.
+    pub fn get(&self, index: usize) -> Option<bool> {
+        if index >= self.size {
+            None
+        } else {
+            Some(self.words()[index / 64] & (1 << (index % 64)) != 0)
+        }
+    }
+
+    /// Set the bit at a given index.
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn set(&mut self, index: usize, value: bool) {
+        if index >= self.size {
+            panic!(
+                "SimdBitVec::set: index {} out of bounds for length {}",
+                index, self.size
+            )
+        }
+        let word = &mut self.words_mut()[index / 64];
+        if value {
+            *word |= 1 << (index % 64);
+        } else {
+            *word &= !(1 << (index % 64));
+        }
+    }
+
+    /// Push a bit to the end of the vector.
+    pub fn push(&mut self, value: bool) {
+        if self.size == self.vec.len() * BLOCK_BITS {
+            self.vec.push(arch::__m256i::default_zero());
+        }
+        self.size += 1;
+        self.set(self.size - 1, value);
+    }
+
+    /// Count the number of `true` bits in the vector.
+    pub fn count_ones(&self) -> usize {
+        popcount(&self.vec)
+    }
+
+    /// Count the number of `true` bits before `index`.
+    ///
+    /// Panics if `index` is greater than the length of the vector.
+    pub fn rank(&self, index: usize) -> usize {
+        if index > self.size {
+            panic!(
+                "SimdBitVec::rank: index {} out of bounds for length {}",
+                index, self.size
+            )
+        }
+        let block = index / BLOCK_BITS;
+        let words = self.words();
+        let word = index / 64;
+        let partial = match index % 64 {
+            0 => 0,
+            bits => (words[word] & ((1 << bits) - 1)).count_ones() as usize,
+        };
+        popcount(&self.vec[..block])
+            + words[block * 4..word]
+                .iter()
+                .map(|word| word.count_ones() as usize)
+                .sum::<usize>()
+            + partial
+    }
+
+    /// Find the index of the `n`th `true` bit, counting from zero.
+    ///
+    /// Returns `None` if there are no more than `n` `true` bits.
+    pub fn select(&self, n: usize) -> Option<usize> {
+        self.select_from(0, n, true)
+    }
+
+    /// Find the index of the `n`th bit equal to `value`, counting from zero
+    /// at the start of block `start`.
+    pub(crate) fn select_from(&self, start: usize, mut n: usize, value: bool) -> Option<usize> {
+        let count = |blocks: &[arch::__m256i]| {
+            let ones = popcount(blocks);
+            if value {
+                ones
+            } else {
+                blocks.len() * BLOCK_BITS - ones
+            }
+        };
+        let mut block = start;
+        // Skip whole runs of blocks by their popcounts, then narrow down to
+        // a block and a word.
+        for run in self.vec[start..].chunks(SELECT_STRIDE) {
+            let run_count = count(run);
+            if n < run_count {
+                break;
+            }
+            n -= run_count;
+            block += run.len();
+        }
+        if block == self.vec.len() {
+            return None;
+        }
+        loop {
+            let block_count = count(&self.vec[block..=block]);
+            if n < block_count {
+                break;
+            }
+            n -= block_count;
+            block += 1;
+        }
+        for (index, word) in self.words().iter().enumerate().skip(block * 4) {
+            let word = if value { *word } else { !*word };
+            let word_count = word.count_ones() as usize;
+            if n < word_count {
+                let found = index * 64 + select_in_word(word, n);
+                // Padding bits are zero, so only a search for zeros can
+                // land past the end.
+                return Some(found).filter(|found| *found < self.size);
+            }
+            n -= word_count;
+        }
+        None
+    }
+
+    /// Iterate over the bits in the vector.
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.size).map(move |index| self.words()[index / 64] & (1 << (index % 64)) != 0)
+    }
+
+    /// Iterate over the indices of the `true` bits, in ascending order.
+    pub fn iter_ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words().iter().enumerate().flat_map(|(index, word)| {
+            let mut word = *word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    None
+                } else {
+                    let bit = word.trailing_zeros() as usize;
+                    word &= word - 1;
+                    Some(index * 64 + bit)
+                }
+            })
+        })
+    }
+
+    /// Set every bit in a range.
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn set_range(&mut self, range: Range<usize>, value: bool) {
+        if range.start > range.end || range.end > self.size {
+            panic!(
+                "SimdBitVec::set_range: range {:?} out of bounds for length {}",
+                range, self.size
+            )
+        }
+        if range.is_empty() {
+            return;
+        }
+        let first = range.start / 64;
+        let last = (range.end - 1) / 64;
+        for (index, word) in self.words_mut()[first..=last].iter_mut().enumerate() {
+            let index = first + index;
+            let low = if index == first { range.start % 64 } else { 0 };
+            let high = if index == last {
+                (range.end - 1) % 64
+            } else {
+                63
+            };
+            let mask = (u64::MAX >> (63 - high)) & (u64::MAX << low);
+            if value {
+                *word |= mask;
+            } else {
+                *word &= !mask;
+            }
+        }
+    }
+
+    fn apply(&mut self, other: &SimdBitVec, op: BitOp) {
+        if self.size != other.size {
+            panic!(
+                "SimdBitVec::{}: length {} doesn't match length {}",
+                op.name(),
+                other.size,
+                self.size
+            )
+        }
+        if is_x86_feature_detected!("avx2") {
+            unsafe { op.apply_avx2(&mut self.vec, &other.vec) }
+        } else {
+            for (left, right) in self.words_mut().iter_mut().zip(other.words()) {
+                *left = op.scalar(*left, *right);
+            }
+        }
+    }
+
+    /// Keep only the bits which are also set in `other`.
+    ///
+    /// Panics if the vectors differ in length.
+    pub fn and(&mut self, other: &SimdBitVec) {
+        self.apply(other, BitOp::And)
+    }
+
+    /// Set the bits which are set in `other`.
+    ///
+    /// Panics if the vectors differ in length.
+    pub fn or(&mut self, other: &SimdBitVec) {
+        self.apply(other, BitOp::Or)
+    }
+
+    /// Flip the bits which are set in `other`.
+    ///
+    /// Panics if the vectors differ in length.
+    pub fn xor(&mut self, other: &SimdBitVec) {
+        self.apply(other, BitOp::Xor)
+    }
+
+    /// Clear the bits which are set in `other`.
+    ///
+    /// Panics if the vectors differ in length.
+    pub fn andnot(&mut self, other: &SimdBitVec) {
+        self.apply(other, BitOp::AndNot)
+    }
+
+    /// Construct a bit vector of `len` bits from 64-bit words, least
+    /// significant bit first.
+    ///
+    /// Panics if the words hold fewer than `len` bits.
+    pub fn from_words(words: SimdVec<u64>, len: usize) -> Self {
+        if len > words.len() * 64 {
+            panic!(
+                "SimdBitVec::from_words: length {} exceeds the {} bits in {} words",
+                len,
+                words.len() * 64,
+                words.len()
+            )
+        }
+        let mut vec = words.into_blocks();
+        vec.truncate(len.div_ceil(BLOCK_BITS));
+        let mut out = SimdBitVec { size: len, vec };
+        out.clear_padding();
+        out
+    }
+
+    /// Convert the bit vector into 64-bit words, least significant bit
+    /// first, without copying.
+    pub fn into_words(self) -> SimdVec<u64> {
+        let words = self.size.div_ceil(64);
+        SimdVec::from_blocks(self.vec, words)
+    }
+
+    /// Get the bits as a slice of 64-bit words, least significant bit first.
+    ///
+    /// The last word may include padding bits, which are always zero.
+    pub fn words(&self) -> &[u64] {
+        unsafe {
+            std::slice::from_raw_parts(self.vec.as_ptr() as *const u64, self.size.div_ceil(64))
+        }
+    }
+
+    pub(crate) fn words_mut(&mut self) -> &mut [u64] {
+        unsafe {
+            std::slice::from_raw_parts_mut(
+                self.vec.as_mut_ptr() as *mut u64,
+                self.size.div_ceil(64),
+            )
+        }
+    }
+
+    /// Zero out any bits past the end of the vector.
+    pub(crate) fn clear_padding(&mut self) {
+        let tail = self.size % 64;
+        if tail != 0 {
+            let last = self.size / 64;
+            self.words_mut()[last] &= (1 << tail) - 1;
+        }
+        // Whole words past the end of the last block's used words too.
+        let words = self.size.div_ceil(64);
+        let padded = unsafe {
+            std::slice::from_raw_parts_mut(self.vec.as_mut_ptr() as *mut u64, self.vec.len() * 4)
+        };
+        for word in &mut padded[words..] {
+            *word = 0;
+        }
+    }
+}
+
+impl Default for SimdBitVec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Extend<bool> for SimdBitVec {
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = bool>,
+    {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl FromIterator<bool> for SimdBitVec {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = bool>,
+    {
+        let mut out = Self::new();
+        out.extend(iter);
+        out
+    }
+}
+
+impl From<&[bool]> for SimdBitVec {
+    fn from(slice: &[bool]) -> Self {
+        let mut out = Self::zeroed(slice.len());
+        for (word, bits) in out.words_mut().iter_mut().zip(slice.chunks(64)) {
+            *word = bits
+                .iter()
+                .enumerate()
+                .fold(0, |word, (index, bit)| word | (u64::from(*bit) << index));
+        }
+        out
+    }
+}
+
+impl From<&SimdBitVec> for Vec<bool> {
+    fn from(bits: &SimdBitVec) -> Self {
+        bits.iter().collect()
+    }
+}
+
+macro_rules! bit_operator {
+    ($op:ident, $fn:ident, $assign:ident, $assign_fn:ident, $method:ident) => {
+        impl<'a> $assign<&'a SimdBitVec> for SimdBitVec {
+            fn $assign_fn(&mut self, other: &SimdBitVec) {
+                self.$method(other)
+            }
+        }
+
+        impl<'a, 'b> $op<&'b SimdBitVec> for &'a SimdBitVec {
+            type Output = SimdBitVec;
+
+            fn $fn(self, other: &SimdBitVec) -> SimdBitVec {
+                let mut out = self.clone();
+                out.$method(other);
+                out
+            }
+        }
+    };
+}
+
+bit_operator!(BitAnd, bitand, BitAndAssign, bitand_assign, and);
+bit_operator!(BitOr, bitor, BitOrAssign, bitor_assign, or);
+bit_operator!(BitXor, bitxor, BitXorAssign, bitxor_assign, xor);
+
+impl Debug for SimdBitVec {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl Hash for SimdBitVec {
+    fn hash<H>(&self, hasher: &mut H)
+    where
+        H: Hasher,
+    {
+        self.size.hash(hasher);
+        self.words().hash(hasher)
+    }
+}
+
+impl PartialEq for SimdBitVec {
+    fn eq(&self, other: &Self) -> bool {
+        self.size == other.size && self.words() == other.words()
+    }
+}
+
+impl Eq for SimdBitVec {}
@@ -0,0 +1,370 @@
+use std::arch::x86_64 as arch;
+use std::arch::x86_64::__m256i;
+use std::fmt::{Debug, Error, Formatter};
+use std::hash::{BuildHasher, Hash};
+
+use bitmaps::Bitmap;
+
+use crate::{DefaultZero, SeededState, SimdArrayOps, SimdBitVec, SimdOps, SimdVec};
+
+const WORDS_PER_BLOCK: usize = 8;
+const BYTES_PER_BLOCK: usize = 32;
+
+/// Odd multipliers picking one bit in each word of a block, as used by the
+/// Parquet and Impala split-block Bloom filters.
+#[repr(align(32))]
+struct Salts([u32; WORDS_PER_BLOCK]);
+
+const SALTS: Salts = Salts([
+    0x47b6_137b,
+    0x4497_4d91,
+    0x8824_ad5b,
+    0xa2b7_289d,
+    0x7054_95c7,
+    0x2df1_424b,
+    0x9efc_4947,
+    0x5c6b_fb31,
+]);
+
+/// How many lookups `contains_many` hashes and prefetches ahead of testing.
+const BATCH: usize = 16;
+
+fn words(block: &__m256i) -> &[u32; WORDS_PER_BLOCK] {
+    unsafe { &*(block as *const __m256i as *const [u32; WORDS_PER_BLOCK]) }
+}
+
+fn words_mut(block: &mut __m256i) -> &mut [u32; WORDS_PER_BLOCK] {
+    unsafe { &mut *(block as *mut __m256i as *mut [u32; WORDS_PER_BLOCK]) }
+}
+
+/// Build the 8 bit mask a key sets in a block, one bit per 32-bit word.
+#[target_feature(enable = "avx2")]
+unsafe fn block_mask(key: u32) -> __m256i {
+    let salts = arch::_mm256_load_si256(SALTS.0.as_ptr() as *const __m256i);
+    let products = arch::_mm256_mullo_epi32(arch::_mm256_set1_epi32(key as i32), salts);
+    let shifts = arch::_mm256_srli_epi32(products, 27);
+    arch::_mm256_sllv_epi32(arch::_mm256_set1_epi32(1), shifts)
+}
+
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn insert_avx2(block: &mut __m256i, key: u32) {
+    *block = arch::_mm256_or_si256(*block, block_mask(key));
+}
+
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn contains_avx2(block: &__m256i, key: u32) -> bool {
+    let mask = block_mask(key);
+    let present = arch::_mm256_and_si256(*block, mask);
+    <u32 as SimdOps<__m256i>>::cmp_eq(present, mask) == !Bitmap::new()
+}
+
+#[target_feature(enable = "avx2")]
+unsafe fn union_avx2(left: &mut [__m256i], right: &[__m256i]) {
+    for (left, right) in left.iter_mut().zip(right) {
+        *left = arch::_mm256_or_si256(*left, *right);
+    }
+}
+
+fn bit(key: u32, word: usize) -> u32 {
+    1 << (key.wrapping_mul(SALTS.0[word]) >> 27)
+}
+
+pub(crate) fn insert_scalar(block: &mut __m256i, key: u32) {
+    for (index, word) in words_mut(block).iter_mut().enumerate() {
+        *word |= bit(key, index);
+    }
+}
+
+pub(crate) fn contains_scalar(block: &__m256i, key: u32) -> bool {
+    words(block)
+        .iter()
+        .enumerate()
+        .all(|(index, word)| word & bit(key, index) != 0)
+}
+
+/// A split-block Bloom filter.
+///
+/// Each item hashes to one 32-byte block and sets one bit in each of the
+/// block's eight 32-bit words, so an insert is a single vector OR and a
+/// lookup a single vector AND and compare, touching one cache line. This
+/// costs a somewhat higher false positive rate than a classic Bloom filter
+/// of the same size. See ['Cache-, Hash- and Space-Efficient Bloom
+/// Filters,' Putze, Sanders and Singler,
+/// 2007](https://algo2.iti.kit.edu/documents/cacheefficientbloomfilters-jea.pdf).
+///
+/// Items are hashed with a `SeededState`, so filters built with the same
+/// seed and block count can be combined, and a serialised filter gives the
+/// same answers when read back.
+#[derive(Clone)]
+pub struct SimdBloomFilter {
+    blocks: Vec<__m256i>,
+    hasher: SeededState,
+}
+
+impl SimdBloomFilter {
+    /// Construct an empty filter of `blocks` 32-byte blocks.
+    ///
+    /// Panics if `blocks` is zero.
+    pub fn new(blocks: usize, seed: u64) -> Self {
+        assert!(
+            blocks > 0,
+            "SimdBloomFilter::new: a filter needs at least one block"
+        );
+        SimdBloomFilter {
+            blocks: vec![__m256i::default_zero(); blocks],
+            hasher: SeededState::new(seed),
+        }
+    }
+
+    /// Construct an empty filter large enough to hold `items` items with a
+    /// false positive rate of at most `rate`.
+    ///
+    /// Panics if `rate` isn't between 0 and 1.
+    pub fn with_rate(items: usize, rate: f64, seed: u64) -> Self {
+        assert!(
+            rate > 0.0 && rate < 1.0,
+            "SimdBloomFilter::with_rate: false positive rate {} not between 0 and 1",
+            rate
+        );
+        // Start from the size of a classic Bloom filter, which is a lower
+        // bound, and grow until the split-block estimate is good enough.
+        let bits = -(items as f64) * rate.ln() / (2f64.ln() * 2f64.ln());
+        let mut blocks = std::cmp::max(1, (bits / 256.0) as usize);
+        while Self::expected_false_positive_rate(items, blocks) > rate {
+            blocks += blocks / 16 + 1;
+        }
+        Self::new(blocks, seed)
+    }
+
+    /// Estimate the false positive rate of a filter of `blocks` blocks
+    /// holding `items` distinct items.
+    ///
+    /// The number of items landing in each block is close to a Poisson
+    /// distribution, so this sums the false positive rate of a block with
+    /// `k` items, weighted by the chance of it having `k`.
+    pub fn expected_false_positive_rate(items: usize, blocks: usize) -> f64 {
+        let lambda = items as f64 / blocks as f64;
+        let limit = (lambda + 12.0 * lambda.sqrt() + 32.0) as usize;
+        // Poisson probabilities are computed as logarithms to avoid
+        // underflowing `exp(-lambda)` for crowded filters.
+        let mut log_probability = -lambda;
+        let mut rate = 0.0;
+        for k in 0..=limit {
+            if k > 0 {
+                log_probability += lambda.ln() - (k as f64).ln();
+            }
+            let block_rate = (1.0 - (31.0f64 / 32.0).powi(k as i32)).powi(8);
+            rate += log_probability.exp() * block_rate;
+        }
+        rate.min(1.0)
+    }
+
+    /// Get the number of 32-byte blocks in the filter.
+    pub fn block_count(&self) -> usize {
+        self.blocks.len()
+    }
+
+    /// Get the seed used to hash items.
+    pub fn seed(&self) -> u64 {
+        self.hasher.seed()
+    }
+
+    /// Remove every item from the filter.
+    pub fn clear(&mut self) {
+        for block in &mut self.blocks {
+            *block = __m256i::default_zero();
+        }
+    }
+
+    /// Find an item's block index and the key picking its bits.
+    fn locate<T>(&self, item: &T) -> (usize, u32)
+    where
+        T: Hash + ?Sized,
+    {
+        let hash = self.hasher.hash_one(item);
+        // Scale the high half to the block count, avoiding a division.
+        let block = (u128::from(hash >> 32) * self.blocks.len() as u128) >> 32;
+        (block as usize, hash as u32)
+    }
+
+    /// Insert an item.
+    pub fn insert<T>(&mut self, item: &T)
+    where
+        T: Hash + ?Sized,
+    {
+        let (block, key) = self.locate(item);
+        let block = &mut self.blocks[block];
+        if is_x86_feature_detected!("avx2") {
+            unsafe { insert_avx2(block, key) }
+        } else {
+            insert_scalar(block, key)
+        }
+    }
+
+    /// Test if an item may have been inserted.
+    ///
+    /// Returns `false` only if the item was never inserted.
+    pub fn contains<T>(&self, item: &T) -> bool
+    where
+        T: Hash + ?Sized,
+    {
+        let (block, key) = self.locate(item);
+        let block = &self.blocks[block];
+        if is_x86_feature_detected!("avx2") {
+            unsafe { contains_avx2(block, key) }
+        } else {
+            contains_scalar(block, key)
+        }
+    }
+
+    /// Test a batch of items, returning a bit for each which is `true` if
+    /// the item may have been inserted.
+    ///
+    /// Items are hashed a few at a time and their blocks prefetched before
+    /// any are tested, so the memory accesses for a batch overlap.
+    pub fn contains_many<T>(&self, items: &[T]) -> SimdBitVec
+    where
+        T: Hash,
+    {
+        let avx2 = is_x86_feature_detected!("avx2");
+        let mut out = SimdBitVec::zeroed(items.len());
+        let mut located = [(0, 0); BATCH];
+        for (chunk_index, chunk) in items.chunks(BATCH).enumerate() {
+            for (slot, item) in located.iter_mut().zip(chunk) {
+                *slot = self.locate(item);
+                let block = &self.blocks[slot.0] as *const __m256i as *const i8;
+                // SSE is part of the x86_64 baseline.
+                unsafe { arch::_mm_prefetch::<{ arch::_MM_HINT_T0 }>(block) };
+            }
+            for (index, (block, key)) in located[..chunk.len()].iter().enumerate() {
+                let block = &self.blocks[*block];
+                let found = if avx2 {
+                    unsafe { contains_avx2(block, *key) }
+                } else {
+                    contains_scalar(block, *key)
+                };
+                out.set(chunk_index * BATCH + index, found);
+            }
+        }
+        out
+    }
+
+    /// Add every item in `other` to this filter.
+    ///
+    /// Panics if the filters differ in block count or seed.
+    pub fn union(&mut self, other: &SimdBloomFilter) {
+        assert!(
+            self.blocks.len() == other.blocks.len() && self.seed() == other.seed(),
+            "SimdBloomFilter::union: filters differ in block count or seed"
+        );
+        if is_x86_feature_detected!("avx2") {
+            unsafe { union_avx2(&mut self.blocks, &other.blocks) }
+        } else {
+            for (left, right) in self.blocks.iter_mut().zip(&other.blocks) {
+                for (left, right) in words_mut(left).iter_mut().zip(words(right)) {
+                    *left |= right;
+                }
+            }
+        }
+    }
+
+    /// Estimate the false positive rate from how full the filter is.
+    ///
+    /// An item not in the filter is a false positive if all 8 of its bits
+    /// happen to be set, so for each block this is the product of the
+    /// fraction of bits set in each word, averaged over the blocks.
+    pub fn false_positive_rate(&self) -> f64 {
+        let total: f64 = self
+            .blocks
+            .iter()
+            .map(|block| {
+                words(block)
+                    .iter()
+                    .map(|word| f64::from(word.count_ones()) / 32.0)
+                    .product::<f64>()
+            })
+            .sum();
+        total / self.blocks.len() as f64
+    }
+
+    /// Get the filter's bits as 32-bit words, 8 to a block.
+    pub fn as_words(&self) -> &[u32] {
+        unsafe {
+            std::slice::from_raw_parts(
+                self.blocks.as_ptr() as *const u32,
+                self.blocks.len() * WORDS_PER_BLOCK,
+            )
+        }
+    }
+
+    /// Copy the filter's block vector into a `SimdVec`.
+    pub fn to_vec(&self) -> SimdVec<u32> {
+        SimdVec::from_blocks(self.blocks.clone(), self.blocks.len() * WORDS_PER_BLOCK)
+    }
+
+    /// Construct a filter from a block vector made by `to_vec`, and the seed
+    /// it was built with.
+    ///
+    /// Returns `None` if the vector doesn't hold a whole, non-zero number of
+    /// blocks.
+    pub fn from_vec(vec: &SimdVec<u32>, seed: u64) -> Option<Self> {
+        if vec.is_empty() || !vec.len().is_multiple_of(WORDS_PER_BLOCK) {
+            return None;
+        }
+        Some(SimdBloomFilter {
+            blocks: vec.data_m256().to_vec(),
+            hasher: SeededState::new(seed),
+        })
+    }
+
+    /// Serialise the filter's blocks as little-endian 32-bit words.
+    ///
+    /// The seed isn't included, and has to be stored alongside.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.as_words()
+            .iter()
+            .flat_map(|word| word.to_le_bytes())
+            .collect()
+    }
+
+    /// Construct a filter from bytes made by `to_bytes`, and the seed it was
+    /// built with.
+    ///
+    /// Returns `None` if the bytes don't hold a whole, non-zero number of
+    /// blocks.
+    pub fn from_bytes(bytes: &[u8], seed: u64) -> Option<Self> {
+        if bytes.is_empty() || !bytes.len().is_multiple_of(BYTES_PER_BLOCK) {
+            return None;
+        }
+        let mut filter = Self::new(bytes.len() / BYTES_PER_BLOCK, seed);
+        for (block, bytes) in filter
+            .blocks
+            .iter_mut()
+            .zip(bytes.chunks_exact(BYTES_PER_BLOCK))
+        {
+            for (word, bytes) in words_mut(block).iter_mut().zip(bytes.chunks_exact(4)) {
+                let mut buffer = [0; 4];
+                buffer.copy_from_slice(bytes);
+                *word = u32::from_le_bytes(buffer);
+            }
+        }
+        Some(filter)
+    }
+}
+
+impl Debug for SimdBloomFilter {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        f.debug_struct("SimdBloomFilter")
+            .field("blocks", &self.blocks.len())
+            .field("seed", &self.seed())
+            .finish()
+    }
+}
+
+impl PartialEq for SimdBloomFilter {
+    fn eq(&self, other: &Self) -> bool {
+        self.seed() == other.seed() && self.as_words() == other.as_words()
+    }
+}
+
+impl Eq for SimdBloomFilter {}
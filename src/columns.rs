@@ -0,0 +1,203 @@
+use std::arch::x86_64::{__m128i, __m256i};
+use std::fmt::{Debug, Error, Formatter};
+use std::iter::FromIterator;
+
+use crate::{DefaultZero, SimdArrayOps, SimdOps, SimdVec};
+
+/// A vector of records stored as a structure of arrays: a SIMD aligned
+/// column of keys and a plain column of values.
+///
+/// Keeping the keys in their own `SimdVec` means they can be searched with
+/// the full set of `SimdArrayOps` without keeping a second copy of them
+/// alongside the records. An index into the key column is also an index into
+/// the value column.
+#[derive(Clone)]
+pub struct SimdColumns<K, V> {
+    keys: SimdVec<K>,
+    values: Vec<V>,
+}
+
+impl<K, V> SimdColumns<K, V>
+where
+    K: Copy + DefaultZero,
+{
+    /// Construct an empty container.
+    pub fn new() -> Self {
+        SimdColumns {
+            keys: SimdVec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    /// Construct an empty container with space for at least `capacity`
+    /// records.
+    pub fn with_capacity(capacity: usize) -> Self {
+        SimdColumns {
+            keys: SimdVec::with_capacity(capacity),
+            values: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Get the number of records.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Test if the container is empty.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Push a record to the end of the container.
+    pub fn push(&mut self, key: K, value: V) {
+        self.keys.push(key);
+        self.values.push(value);
+    }
+
+    /// Pop a record off the end of the container.
+    ///
+    /// Returns `None` if the container was empty.
+    pub fn pop(&mut self) -> Option<(K, V)> {
+        let value = self.values.pop()?;
+        Some((self.keys.pop().unwrap(), value))
+    }
+
+    /// Get the key column.
+    pub fn keys(&self) -> &SimdVec<K> {
+        &self.keys
+    }
+
+    /// Get the value column.
+    pub fn values(&self) -> &[V] {
+        &self.values
+    }
+
+    /// Get the value column mutably.
+    ///
+    /// Keys can't be changed in place, as that could break the ordering
+    /// searches rely on.
+    pub fn values_mut(&mut self) -> &mut [V] {
+        &mut self.values
+    }
+
+    /// Get the record at a given index.
+    ///
+    /// Returns `None` if the index is out of bounds.
+    pub fn get(&self, index: usize) -> Option<(K, &V)> {
+        Some((*self.keys.get(index)?, &self.values[index]))
+    }
+
+    /// Iterate over the records.
+    pub fn iter(&self) -> impl Iterator<Item = (K, &V)> + '_ {
+        self.keys.iter().copied().zip(self.values.iter())
+    }
+
+    /// Sort the records by key.
+    ///
+    /// The sort is stable, so records with equal keys keep their order.
+    pub fn sort_by_key(&mut self)
+    where
+        K: Ord,
+    {
+        let mut order: Vec<usize> = (0..self.len()).collect();
+        order.sort_by_key(|index| self.keys[*index]);
+        let mut values: Vec<Option<V>> = self.values.drain(..).map(Some).collect();
+        let mut keys = SimdVec::with_capacity(order.len());
+        for index in order {
+            keys.push(self.keys[index]);
+            self.values.push(values[index].take().unwrap());
+        }
+        self.keys = keys;
+    }
+}
+
+impl<K, V> SimdColumns<K, V>
+where
+    K: Ord + Copy + DefaultZero + SimdOps<__m256i> + SimdOps<__m128i>,
+{
+    /// Search for a key in a container sorted by key.
+    ///
+    /// This is `SimdArrayOps::search` on the key column.
+    pub fn search(&self, key: K) -> Result<usize, usize> {
+        self.keys.search(key)
+    }
+
+    /// Look up the value for a key in a container sorted by key.
+    ///
+    /// If the key occurs more than once, any of its values may be returned.
+    pub fn get_by_key(&self, key: K) -> Option<&V> {
+        self.search(key).ok().map(|index| &self.values[index])
+    }
+
+    /// Look up the value for a key mutably, in a container sorted by key.
+    ///
+    /// If the key occurs more than once, any of its values may be returned.
+    pub fn get_by_key_mut(&mut self, key: K) -> Option<&mut V> {
+        let index = self.search(key).ok()?;
+        Some(&mut self.values[index])
+    }
+}
+
+impl<K, V> Default for SimdColumns<K, V>
+where
+    K: Copy + DefaultZero,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> Extend<(K, V)> for SimdColumns<K, V>
+where
+    K: Copy + DefaultZero,
+{
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        for (key, value) in iter {
+            self.push(key, value);
+        }
+    }
+}
+
+impl<K, V> FromIterator<(K, V)> for SimdColumns<K, V>
+where
+    K: Copy + DefaultZero,
+{
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let mut out = Self::new();
+        out.extend(iter);
+        out
+    }
+}
+
+impl<K, V> Debug for SimdColumns<K, V>
+where
+    K: Copy + DefaultZero + Debug,
+    V: Debug,
+{
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V> PartialEq for SimdColumns<K, V>
+where
+    K: PartialEq,
+    V: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.keys == other.keys && self.values == other.values
+    }
+}
+
+impl<K, V> Eq for SimdColumns<K, V>
+where
+    K: Eq,
+    V: Eq,
+{
+}
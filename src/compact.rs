@@ -0,0 +1,288 @@
+use std::arch::x86_64::{self as arch, __m256i};
+use std::mem::size_of;
+use std::ops::Range;
+
+use crate::kernels::pack_mask;
+use crate::{DefaultZero, SimdArrayOps, SimdBitVec, SimdOps, SimdRegister, SimdVec};
+
+// Left-packing shuffle tables, indexed by a lane selection mask.
+//
+// 32 and 64 bit lanes are packed with a cross-lane dword permute, whose eight
+// 3-bit indices are stored one per nibble. 8 and 16 bit lanes are packed with
+// a byte shuffle over 8 lanes at a time.
+
+const fn dword_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut mask = 0;
+    while mask < 256 {
+        let mut entry = 0;
+        let mut slot = 0;
+        let mut lane = 0;
+        while lane < 8 {
+            if mask & (1 << lane) != 0 {
+                entry |= (lane as u32) << (slot * 4);
+                slot += 1;
+            }
+            lane += 1;
+        }
+        table[mask] = entry;
+        mask += 1;
+    }
+    table
+}
+
+const fn qword_table() -> [u32; 16] {
+    let mut table = [0; 16];
+    let mut mask = 0;
+    while mask < 16 {
+        let mut entry = 0;
+        let mut slot = 0;
+        let mut lane = 0;
+        while lane < 4 {
+            if mask & (1 << lane) != 0 {
+                entry |=
+                    ((lane as u32 * 2) << (slot * 4)) | ((lane as u32 * 2 + 1) << (slot * 4 + 4));
+                slot += 2;
+            }
+            lane += 1;
+        }
+        table[mask] = entry;
+        mask += 1;
+    }
+    table
+}
+
+const fn word_table() -> [[u8; 16]; 256] {
+    let mut table = [[0x80; 16]; 256];
+    let mut mask = 0;
+    while mask < 256 {
+        let mut slot = 0;
+        let mut lane = 0;
+        while lane < 8 {
+            if mask & (1 << lane) != 0 {
+                table[mask][slot] = lane as u8 * 2;
+                table[mask][slot + 1] = lane as u8 * 2 + 1;
+                slot += 2;
+            }
+            lane += 1;
+        }
+        mask += 1;
+    }
+    table
+}
+
+const fn byte_table() -> [u64; 256] {
+    let mut table = [0; 256];
+    let mut mask = 0;
+    while mask < 256 {
+        let mut entry = u64::MAX;
+        let mut slot = 0;
+        let mut lane = 0;
+        while lane < 8 {
+            if mask & (1 << lane) != 0 {
+                entry &= !(0xff << (slot * 8));
+                entry |= (lane as u64) << (slot * 8);
+                slot += 1;
+            }
+            lane += 1;
+        }
+        table[mask] = entry;
+        mask += 1;
+    }
+    table
+}
+
+static DWORD_TABLE: [u32; 256] = dword_table();
+static QWORD_TABLE: [u32; 16] = qword_table();
+static WORD_TABLE: [[u8; 16]; 256] = word_table();
+static BYTE_TABLE: [u64; 256] = byte_table();
+
+/// A predicate which can be evaluated a whole register at a time.
+pub(crate) enum Filter<A> {
+    Eq(A),
+    Gt(A),
+    Range(Range<A>),
+}
+
+impl<A> Filter<A>
+where
+    A: Ord + Copy,
+{
+    fn matches(&self, value: A) -> bool {
+        match self {
+            Filter::Eq(key) => value == *key,
+            Filter::Gt(key) => value > *key,
+            Filter::Range(range) => range.start <= value && value < range.end,
+        }
+    }
+}
+
+/// Write the lanes of `block` selected by `mask`, one bit per lane, to `out`
+/// in order, and return the number of lanes written.
+///
+/// Up to 32 bytes are written to `out` regardless of how many lanes were
+/// selected.
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn compress_block<A>(block: __m256i, mask: u32, out: *mut u8) -> usize {
+    match size_of::<A>() {
+        4 | 8 => {
+            let entry = if size_of::<A>() == 4 {
+                DWORD_TABLE[mask as usize]
+            } else {
+                QWORD_TABLE[mask as usize]
+            };
+            let indices = arch::_mm256_and_si256(
+                arch::_mm256_srlv_epi32(
+                    arch::_mm256_set1_epi32(entry as i32),
+                    arch::_mm256_setr_epi32(0, 4, 8, 12, 16, 20, 24, 28),
+                ),
+                arch::_mm256_set1_epi32(7),
+            );
+            let packed = arch::_mm256_permutevar8x32_epi32(block, indices);
+            arch::_mm256_storeu_si256(out as *mut __m256i, packed);
+            mask.count_ones() as usize
+        }
+        2 => {
+            let halves = [
+                arch::_mm256_castsi256_si128(block),
+                arch::_mm256_extracti128_si256(block, 1),
+            ];
+            let mut written = 0;
+            for (index, half) in halves.iter().enumerate() {
+                let lanes = ((mask >> (index * 8)) & 0xff) as usize;
+                let shuffle = arch::_mm_loadu_si128(WORD_TABLE[lanes].as_ptr() as *const _);
+                let packed = arch::_mm_shuffle_epi8(*half, shuffle);
+                arch::_mm_storeu_si128(out.add(written * 2) as *mut _, packed);
+                written += lanes.count_ones() as usize;
+            }
+            written
+        }
+        1 => {
+            let mut bytes = [0u8; 32];
+            arch::_mm256_storeu_si256(bytes.as_mut_ptr() as *mut __m256i, block);
+            let mut written = 0;
+            for group in 0..4 {
+                let lanes = ((mask >> (group * 8)) & 0xff) as usize;
+                let source = arch::_mm_loadl_epi64(bytes.as_ptr().add(group * 8) as *const _);
+                let shuffle = arch::_mm_cvtsi64_si128(BYTE_TABLE[lanes] as i64);
+                let packed = arch::_mm_shuffle_epi8(source, shuffle);
+                arch::_mm_storel_epi64(out.add(written) as *mut _, packed);
+                written += lanes.count_ones() as usize;
+            }
+            written
+        }
+        size => {
+            let source = &block as *const __m256i as *const u8;
+            let mut written = 0;
+            for lane in 0..size_of::<__m256i>() / size {
+                if mask & (1 << lane) != 0 {
+                    std::ptr::copy_nonoverlapping(
+                        source.add(lane * size),
+                        out.add(written * size),
+                        size,
+                    );
+                    written += 1;
+                }
+            }
+            written
+        }
+    }
+}
+
+/// Left-pack the elements of `source` selected by `select`, which maps each
+/// block index and block to a lane mask, into a new vector.
+#[target_feature(enable = "avx2")]
+unsafe fn compact_blocks<A, S, F>(source: &S, mut select: F) -> SimdVec<A>
+where
+    A: Ord + Copy + DefaultZero,
+    S: SimdArrayOps<A> + ?Sized,
+    F: FnMut(usize, __m256i) -> u32,
+{
+    let lanes = size_of::<__m256i>() / size_of::<A>();
+    let len = source.len();
+    let blocks = len.div_ceil(lanes);
+    // One block of slack absorbs the full width stores past the output.
+    let mut out = vec![__m256i::default_zero(); blocks + 1];
+    let target = out.as_mut_ptr() as *mut u8;
+    let mut written = 0;
+    for (index, block) in source.data_m256()[..blocks].iter().enumerate() {
+        let block = block.load();
+        let mut mask = select(index, block);
+        let remaining = len - index * lanes;
+        if remaining < lanes {
+            mask &= ((1u64 << remaining) - 1) as u32;
+        }
+        written += compress_block::<A>(block, mask, target.add(written * size_of::<A>()));
+    }
+    out.truncate(written.div_ceil(lanes));
+    SimdVec::from_blocks(out, written)
+}
+
+/// Collect the elements of `source` whose bits are set in `selection`.
+pub(crate) fn compact<A, S>(source: &S, selection: &SimdBitVec) -> SimdVec<A>
+where
+    A: Ord + Copy + DefaultZero,
+    S: SimdArrayOps<A> + ?Sized,
+{
+    if selection.len() != source.len() {
+        panic!(
+            "SimdArrayOps::compact: selection has length {} but array has length {}",
+            selection.len(),
+            source.len()
+        )
+    }
+    if is_x86_feature_detected!("avx2") {
+        let lanes = size_of::<__m256i>() / size_of::<A>();
+        let words = selection.words();
+        unsafe {
+            compact_blocks(source, |index, _| {
+                let offset = index * lanes;
+                let bits = words[offset / 64] >> (offset % 64);
+                (bits & ((1u64 << lanes) - 1)) as u32
+            })
+        }
+    } else {
+        let mut out = SimdVec::new();
+        out.extend(
+            source
+                .iter()
+                .zip(selection.iter())
+                .filter(|(_, selected)| *selected)
+                .map(|(value, _)| *value),
+        );
+        out
+    }
+}
+
+/// Collect the elements of `source` which match `filter`.
+pub(crate) fn filter<A, S>(source: &S, filter: Filter<A>) -> SimdVec<A>
+where
+    A: Ord + Copy + DefaultZero + SimdOps<__m256i>,
+    S: SimdArrayOps<A> + ?Sized,
+{
+    if !is_x86_feature_detected!("avx2") {
+        let mut out = SimdVec::new();
+        out.extend(
+            source
+                .iter()
+                .copied()
+                .filter(|value| filter.matches(*value)),
+        );
+        return out;
+    }
+    unsafe {
+        let (first, second) = match &filter {
+            Filter::Eq(key) | Filter::Gt(key) => (A::set(*key), A::set(*key)),
+            Filter::Range(range) => (A::set(range.start), A::set(range.end)),
+        };
+        compact_blocks(source, |_, block| {
+            let mask = match filter {
+                Filter::Eq(_) => A::cmp_eq(block, first),
+                Filter::Gt(_) => A::cmp_gt(block, first),
+                Filter::Range(_) => A::cmp_gt(second, block) & !A::cmp_gt(first, block),
+            };
+            pack_mask(__m256i::mask_value(mask), A::BITS_PER_CMP)
+        })
+    }
+}
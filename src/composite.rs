@@ -0,0 +1,195 @@
+use std::arch::x86_64::{__m128i, __m256i};
+use std::fmt::{Debug, Error, Formatter};
+use std::iter::FromIterator;
+use std::ops::Range;
+
+use crate::array_ops::rank_in;
+use crate::{DefaultZero, SimdOps, SimdVec};
+
+/// A vector of two-part keys, stored one column per part.
+///
+/// Each column is its own `SimdVec`. When the keys are sorted
+/// lexicographically, a lookup searches the first column for the range of
+/// keys sharing the first part, then searches the second column within that
+/// range, using SIMD comparisons at each step.
+#[derive(Clone)]
+pub struct SimdCompositeVec<A, B> {
+    first: SimdVec<A>,
+    second: SimdVec<B>,
+}
+
+impl<A, B> SimdCompositeVec<A, B>
+where
+    A: Copy + DefaultZero,
+    B: Copy + DefaultZero,
+{
+    /// Construct an empty vector.
+    pub fn new() -> Self {
+        SimdCompositeVec {
+            first: SimdVec::new(),
+            second: SimdVec::new(),
+        }
+    }
+
+    /// Construct an empty vector with space for at least `capacity` keys.
+    pub fn with_capacity(capacity: usize) -> Self {
+        SimdCompositeVec {
+            first: SimdVec::with_capacity(capacity),
+            second: SimdVec::with_capacity(capacity),
+        }
+    }
+
+    /// Get the number of keys in the vector.
+    pub fn len(&self) -> usize {
+        self.first.len()
+    }
+
+    /// Test if the vector is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Push a key to the end of the vector.
+    pub fn push(&mut self, key: (A, B)) {
+        self.first.push(key.0);
+        self.second.push(key.1);
+    }
+
+    /// Pop a key off the end of the vector.
+    ///
+    /// Returns `None` if the vector was empty.
+    pub fn pop(&mut self) -> Option<(A, B)> {
+        Some((self.first.pop()?, self.second.pop().unwrap()))
+    }
+
+    /// Get the key at a given index.
+    ///
+    /// Returns `None` if the index is out of bounds.
+    pub fn get(&self, index: usize) -> Option<(A, B)> {
+        Some((*self.first.get(index)?, self.second[index]))
+    }
+
+    /// Get the column of first parts.
+    pub fn first(&self) -> &SimdVec<A> {
+        &self.first
+    }
+
+    /// Get the column of second parts.
+    pub fn second(&self) -> &SimdVec<B> {
+        &self.second
+    }
+
+    /// Iterate over the keys.
+    pub fn iter(&self) -> impl Iterator<Item = (A, B)> + '_ {
+        self.first.iter().copied().zip(self.second.iter().copied())
+    }
+}
+
+impl<A, B> SimdCompositeVec<A, B>
+where
+    A: Ord + Copy + DefaultZero + SimdOps<__m256i> + SimdOps<__m128i>,
+    B: Ord + Copy + DefaultZero + SimdOps<__m256i> + SimdOps<__m128i>,
+{
+    /// Find the range of keys equal to `key` in a lexicographically sorted
+    /// vector.
+    ///
+    /// If there are none, the range is empty and starts where `key` would be
+    /// inserted.
+    pub fn equal_range(&self, key: &(A, B)) -> Range<usize> {
+        let first = self.first_range(key.0);
+        rank_in(&self.second, key.1, false, first.clone())
+            ..rank_in(&self.second, key.1, true, first)
+    }
+
+    /// Find the index of the first key not less than `key` in a
+    /// lexicographically sorted vector.
+    pub fn lower_bound(&self, key: &(A, B)) -> usize {
+        rank_in(&self.second, key.1, false, self.first_range(key.0))
+    }
+
+    /// Search for a key in a lexicographically sorted vector.
+    ///
+    /// Returns the index of the first occurrence of the key, or the index
+    /// where it would be inserted.
+    pub fn search(&self, key: &(A, B)) -> Result<usize, usize> {
+        let range = self.equal_range(key);
+        if range.is_empty() {
+            Err(range.start)
+        } else {
+            Ok(range.start)
+        }
+    }
+
+    /// Find the range of keys whose first part equals `first`.
+    fn first_range(&self, first: A) -> Range<usize> {
+        let all = 0..self.len();
+        rank_in(&self.first, first, false, all.clone())..rank_in(&self.first, first, true, all)
+    }
+}
+
+impl<A, B> Default for SimdCompositeVec<A, B>
+where
+    A: Copy + DefaultZero,
+    B: Copy + DefaultZero,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A, B> Extend<(A, B)> for SimdCompositeVec<A, B>
+where
+    A: Copy + DefaultZero,
+    B: Copy + DefaultZero,
+{
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = (A, B)>,
+    {
+        for key in iter {
+            self.push(key);
+        }
+    }
+}
+
+impl<A, B> FromIterator<(A, B)> for SimdCompositeVec<A, B>
+where
+    A: Copy + DefaultZero,
+    B: Copy + DefaultZero,
+{
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (A, B)>,
+    {
+        let mut out = Self::new();
+        out.extend(iter);
+        out
+    }
+}
+
+impl<A, B> Debug for SimdCompositeVec<A, B>
+where
+    A: Copy + DefaultZero + Debug,
+    B: Copy + DefaultZero + Debug,
+{
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<A, B> PartialEq for SimdCompositeVec<A, B>
+where
+    A: PartialEq,
+    B: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.first == other.first && self.second == other.second
+    }
+}
+
+impl<A, B> Eq for SimdCompositeVec<A, B>
+where
+    A: Eq,
+    B: Eq,
+{
+}
@@ -0,0 +1,228 @@
+use std::arch::x86_64::__m128i;
+use std::fmt::{Debug, Error, Formatter};
+use std::hash::{BuildHasher, Hash};
+
+use crate::{SeededState, SimdOps};
+
+const BUCKET_SIZE: usize = 8;
+
+/// How many fingerprints an insert moves before giving up.
+const MAX_KICKS: usize = 500;
+
+/// Eight 16-bit fingerprints, where zero marks a free slot.
+#[derive(Clone, Copy, Default)]
+#[repr(align(16))]
+struct Bucket([u16; BUCKET_SIZE]);
+
+impl Bucket {
+    /// Find the slot holding `fingerprint`.
+    fn find(&self, fingerprint: u16) -> Option<usize> {
+        // SSE2 is part of the x86_64 baseline.
+        unsafe {
+            // The alignment makes this a valid aligned load.
+            let slots = *(self.0.as_ptr() as *const __m128i);
+            let eq = <i16 as SimdOps<__m128i>>::cmp_eq(
+                slots,
+                <i16 as SimdOps<__m128i>>::set(fingerprint as i16),
+            );
+            eq.first_index()
+                .map(|index| index / <i16 as SimdOps<__m128i>>::BITS_PER_CMP)
+        }
+    }
+}
+
+/// A cuckoo filter: approximate set membership which supports removal.
+///
+/// Each item is reduced to a 16-bit fingerprint stored in one of two
+/// buckets of eight, where either bucket can be found from the other and the
+/// fingerprint alone. A lookup compares the fingerprint against a whole
+/// bucket with one `SimdOps::cmp_eq` on `i16` lanes, so tests at most two
+/// 16-byte registers. When both buckets are full, an insert evicts a
+/// fingerprint to its other bucket, and so on, up to a limit. See ['Cuckoo
+/// Filter: Practically Better Than Bloom,' Fan, Andersen, Kaminsky and
+/// Mitzenmacher, 2014](https://www.cs.cmu.edu/~dga/papers/cuckoo-conext2014.pdf).
+///
+/// Items are hashed with a `SeededState`, and evictions are chosen by a
+/// generator seeded from the same seed, so a filter's contents and answers
+/// are reproducible for a given seed and sequence of operations.
+#[derive(Clone)]
+pub struct SimdCuckooFilter {
+    buckets: Vec<Bucket>,
+    len: usize,
+    hasher: SeededState,
+    rng: u64,
+}
+
+impl SimdCuckooFilter {
+    /// Construct an empty filter with room for about `capacity` items.
+    ///
+    /// The bucket count is rounded up to a power of two, and inserts can
+    /// begin to fail once the filter is around 95% full.
+    pub fn new(capacity: usize, seed: u64) -> Self {
+        let buckets = std::cmp::max(2, capacity.div_ceil(BUCKET_SIZE).next_power_of_two());
+        SimdCuckooFilter {
+            buckets: vec![Bucket::default(); buckets],
+            len: 0,
+            hasher: SeededState::new(seed),
+            // Xorshift needs a non-zero state.
+            rng: seed | 1,
+        }
+    }
+
+    /// Get the number of items in the filter.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Test if the filter is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the number of fingerprint slots in the filter.
+    pub fn capacity(&self) -> usize {
+        self.buckets.len() * BUCKET_SIZE
+    }
+
+    /// Get the fraction of fingerprint slots in use.
+    pub fn load_factor(&self) -> f64 {
+        self.len as f64 / self.capacity() as f64
+    }
+
+    /// Get the seed used to hash items.
+    pub fn seed(&self) -> u64 {
+        self.hasher.seed()
+    }
+
+    /// Remove every item from the filter.
+    pub fn clear(&mut self) {
+        for bucket in &mut self.buckets {
+            *bucket = Bucket::default();
+        }
+        self.len = 0;
+    }
+
+    /// Find an item's fingerprint and first bucket.
+    fn locate<T>(&self, item: &T) -> (u16, usize)
+    where
+        T: Hash + ?Sized,
+    {
+        let hash = self.hasher.hash_one(item);
+        // Zero marks a free slot, so isn't a valid fingerprint.
+        let fingerprint = std::cmp::max(1, hash as u16);
+        let bucket = (hash >> 32) as usize & (self.buckets.len() - 1);
+        (fingerprint, bucket)
+    }
+
+    /// Find the other bucket a fingerprint can be stored in.
+    ///
+    /// This is its own inverse, so it works from either bucket.
+    fn alternate(&self, bucket: usize, fingerprint: u16) -> usize {
+        let offset = (u64::from(fingerprint).wrapping_mul(0xc6a4_a793_5bd1_e995) >> 32) as usize;
+        (bucket ^ offset) & (self.buckets.len() - 1)
+    }
+
+    fn next_random(&mut self) -> usize {
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 7;
+        self.rng ^= self.rng << 17;
+        self.rng as usize
+    }
+
+    /// Store a fingerprint in a free slot of a bucket, if there is one.
+    fn store(&mut self, bucket: usize, fingerprint: u16) -> bool {
+        match self.buckets[bucket].find(0) {
+            Some(slot) => {
+                self.buckets[bucket].0[slot] = fingerprint;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Insert an item.
+    ///
+    /// Returns `false`, leaving the filter unchanged, if no room could be
+    /// made for it. An item can be inserted more than once, taking a slot
+    /// each time.
+    pub fn insert<T>(&mut self, item: &T) -> bool
+    where
+        T: Hash + ?Sized,
+    {
+        let (fingerprint, first) = self.locate(item);
+        let second = self.alternate(first, fingerprint);
+        if self.store(first, fingerprint) || self.store(second, fingerprint) {
+            self.len += 1;
+            return true;
+        }
+        // Evict fingerprints along a random path, recording each swap so
+        // they can be undone if no free slot turns up.
+        let mut swaps = Vec::with_capacity(MAX_KICKS);
+        let mut bucket = if self.next_random() & 1 == 0 {
+            first
+        } else {
+            second
+        };
+        let mut homeless = fingerprint;
+        for _ in 0..MAX_KICKS {
+            let slot = self.next_random() % BUCKET_SIZE;
+            let evicted = std::mem::replace(&mut self.buckets[bucket].0[slot], homeless);
+            swaps.push((bucket, slot, evicted));
+            homeless = evicted;
+            bucket = self.alternate(bucket, homeless);
+            if self.store(bucket, homeless) {
+                self.len += 1;
+                return true;
+            }
+        }
+        for (bucket, slot, evicted) in swaps.into_iter().rev() {
+            self.buckets[bucket].0[slot] = evicted;
+        }
+        false
+    }
+
+    /// Test if an item may have been inserted.
+    ///
+    /// Returns `false` only if the item isn't in the filter.
+    pub fn contains<T>(&self, item: &T) -> bool
+    where
+        T: Hash + ?Sized,
+    {
+        let (fingerprint, first) = self.locate(item);
+        self.buckets[first].find(fingerprint).is_some()
+            || self.buckets[self.alternate(first, fingerprint)]
+                .find(fingerprint)
+                .is_some()
+    }
+
+    /// Remove one copy of an item.
+    ///
+    /// Returns `false` if its fingerprint wasn't found. Only remove items
+    /// which were inserted: removing another item with the same fingerprint
+    /// and buckets would remove that item instead.
+    pub fn remove<T>(&mut self, item: &T) -> bool
+    where
+        T: Hash + ?Sized,
+    {
+        let (fingerprint, first) = self.locate(item);
+        let second = self.alternate(first, fingerprint);
+        for bucket in [first, second] {
+            if let Some(slot) = self.buckets[bucket].find(fingerprint) {
+                self.buckets[bucket].0[slot] = 0;
+                self.len -= 1;
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl Debug for SimdCuckooFilter {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        f.debug_struct("SimdCuckooFilter")
+            .field("len", &self.len)
+            .field("capacity", &self.capacity())
+            .field("seed", &self.seed())
+            .finish()
+    }
+}
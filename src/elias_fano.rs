@@ -0,0 +1,226 @@
+use std::fmt::{Debug, Error, Formatter};
+
+use crate::{SimdBitVec, SimdVec};
+
+/// How many ones, or zeros, of the upper bits pass between select samples.
+const SAMPLE_RATE: usize = 256;
+const BLOCK_BITS: usize = 256;
+
+/// A compressed sorted sequence of `u64`s in the Elias–Fano representation.
+///
+/// Each value is split into its low `low_bits` bits, stored packed, and the
+/// rest, stored in unary as gaps in a `SimdBitVec`: value `i` sets bit
+/// `(value >> low_bits) + i`. With `low_bits` close to the log of the
+/// average gap, this takes less than `2 + log(max / len)` bits a value,
+/// which suits sparse sequences such as file offsets or document ids. See
+/// ['Efficient Storage and Retrieval by Content and Address of Static
+/// Files,' Elias, 1974](https://doi.org/10.1145/321812.321820).
+///
+/// Getting a value selects a one in the upper bits, and finding a lower
+/// bound selects a zero. Both start from a sample taken every 256 ones or
+/// zeros, and skip forward a run of 32-byte blocks at a time using the SIMD
+/// popcount of `SimdBitVec`.
+#[derive(Clone)]
+pub struct SimdEliasFano {
+    len: usize,
+    low_bits: u32,
+    lower: Vec<u64>,
+    upper: SimdBitVec,
+    /// The block holding every `SAMPLE_RATE`th one, and the ones before it.
+    one_samples: Vec<(usize, usize)>,
+    /// The block holding every `SAMPLE_RATE`th zero, and the zeros before
+    /// it.
+    zero_samples: Vec<(usize, usize)>,
+}
+
+impl SimdEliasFano {
+    /// Encode a sorted slice of values.
+    ///
+    /// Panics if the values are not sorted.
+    pub fn new(values: &[u64]) -> Self {
+        if let Some(index) = values.windows(2).position(|pair| pair[0] > pair[1]) {
+            panic!(
+                "SimdEliasFano::new: values are not sorted at index {}",
+                index + 1
+            )
+        }
+        let len = values.len();
+        let max = values.last().copied().unwrap_or(0);
+        let low_bits = if len == 0 {
+            0
+        } else {
+            let average_gap = (u128::from(max) + 1) / len as u128;
+            std::cmp::min(63, average_gap.checked_ilog2().unwrap_or(0))
+        };
+        let mut lower = vec![0; (len * low_bits as usize).div_ceil(64)];
+        let mut upper = SimdBitVec::zeroed(len + (max >> low_bits) as usize + 1);
+        let low_mask = !(u64::MAX << low_bits);
+        for (index, value) in values.iter().enumerate() {
+            upper.set((value >> low_bits) as usize + index, true);
+            if low_bits > 0 {
+                let bit = index * low_bits as usize;
+                let (word, shift) = (bit / 64, bit % 64);
+                lower[word] |= (value & low_mask) << shift;
+                if shift + low_bits as usize > 64 {
+                    lower[word + 1] |= (value & low_mask) >> (64 - shift);
+                }
+            }
+        }
+        let mut one_samples = Vec::new();
+        let mut zero_samples = Vec::new();
+        let (mut ones, mut zeros, mut ones_before_block) = (0, 0, 0);
+        for (position, bit) in upper.iter().enumerate() {
+            let block = position / BLOCK_BITS;
+            if position % BLOCK_BITS == 0 {
+                ones_before_block = ones;
+            }
+            if bit {
+                if ones % SAMPLE_RATE == 0 {
+                    one_samples.push((block, ones_before_block));
+                }
+                ones += 1;
+            } else {
+                if zeros % SAMPLE_RATE == 0 {
+                    zero_samples.push((block, block * BLOCK_BITS - ones_before_block));
+                }
+                zeros += 1;
+            }
+        }
+        SimdEliasFano {
+            len,
+            low_bits,
+            lower,
+            upper,
+            one_samples,
+            zero_samples,
+        }
+    }
+
+    /// Get the number of values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Test if the sequence is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the number of low bits stored for each value.
+    pub fn low_bits(&self) -> u32 {
+        self.low_bits
+    }
+
+    /// Get the number of bytes used by the encoded sequence and its select
+    /// samples.
+    pub fn size_in_bytes(&self) -> usize {
+        std::mem::size_of_val(self.lower.as_slice())
+            + self.upper.len().div_ceil(BLOCK_BITS) * BLOCK_BITS / 8
+            + std::mem::size_of_val(self.one_samples.as_slice())
+            + std::mem::size_of_val(self.zero_samples.as_slice())
+    }
+
+    fn low(&self, index: usize) -> u64 {
+        if self.low_bits == 0 {
+            return 0;
+        }
+        let bits = self.low_bits as usize;
+        let bit = index * bits;
+        let (word, shift) = (bit / 64, bit % 64);
+        let mut low = self.lower[word] >> shift;
+        if shift + bits > 64 {
+            low |= self.lower[word + 1] << (64 - shift);
+        }
+        low & self.low_mask()
+    }
+
+    fn low_mask(&self) -> u64 {
+        !(u64::MAX << self.low_bits)
+    }
+
+    /// Find the position of the `n`th one, or zero, in the upper bits.
+    fn select(&self, n: usize, value: bool) -> Option<usize> {
+        let samples = if value {
+            &self.one_samples
+        } else {
+            &self.zero_samples
+        };
+        let (block, before) = *samples.get(n / SAMPLE_RATE)?;
+        self.upper.select_from(block, n - before, value)
+    }
+
+    /// Get the value at a given index.
+    ///
+    /// Returns `None` if the index is out of bounds.
+    pub fn get(&self, index: usize) -> Option<u64> {
+        if index >= self.len {
+            return None;
+        }
+        let high = (self.select(index, true).unwrap() - index) as u64;
+        Some(high << self.low_bits | self.low(index))
+    }
+
+    /// Find the index of the first value not less than `value`.
+    pub fn lower_bound(&self, value: u64) -> usize {
+        let high = (value >> self.low_bits) as usize;
+        // Values with a smaller high part come before the `high`th zero.
+        let (mut position, mut index) = match high {
+            0 => (0, 0),
+            _ => match self.select(high - 1, false) {
+                Some(zero) => (zero + 1, zero + 1 - high),
+                None => return self.len,
+            },
+        };
+        // Then scan the values sharing its high part.
+        while self.upper.get(position) == Some(true) {
+            if self.low(index) >= value & self.low_mask() {
+                return index;
+            }
+            position += 1;
+            index += 1;
+        }
+        index
+    }
+
+    /// Test if the sequence holds `value`.
+    pub fn contains(&self, value: u64) -> bool {
+        self.get(self.lower_bound(value)) == Some(value)
+    }
+
+    /// Iterate over the values.
+    pub fn iter(&self) -> impl Iterator<Item = u64> + '_ {
+        self.upper
+            .iter_ones()
+            .enumerate()
+            .map(move |(index, position)| {
+                ((position - index) as u64) << self.low_bits | self.low(index)
+            })
+    }
+
+    /// Decode every value into a `SimdVec`.
+    pub fn to_vec(&self) -> SimdVec<u64> {
+        let mut out = SimdVec::with_capacity(self.len);
+        out.extend(self.iter());
+        out
+    }
+}
+
+impl From<&SimdVec<u64>> for SimdEliasFano {
+    fn from(vec: &SimdVec<u64>) -> Self {
+        Self::new(vec)
+    }
+}
+
+impl Debug for SimdEliasFano {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl PartialEq for SimdEliasFano {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for SimdEliasFano {}
@@ -0,0 +1,91 @@
+use std::hash::{BuildHasher, Hasher};
+
+/// An odd constant with well mixed bits, from the golden ratio.
+const MULTIPLIER: u64 = 0x9e37_79b9_7f4a_7c15;
+/// Mixed into the seed so that a zero seed doesn't start from a zero state.
+const SEED_OFFSET: u64 = 0x243f_6a88_85a3_08d3;
+
+/// Multiply by `MULTIPLIER` at full width and fold the halves together.
+fn fold(value: u64) -> u64 {
+    let product = u128::from(value) * u128::from(MULTIPLIER);
+    product as u64 ^ (product >> 64) as u64
+}
+
+/// The MurmurHash3 finaliser, which spreads every input bit over the output.
+fn finalise(mut value: u64) -> u64 {
+    value ^= value >> 33;
+    value = value.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    value ^= value >> 33;
+    value = value.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    value ^ (value >> 33)
+}
+
+/// A `BuildHasher` giving the same hashes on every run for the same seed.
+///
+/// `RandomState` picks a new key for each process, which is what a hash map
+/// exposed to untrusted keys wants, but makes approximate structures like
+/// `SimdBloomFilter` behave differently from run to run. Hashes from this
+/// are a pure function of the seed and the hashed bytes, so filters can be
+/// rebuilt, compared and tested reproducibly. It offers no protection
+/// against keys chosen to collide.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SeededState {
+    seed: u64,
+}
+
+impl SeededState {
+    /// Construct a hasher builder with a given seed.
+    pub fn new(seed: u64) -> Self {
+        SeededState { seed }
+    }
+
+    /// Get the seed.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+}
+
+impl BuildHasher for SeededState {
+    type Hasher = SeededHasher;
+
+    fn build_hasher(&self) -> SeededHasher {
+        SeededHasher {
+            state: fold(self.seed ^ SEED_OFFSET),
+            len: 0,
+        }
+    }
+}
+
+/// The `Hasher` built by `SeededState`.
+#[derive(Clone, Debug)]
+pub struct SeededHasher {
+    state: u64,
+    len: u64,
+}
+
+impl Hasher for SeededHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            let mut word = [0; 8];
+            word.copy_from_slice(chunk);
+            self.state = fold(self.state ^ u64::from_le_bytes(word));
+        }
+        let tail = chunks.remainder();
+        if !tail.is_empty() {
+            let mut word = [0; 8];
+            word[..tail.len()].copy_from_slice(tail);
+            self.state = fold(self.state ^ u64::from_le_bytes(word));
+        }
+        self.len += bytes.len() as u64;
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.state = fold(self.state ^ value);
+        self.len += 8;
+    }
+
+    fn finish(&self) -> u64 {
+        finalise(fold(self.state ^ self.len.rotate_right(8)))
+    }
+}
@@ -0,0 +1,448 @@
+use std::arch::x86_64::__m128i;
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::fmt::{Debug, Error, Formatter};
+use std::hash::{BuildHasher, Hash};
+use std::iter::FromIterator;
+
+use bitmaps::Bitmap;
+
+use crate::{SimdOps, SimdRegister};
+
+const GROUP_SIZE: usize = 16;
+
+/// The control byte of a slot which has never been filled.
+const EMPTY: u8 = 0x80;
+/// The control byte of a slot whose entry was removed.
+const DELETED: u8 = 0xfe;
+
+type GroupMask = Bitmap<<__m128i as SimdRegister>::MovemaskSize>;
+
+/// The control bytes for 16 slots.
+///
+/// A full slot's control byte is the low 7 bits of its key's hash, which
+/// leaves the high bit clear. Empty and deleted slots have it set.
+#[derive(Clone, Copy)]
+#[repr(align(16))]
+pub(crate) struct Group(pub(crate) [u8; GROUP_SIZE]);
+
+impl Group {
+    const EMPTY: Group = Group([EMPTY; GROUP_SIZE]);
+
+    fn register(&self) -> __m128i {
+        // The alignment makes this a valid aligned load.
+        unsafe { *(self.0.as_ptr() as *const __m128i) }
+    }
+
+    /// Find the slots whose control byte is `byte`.
+    pub(crate) fn match_byte(&self, byte: u8) -> GroupMask {
+        if is_x86_feature_detected!("sse2") {
+            unsafe { self.match_byte_simd(byte) }
+        } else {
+            self.match_byte_scalar(byte)
+        }
+    }
+
+    /// Find the slots which are empty or deleted.
+    pub(crate) fn match_free(&self) -> GroupMask {
+        if is_x86_feature_detected!("sse2") {
+            unsafe { self.match_free_simd() }
+        } else {
+            self.match_free_scalar()
+        }
+    }
+
+    fn match_empty(&self) -> GroupMask {
+        self.match_byte(EMPTY)
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub(crate) unsafe fn match_byte_simd(&self, byte: u8) -> GroupMask {
+        <i8 as SimdOps<__m128i>>::cmp_eq(self.register(), <i8 as SimdOps<__m128i>>::set(byte as i8))
+    }
+
+    pub(crate) fn match_byte_scalar(&self, byte: u8) -> GroupMask {
+        let mut mask = Bitmap::new();
+        for (index, control) in self.0.iter().enumerate() {
+            mask.set(index, *control == byte);
+        }
+        mask
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub(crate) unsafe fn match_free_simd(&self) -> GroupMask {
+        // Free control bytes have the high bit set, so are negative.
+        <i8 as SimdOps<__m128i>>::cmp_gt(<i8 as SimdOps<__m128i>>::set(0), self.register())
+    }
+
+    pub(crate) fn match_free_scalar(&self) -> GroupMask {
+        let mut mask = Bitmap::new();
+        for (index, control) in self.0.iter().enumerate() {
+            mask.set(index, control & 0x80 != 0);
+        }
+        mask
+    }
+}
+
+/// Get the number of groups needed to hold `items` entries.
+fn groups_for(items: usize) -> usize {
+    let groups = items.div_ceil(max_load(1));
+    std::cmp::max(groups, 1).next_power_of_two()
+}
+
+/// Get the most entries, counting tombstones, which `groups` groups hold.
+fn max_load(groups: usize) -> usize {
+    groups * GROUP_SIZE / 8 * 7
+}
+
+/// A hash map using open addressing over 16 slot groups, in the style of
+/// SwissTable.
+///
+/// Each slot has a control byte holding 7 bits of its key's hash. A lookup
+/// compares all 16 control bytes of a group against the key's with one
+/// `SimdOps::cmp_eq`, and only compares keys for the slots which match.
+/// Groups are probed in a triangular sequence until a match is found or a
+/// group with an empty slot is reached. Removed entries leave tombstones
+/// where needed to keep later entries reachable, and these are cleared out
+/// when the table is rebuilt.
+///
+/// The table is rebuilt when inserting would take it past 7/8 full. If at
+/// least half of that load is tombstones it keeps its size, otherwise it
+/// doubles.
+#[derive(Clone)]
+pub struct SimdHashMap<K, V, S = RandomState> {
+    control: Vec<Group>,
+    slots: Vec<Option<(K, V)>>,
+    len: usize,
+    tombstones: usize,
+    hasher: S,
+}
+
+impl<K, V> SimdHashMap<K, V, RandomState> {
+    /// Construct an empty map.
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+
+    /// Construct an empty map with space for at least `capacity` entries.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, RandomState::new())
+    }
+}
+
+impl<K, V, S> SimdHashMap<K, V, S> {
+    /// Construct an empty map which hashes keys with `hasher`.
+    pub fn with_hasher(hasher: S) -> Self {
+        SimdHashMap {
+            control: Vec::new(),
+            slots: Vec::new(),
+            len: 0,
+            tombstones: 0,
+            hasher,
+        }
+    }
+
+    /// Construct an empty map with space for at least `capacity` entries,
+    /// which hashes keys with `hasher`.
+    pub fn with_capacity_and_hasher(capacity: usize, hasher: S) -> Self {
+        let mut map = Self::with_hasher(hasher);
+        if capacity > 0 {
+            let groups = groups_for(capacity);
+            map.control = vec![Group::EMPTY; groups];
+            map.slots.resize_with(groups * GROUP_SIZE, || None);
+        }
+        map
+    }
+
+    /// Get the number of entries in the map.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Test if the map is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the number of entries the map can hold without being rebuilt,
+    /// if nothing is removed.
+    pub fn capacity(&self) -> usize {
+        max_load(self.control.len())
+    }
+
+    /// Get the number of tombstones left by removed entries.
+    pub fn tombstones(&self) -> usize {
+        self.tombstones
+    }
+
+    /// Get the hasher used to hash keys.
+    pub fn hasher(&self) -> &S {
+        &self.hasher
+    }
+
+    /// Remove every entry, keeping the allocated space.
+    pub fn clear(&mut self) {
+        for group in &mut self.control {
+            *group = Group::EMPTY;
+        }
+        for slot in &mut self.slots {
+            *slot = None;
+        }
+        self.len = 0;
+        self.tombstones = 0;
+    }
+
+    /// Iterate over the entries, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.slots
+            .iter()
+            .filter_map(|slot| slot.as_ref().map(|(key, value)| (key, value)))
+    }
+
+    /// Iterate mutably over the entries, in no particular order.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut V)> {
+        self.slots
+            .iter_mut()
+            .filter_map(|slot| slot.as_mut().map(|(key, value)| (&*key, value)))
+    }
+
+    /// Iterate over the keys, in no particular order.
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    /// Iterate over the values, in no particular order.
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    /// Get the groups to visit for a hash, in order, starting from the
+    /// group picked by the bits above the control byte.
+    ///
+    /// Stepping by 1, 2, 3... groups visits every group once when the
+    /// number of groups is a power of two.
+    fn probe(&self, hash: u64) -> impl Iterator<Item = usize> {
+        let mask = self.control.len().wrapping_sub(1);
+        let mut group = (hash >> 7) as usize & mask;
+        (0..self.control.len()).map(move |stride| {
+            let current = group;
+            group = (group + stride + 1) & mask;
+            current
+        })
+    }
+
+    fn set_control(&mut self, slot: usize, control: u8) {
+        self.control[slot / GROUP_SIZE].0[slot % GROUP_SIZE] = control;
+    }
+
+    /// Find a free slot for a hash, which must exist.
+    fn find_free(&self, hash: u64) -> usize {
+        for group in self.probe(hash) {
+            if let Some(index) = self.control[group].match_free().first_index() {
+                return group * GROUP_SIZE + index;
+            }
+        }
+        unreachable!("SimdHashMap::find_free: no free slot")
+    }
+}
+
+impl<K, V, S> SimdHashMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    /// Rebuild the table with room for at least one more entry.
+    fn grow(&mut self) {
+        let groups = if self.len < self.capacity() / 2 {
+            groups_for(self.capacity())
+        } else {
+            groups_for(self.capacity() * 2)
+        };
+        self.control = vec![Group::EMPTY; groups];
+        let mut slots = Vec::new();
+        slots.resize_with(groups * GROUP_SIZE, || None);
+        let slots = std::mem::replace(&mut self.slots, slots);
+        self.tombstones = 0;
+        for (key, value) in slots.into_iter().flatten() {
+            let hash = self.hash(&key);
+            let slot = self.find_free(hash);
+            self.set_control(slot, (hash & 0x7f) as u8);
+            self.slots[slot] = Some((key, value));
+        }
+    }
+
+    fn hash<Q>(&self, key: &Q) -> u64
+    where
+        Q: Hash + ?Sized,
+    {
+        self.hasher.hash_one(key)
+    }
+
+    /// Find the slot holding a key.
+    fn find<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash(key);
+        let tag = (hash & 0x7f) as u8;
+        for group in self.probe(hash) {
+            let control = &self.control[group];
+            for index in &control.match_byte(tag) {
+                let slot = group * GROUP_SIZE + index;
+                match &self.slots[slot] {
+                    Some((candidate, _)) if candidate.borrow() == key => return Some(slot),
+                    _ => {}
+                }
+            }
+            if !control.match_empty().is_empty() {
+                break;
+            }
+        }
+        None
+    }
+
+    /// Get the value for a key.
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let slot = self.find(key)?;
+        self.slots[slot].as_ref().map(|(_, value)| value)
+    }
+
+    /// Get the value for a key mutably.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let slot = self.find(key)?;
+        self.slots[slot].as_mut().map(|(_, value)| value)
+    }
+
+    /// Test if the map has a value for a key.
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    /// Insert a value for a key.
+    ///
+    /// Returns the previous value for the key, if there was one. The key
+    /// already in the map is kept.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(slot) = self.find(&key) {
+            let (_, previous) = self.slots[slot].as_mut().unwrap();
+            return Some(std::mem::replace(previous, value));
+        }
+        if self.len + self.tombstones == self.capacity() {
+            self.grow();
+        }
+        let hash = self.hash(&key);
+        let slot = self.find_free(hash);
+        if self.control[slot / GROUP_SIZE].0[slot % GROUP_SIZE] == DELETED {
+            self.tombstones -= 1;
+        }
+        self.set_control(slot, (hash & 0x7f) as u8);
+        self.slots[slot] = Some((key, value));
+        self.len += 1;
+        None
+    }
+
+    /// Remove the value for a key.
+    ///
+    /// Returns the removed value, if there was one.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let slot = self.find(key)?;
+        // A probe only moves past a group with no empty slots, so if this
+        // group has one, no other key can be found through this slot.
+        if self.control[slot / GROUP_SIZE].match_empty().is_empty() {
+            self.set_control(slot, DELETED);
+            self.tombstones += 1;
+        } else {
+            self.set_control(slot, EMPTY);
+        }
+        self.len -= 1;
+        self.slots[slot].take().map(|(_, value)| value)
+    }
+}
+
+impl<K, V, S> Default for SimdHashMap<K, V, S>
+where
+    S: Default,
+{
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K, V, S> Extend<(K, V)> for SimdHashMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+    fn extend<I>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K, V, S> FromIterator<(K, V)> for SimdHashMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher + Default,
+{
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+    {
+        let mut out = Self::default();
+        out.extend(iter);
+        out
+    }
+}
+
+impl<K, V, S> Debug for SimdHashMap<K, V, S>
+where
+    K: Debug,
+    V: Debug,
+{
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V, S> PartialEq for SimdHashMap<K, V, S>
+where
+    K: Hash + Eq,
+    V: PartialEq,
+    S: BuildHasher,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl<K, V, S> Eq for SimdHashMap<K, V, S>
+where
+    K: Hash + Eq,
+    V: Eq,
+    S: BuildHasher,
+{
+}
@@ -0,0 +1,367 @@
+use std::arch::x86_64::{__m128i, __m256i};
+use std::mem::size_of;
+
+use bitmaps::Bitmap;
+
+use crate::simd_arith::ScalarArith;
+use crate::{DefaultZero, SimdArith, SimdArrayOps, SimdBitVec, SimdOps, SimdRegister, SimdVec};
+
+/// Element-wise arithmetic operations.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum ArithOp {
+    Add,
+    Sub,
+    Min,
+    Max,
+    SaturatingAdd,
+    SaturatingSub,
+}
+
+impl ArithOp {
+    #[inline]
+    unsafe fn apply<R, A>(self, left: R, right: R) -> R
+    where
+        R: SimdRegister,
+        A: SimdArith<R>,
+    {
+        match self {
+            ArithOp::Add => A::add(left, right),
+            ArithOp::Sub => A::sub(left, right),
+            ArithOp::Min => A::min(left, right),
+            ArithOp::Max => A::max(left, right),
+            ArithOp::SaturatingAdd => A::saturating_add(left, right),
+            ArithOp::SaturatingSub => A::saturating_sub(left, right),
+        }
+    }
+
+    fn apply_scalar<A>(self, left: A, right: A) -> A
+    where
+        A: Ord + ScalarArith,
+    {
+        match self {
+            ArithOp::Add => left.scalar_add(right),
+            ArithOp::Sub => left.scalar_sub(right),
+            ArithOp::Min => std::cmp::min(left, right),
+            ArithOp::Max => std::cmp::max(left, right),
+            ArithOp::SaturatingAdd => left.scalar_saturating_add(right),
+            ArithOp::SaturatingSub => left.scalar_saturating_sub(right),
+        }
+    }
+}
+
+/// Element-wise comparison operations.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CmpOp {
+    #[inline]
+    unsafe fn apply<R, A>(self, left: R, right: R) -> Bitmap<R::MovemaskSize>
+    where
+        R: SimdRegister,
+        A: SimdOps<R>,
+    {
+        match self {
+            CmpOp::Eq => A::cmp_eq(left, right),
+            CmpOp::Ne => !A::cmp_eq(left, right),
+            CmpOp::Lt => A::cmp_gt(right, left),
+            CmpOp::Le => !A::cmp_gt(left, right),
+            CmpOp::Gt => A::cmp_gt(left, right),
+            CmpOp::Ge => !A::cmp_gt(right, left),
+        }
+    }
+
+    fn apply_scalar<A: Ord>(self, left: &A, right: &A) -> bool {
+        match self {
+            CmpOp::Eq => left == right,
+            CmpOp::Ne => left != right,
+            CmpOp::Lt => left < right,
+            CmpOp::Le => left <= right,
+            CmpOp::Gt => left > right,
+            CmpOp::Ge => left >= right,
+        }
+    }
+}
+
+/// Compress a movemask with `bits_per_cmp` bits per lane into one bit per
+/// lane.
+pub(crate) fn pack_mask(mask: u32, bits_per_cmp: usize) -> u32 {
+    match bits_per_cmp {
+        1 => mask,
+        2 => {
+            let mut x = mask & 0x5555_5555;
+            x = (x | (x >> 1)) & 0x3333_3333;
+            x = (x | (x >> 2)) & 0x0f0f_0f0f;
+            x = (x | (x >> 4)) & 0x00ff_00ff;
+            (x | (x >> 8)) & 0x0000_ffff
+        }
+        4 => {
+            let mut x = mask & 0x1111_1111;
+            x = (x | (x >> 3)) & 0x0303_0303;
+            x = (x | (x >> 6)) & 0x000f_000f;
+            (x | (x >> 12)) & 0x0000_00ff
+        }
+        8 => {
+            let mut x = mask & 0x0101_0101;
+            x = (x | (x >> 7)) & 0x0003_0003;
+            (x | (x >> 14)) & 0x0000_000f
+        }
+        _ => (0..32 / bits_per_cmp).fold(0, |acc, lane| {
+            acc | (((mask >> (lane * bits_per_cmp)) & 1) << lane)
+        }),
+    }
+}
+
+fn check_lengths(left: usize, right: usize) {
+    if left != right {
+        panic!(
+            "SimdArrayOps: arrays have mismatched lengths {} and {}",
+            left, right
+        )
+    }
+}
+
+fn blocks_for<A>(len: usize) -> usize {
+    len.div_ceil(size_of::<__m256i>() / size_of::<A>())
+}
+
+/// Apply `op` to each pair of elements in two arrays.
+pub(crate) fn arith_vec<A, L, T>(left: &L, right: &T, op: ArithOp) -> SimdVec<A>
+where
+    A: Ord + Copy + DefaultZero + SimdArith<__m256i> + SimdArith<__m128i>,
+    L: SimdArrayOps<A> + ?Sized,
+    T: SimdArrayOps<A> + ?Sized,
+{
+    check_lengths(left.len(), right.len());
+    if is_x86_feature_detected!("avx2") {
+        unsafe { arith_vec_with::<__m256i, _, _, _>(left, right, op) }
+    } else if is_x86_feature_detected!("sse4.2") {
+        // The 128-bit operations need up to SSE4.2, which isn't part of the
+        // x86_64 baseline.
+        unsafe { arith_vec_with::<__m128i, _, _, _>(left, right, op) }
+    } else {
+        arith_vec_fallback(left, right, op)
+    }
+}
+
+/// Collect the results of a scalar fallback.
+fn collect<A, I>(values: I) -> SimdVec<A>
+where
+    A: Copy + DefaultZero,
+    I: ExactSizeIterator<Item = A>,
+{
+    let mut out = SimdVec::with_capacity(values.len());
+    out.extend(values);
+    out
+}
+
+pub(crate) fn arith_vec_fallback<A, L, T>(left: &L, right: &T, op: ArithOp) -> SimdVec<A>
+where
+    A: Ord + Copy + DefaultZero + ScalarArith,
+    L: SimdArrayOps<A> + ?Sized,
+    T: SimdArrayOps<A> + ?Sized,
+{
+    collect(
+        left.iter()
+            .zip(right.iter())
+            .map(|(left, right)| op.apply_scalar(*left, *right)),
+    )
+}
+
+pub(crate) unsafe fn arith_vec_with<R, A, L, T>(left: &L, right: &T, op: ArithOp) -> SimdVec<A>
+where
+    R: SimdRegister,
+    A: Ord + Copy + DefaultZero + SimdArith<R>,
+    L: SimdArrayOps<A> + ?Sized,
+    T: SimdArrayOps<A> + ?Sized,
+{
+    let len = left.len();
+    let mut out = vec![__m256i::default_zero(); blocks_for::<A>(len)];
+    let left = R::from_m256i(left.data_m256());
+    let right = R::from_m256i(right.data_m256());
+    let target = R::from_m256i_mut(&mut out);
+    for index in 0..len.div_ceil(A::ALIGNMENT) {
+        op.apply::<R, A>(left[index].load(), right[index].load())
+            .store(&mut target[index]);
+    }
+    SimdVec::from_blocks(out, len)
+}
+
+/// Apply `op` to each element of an array and a scalar.
+pub(crate) fn arith_scalar<A, L>(left: &L, value: A, op: ArithOp) -> SimdVec<A>
+where
+    A: Ord + Copy + DefaultZero + SimdArith<__m256i> + SimdArith<__m128i>,
+    L: SimdArrayOps<A> + ?Sized,
+{
+    if is_x86_feature_detected!("avx2") {
+        unsafe { arith_scalar_with::<__m256i, _, _>(left, value, op) }
+    } else if is_x86_feature_detected!("sse4.2") {
+        unsafe { arith_scalar_with::<__m128i, _, _>(left, value, op) }
+    } else {
+        arith_scalar_fallback(left, value, op)
+    }
+}
+
+pub(crate) fn arith_scalar_fallback<A, L>(left: &L, value: A, op: ArithOp) -> SimdVec<A>
+where
+    A: Ord + Copy + DefaultZero + ScalarArith,
+    L: SimdArrayOps<A> + ?Sized,
+{
+    collect(left.iter().map(|left| op.apply_scalar(*left, value)))
+}
+
+pub(crate) unsafe fn arith_scalar_with<R, A, L>(left: &L, value: A, op: ArithOp) -> SimdVec<A>
+where
+    R: SimdRegister,
+    A: Ord + Copy + DefaultZero + SimdArith<R>,
+    L: SimdArrayOps<A> + ?Sized,
+{
+    let len = left.len();
+    let mut out = vec![__m256i::default_zero(); blocks_for::<A>(len)];
+    let value = A::set(value);
+    let left = R::from_m256i(left.data_m256());
+    let target = R::from_m256i_mut(&mut out);
+    for index in 0..len.div_ceil(A::ALIGNMENT) {
+        op.apply::<R, A>(left[index].load(), value)
+            .store(&mut target[index]);
+    }
+    SimdVec::from_blocks(out, len)
+}
+
+/// Take the absolute value of each element of an array.
+pub(crate) fn abs<A, L>(source: &L) -> SimdVec<A>
+where
+    A: Ord + Copy + DefaultZero + SimdArith<__m256i> + SimdArith<__m128i>,
+    L: SimdArrayOps<A> + ?Sized,
+{
+    if is_x86_feature_detected!("avx2") {
+        unsafe { abs_with::<__m256i, _, _>(source) }
+    } else if is_x86_feature_detected!("sse4.2") {
+        unsafe { abs_with::<__m128i, _, _>(source) }
+    } else {
+        abs_fallback(source)
+    }
+}
+
+pub(crate) fn abs_fallback<A, L>(source: &L) -> SimdVec<A>
+where
+    A: Ord + Copy + DefaultZero + ScalarArith,
+    L: SimdArrayOps<A> + ?Sized,
+{
+    collect(source.iter().map(|value| value.scalar_abs()))
+}
+
+pub(crate) unsafe fn abs_with<R, A, L>(source: &L) -> SimdVec<A>
+where
+    R: SimdRegister,
+    A: Ord + Copy + DefaultZero + SimdArith<R>,
+    L: SimdArrayOps<A> + ?Sized,
+{
+    let len = source.len();
+    let mut out = vec![__m256i::default_zero(); blocks_for::<A>(len)];
+    let source = R::from_m256i(source.data_m256());
+    let target = R::from_m256i_mut(&mut out);
+    for index in 0..len.div_ceil(A::ALIGNMENT) {
+        A::abs(source[index].load()).store(&mut target[index]);
+    }
+    SimdVec::from_blocks(out, len)
+}
+
+/// Compare each pair of elements in two arrays.
+pub(crate) fn compare_vec<A, L, T>(left: &L, right: &T, op: CmpOp) -> SimdBitVec
+where
+    A: Ord + Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    L: SimdArrayOps<A> + ?Sized,
+    T: SimdArrayOps<A> + ?Sized,
+{
+    check_lengths(left.len(), right.len());
+    if is_x86_feature_detected!("avx2") {
+        unsafe {
+            let right = __m256i::from_m256i(right.data_m256());
+            compare_with::<__m256i, _, _, _>(left, |index, data| {
+                op.apply::<__m256i, A>(data, right[index].load())
+            })
+        }
+    } else if is_x86_feature_detected!("sse4.2") {
+        unsafe {
+            let right = __m128i::from_m256i(right.data_m256());
+            compare_with::<__m128i, _, _, _>(left, |index, data| {
+                op.apply::<__m128i, A>(data, right[index].load())
+            })
+        }
+    } else {
+        compare_vec_fallback(left, right, op)
+    }
+}
+
+pub(crate) fn compare_vec_fallback<A, L, T>(left: &L, right: &T, op: CmpOp) -> SimdBitVec
+where
+    A: Ord,
+    L: SimdArrayOps<A> + ?Sized,
+    T: SimdArrayOps<A> + ?Sized,
+{
+    left.iter()
+        .zip(right.iter())
+        .map(|(left, right)| op.apply_scalar(left, right))
+        .collect()
+}
+
+/// Compare each element of an array with a scalar.
+pub(crate) fn compare_scalar<A, L>(left: &L, value: A, op: CmpOp) -> SimdBitVec
+where
+    A: Ord + Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    L: SimdArrayOps<A> + ?Sized,
+{
+    if is_x86_feature_detected!("avx2") {
+        unsafe {
+            let value = <A as SimdOps<__m256i>>::set(value);
+            compare_with::<__m256i, _, _, _>(left, |_, data| op.apply::<__m256i, A>(data, value))
+        }
+    } else if is_x86_feature_detected!("sse4.2") {
+        unsafe {
+            let value = <A as SimdOps<__m128i>>::set(value);
+            compare_with::<__m128i, _, _, _>(left, |_, data| op.apply::<__m128i, A>(data, value))
+        }
+    } else {
+        compare_scalar_fallback(left, value, op)
+    }
+}
+
+pub(crate) fn compare_scalar_fallback<A, L>(left: &L, value: A, op: CmpOp) -> SimdBitVec
+where
+    A: Ord,
+    L: SimdArrayOps<A> + ?Sized,
+{
+    left.iter()
+        .map(|left| op.apply_scalar(left, &value))
+        .collect()
+}
+
+/// Build a selection bitmap from the movemask of each register of `source`.
+///
+/// The closure receives the register index and the loaded register.
+pub(crate) unsafe fn compare_with<R, A, L, F>(source: &L, mut f: F) -> SimdBitVec
+where
+    R: SimdRegister,
+    A: Ord + SimdOps<R>,
+    L: SimdArrayOps<A> + ?Sized,
+    F: FnMut(usize, R) -> Bitmap<R::MovemaskSize>,
+{
+    let len = source.len();
+    let mut out = SimdBitVec::zeroed(len);
+    let data = R::from_m256i(source.data_m256());
+    let words = out.words_mut();
+    for (index, register) in data[..len.div_ceil(A::ALIGNMENT)].iter().enumerate() {
+        let bits = pack_mask(R::mask_value(f(index, register.load())), A::BITS_PER_CMP);
+        let offset = index * A::ALIGNMENT;
+        words[offset / 64] |= u64::from(bits) << (offset % 64);
+    }
+    out.clear_padding();
+    out
+}
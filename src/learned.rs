@@ -0,0 +1,201 @@
+use std::arch::x86_64::{__m128i, __m256i};
+use std::mem::size_of;
+
+use crate::array_ops::lanes_within;
+use crate::simd_ops::has_sse_compare;
+use crate::{DefaultZero, SimdArrayOps, SimdOps, SimdRegister, SimdVec};
+
+/// One piece of the linear model, covering the keys from its first key up to
+/// the first key of the next segment.
+#[derive(Clone, Copy, Debug)]
+struct Segment {
+    slope: f64,
+    /// The position of the segment's first key.
+    intercept: usize,
+}
+
+/// A learned index over a sorted vector.
+///
+/// A piecewise linear model, built in the style of the [PGM
+/// index](https://pgm.di.unipi.it/), predicts the position of a key to
+/// within `epsilon` places. The predicted window is then scanned with SIMD
+/// comparisons. The first key of each segment is kept in a `SimdVec`, so
+/// finding the segment is itself a k-ary search.
+///
+/// Lookups are verified against the data, so they are always correct, even
+/// if a prediction misses its window.
+#[derive(Clone, Debug)]
+pub struct SimdLearnedIndex<A> {
+    data: SimdVec<A>,
+    epsilon: usize,
+    keys: SimdVec<A>,
+    segments: Vec<Segment>,
+}
+
+impl<A> SimdLearnedIndex<A>
+where
+    A: Ord + Copy + Into<i128> + DefaultZero + SimdOps<__m256i> + SimdOps<__m128i>,
+{
+    /// Build an index over a sorted vector, with predictions accurate to
+    /// within `epsilon` places.
+    ///
+    /// A larger `epsilon` gives a smaller model but a longer scan per
+    /// lookup. If `data` is not sorted, lookups are unspecified.
+    pub fn new(data: SimdVec<A>, epsilon: usize) -> Self {
+        let mut keys = SimdVec::new();
+        let mut segments = Vec::new();
+        // The shrinking cone of slopes which keep every point of the current
+        // segment within epsilon of its prediction.
+        let mut origin: Option<(i128, usize)> = None;
+        let (mut slope_low, mut slope_high) = (0.0, f64::INFINITY);
+        let epsilon_f = epsilon as f64;
+        for (position, key) in data.iter().enumerate() {
+            if position > 0 && data[position - 1] == *key {
+                continue;
+            }
+            let x: i128 = (*key).into();
+            if let Some((origin_x, origin_y)) = origin {
+                // Sorted keys make this non-negative, and as a `u128` it
+                // can't overflow even across the whole `i128` range.
+                let dx = x.wrapping_sub(origin_x) as u128 as f64;
+                let dy = (position - origin_y) as f64;
+                let low = (dy - epsilon_f) / dx;
+                let high = (dy + epsilon_f) / dx;
+                if low <= slope_high && high >= slope_low {
+                    slope_low = f64::max(slope_low, low);
+                    slope_high = f64::min(slope_high, high);
+                    continue;
+                }
+                segments.push(Segment {
+                    slope: Self::pick_slope(slope_low, slope_high),
+                    intercept: origin_y,
+                });
+            }
+            keys.push(*key);
+            origin = Some((x, position));
+            slope_low = 0.0;
+            slope_high = f64::INFINITY;
+        }
+        if let Some((_, origin_y)) = origin {
+            segments.push(Segment {
+                slope: Self::pick_slope(slope_low, slope_high),
+                intercept: origin_y,
+            });
+        }
+        SimdLearnedIndex {
+            data,
+            epsilon,
+            keys,
+            segments,
+        }
+    }
+
+    fn pick_slope(low: f64, high: f64) -> f64 {
+        if high.is_infinite() {
+            // A segment of a single key
+            0.0
+        } else {
+            (low + high) / 2.0
+        }
+    }
+
+    /// Get the indexed data.
+    pub fn data(&self) -> &SimdVec<A> {
+        &self.data
+    }
+
+    /// Get the error bound the model was built with.
+    pub fn epsilon(&self) -> usize {
+        self.epsilon
+    }
+
+    /// Get the number of linear segments in the model.
+    pub fn segment_count(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Get the size of the model in bytes, not counting the indexed data.
+    pub fn model_size(&self) -> usize {
+        self.keys.len() * size_of::<A>() + self.segments.len() * size_of::<Segment>()
+    }
+
+    /// Predict the position of a key.
+    fn predict(&self, key: A) -> Option<usize> {
+        let segment = match self.keys.search(key) {
+            Ok(index) => return Some(self.segments[index].intercept),
+            Err(0) => return None,
+            Err(index) => index - 1,
+        };
+        let Segment { slope, intercept } = self.segments[segment];
+        let dx = key.into().wrapping_sub(self.keys[segment].into()) as u128 as f64;
+        Some(intercept.saturating_add((slope * dx) as usize))
+    }
+
+    /// Find the index of the first element not less than `key`.
+    pub fn lower_bound(&self, key: A) -> usize {
+        let len = self.data.len();
+        let predicted = match self.predict(key) {
+            Some(predicted) => std::cmp::min(predicted, len),
+            None => return 0,
+        };
+        let low = predicted.saturating_sub(self.epsilon);
+        let high = std::cmp::min(predicted + self.epsilon + 2, len);
+        let index = low + count_less(&self.data, key, low, high);
+        let missed_low = index == low && low > 0 && self.data[low - 1] >= key;
+        let missed_high = index == high && high < len && self.data[high] < key;
+        if missed_low || missed_high {
+            self.data.partition_point(|value| *value < key)
+        } else {
+            index
+        }
+    }
+
+    /// Search for a key.
+    ///
+    /// Without duplicates this gives the same result as
+    /// `SimdArrayOps::search`. If the key occurs more than once, this returns
+    /// the index of its first occurrence.
+    pub fn search(&self, key: A) -> Result<usize, usize> {
+        let index = self.lower_bound(key);
+        if index < self.data.len() && self.data[index] == key {
+            Ok(index)
+        } else {
+            Err(index)
+        }
+    }
+}
+
+/// Count the elements less than `key` in `low..high` of a sorted array.
+fn count_less<A, S>(source: &S, key: A, low: usize, high: usize) -> usize
+where
+    A: Ord + Copy + SimdOps<__m256i> + SimdOps<__m128i>,
+    S: SimdArrayOps<A> + ?Sized,
+{
+    if low >= high {
+        return 0;
+    }
+    if is_x86_feature_detected!("avx2") {
+        unsafe { count_less_with::<__m256i, _, _>(source, key, low, high) }
+    } else if has_sse_compare::<A>() {
+        unsafe { count_less_with::<__m128i, _, _>(source, key, low, high) }
+    } else {
+        source[low..high].iter().filter(|item| **item < key).count()
+    }
+}
+
+unsafe fn count_less_with<R, A, S>(source: &S, key: A, low: usize, high: usize) -> usize
+where
+    R: SimdRegister,
+    A: Ord + Copy + SimdOps<R>,
+    S: SimdArrayOps<A> + ?Sized,
+{
+    let keys = A::set(key);
+    let mut count = 0;
+    let mut pos = low - low % A::ALIGNMENT;
+    while pos < high {
+        let less = A::cmp_gt(keys, source.load(pos)) & lanes_within::<R, A>(pos, &(low..high));
+        count += less.len() / A::BITS_PER_CMP;
+        pos += A::ALIGNMENT;
+    }
+    count
+}
@@ -4,6 +4,21 @@ pub use crate::default_zero::DefaultZero;
 mod simd_ops;
 pub use crate::simd_ops::{SimdOps, SimdRegister};
 
+mod simd_arith;
+pub use crate::simd_arith::SimdArith;
+
+mod kernels;
+
+mod compact;
+
+mod sort;
+pub use crate::sort::SortKey;
+
+mod set_ops;
+
+mod radix;
+pub use crate::radix::RadixKey;
+
 mod array_ops;
 pub use crate::array_ops::SimdArrayOps;
 
@@ -13,17 +28,74 @@ pub use crate::array::SimdArray;
 mod vec;
 pub use crate::vec::SimdVec;
 
+mod bitvec;
+pub use crate::bitvec::SimdBitVec;
+
+mod learned;
+pub use crate::learned::SimdLearnedIndex;
+
+mod columns;
+pub use crate::columns::SimdColumns;
+
+mod composite;
+pub use crate::composite::SimdCompositeVec;
+
+mod str_index;
+pub use crate::str_index::SimdStrIndex;
+
+mod art;
+pub use crate::art::SimdArt;
+
+mod hash_map;
+pub use crate::hash_map::SimdHashMap;
+
+mod hash;
+pub use crate::hash::{SeededHasher, SeededState};
+
+mod bloom;
+pub use crate::bloom::SimdBloomFilter;
+
+mod cuckoo;
+pub use crate::cuckoo::SimdCuckooFilter;
+
+mod packed;
+pub use crate::packed::SimdPackedVec;
+
+mod elias_fano;
+pub use crate::elias_fano::SimdEliasFano;
+
+mod varint;
+pub use crate::varint::VarintError;
+
+mod scan;
+pub use crate::scan::OverflowError;
+
+mod newtype;
+#[doc(hidden)]
+pub use crate::newtype::__private;
+
 #[cfg(test)]
 // FIXME: Clippy-in-rls is unhappy about something in the proptest! macro,
 // remove the below when it stops being silly.
 #[allow(clippy::unnecessary_operation)]
 mod test {
     use super::*;
-    use proptest::collection::{btree_set, SizeRange};
+    use crate::array_ops::{self, KeyDistance};
+    use crate::kernels::{self, ArithOp, CmpOp};
+    use crate::scan::{self, ScanInt, ScanOp};
+    use crate::set_ops;
+    use crate::simd_ops::has_sse_compare;
+    use crate::varint::{self, Varint};
+    use bitmaps::Bitmap;
+    use proptest::collection::{btree_set, vec, SizeRange};
     use proptest::strategy::{BoxedStrategy, Strategy, ValueTree};
-    use proptest::{num, proptest};
+    use proptest::{num, prop_oneof, proptest};
     use std::arch::x86_64::{__m128i, __m256i};
+    use std::collections::{BTreeMap, BTreeSet, HashMap};
     use std::fmt::{Debug, Display};
+    use std::hash::{BuildHasher, BuildHasherDefault, Hasher};
+    use std::mem::size_of;
+    use std::ops::{Bound, Range};
     use typenum::U32;
 
     fn sorted_vec<T>(
@@ -112,7 +184,7 @@ mod test {
         A: Ord + Copy + DefaultZero + Debug + SimdOps<__m128i>,
         SimdArray<A, U32>: SimdArrayOps<A>,
     {
-        if !is_x86_feature_detected!("sse2") {
+        if !has_sse_compare::<A>() {
             return;
         }
 
@@ -127,7 +199,7 @@ mod test {
         A: Ord + Copy + DefaultZero + Debug + Display + SimdOps<__m128i>,
         SimdArray<A, U32>: SimdArrayOps<A>,
     {
-        if !is_x86_feature_detected!("sse2") {
+        if !has_sse_compare::<A>() {
             return;
         }
 
@@ -203,107 +275,2166 @@ mod test {
         }
     }
 
-    proptest! {
-        #[test]
-        fn plain_binary_search_present(items in sorted_vec(num::i8::ANY, 1..1024), index in num::usize::ANY) {
-            let index = index % items.len();
-            let item = items[index];
-            assert_eq!(Ok(index), items.binary_search(&item))
+    fn has_register<R: SimdRegister>() -> bool {
+        if size_of::<R>() == size_of::<__m256i>() {
+            is_x86_feature_detected!("avx2")
+        } else {
+            is_x86_feature_detected!("sse2")
         }
+    }
 
-        #[test]
-        fn plain_binary_search_any(items in sorted_vec(num::i8::ANY, 1..1024), key in num::i8::ANY) {
-            match items.binary_search(&key) {
-                Ok(index) => assert_eq!(items[index], key),
-                Err(index) => {
-                    if index < items.len() {
-                        assert!(items[index] > key, "insert index value {} should be higher than search key {}", items[index], key);
+    trait ScalarArith: Sized {
+        fn apply(op: ArithOp, left: Self, right: Self) -> Self;
+        fn reference_abs(self) -> Self;
+    }
+
+    macro_rules! scalar_arith {
+        ($($t:ty),*) => {
+            $(
+                impl ScalarArith for $t {
+                    fn apply(op: ArithOp, left: $t, right: $t) -> $t {
+                        match op {
+                            ArithOp::Add => left.wrapping_add(right),
+                            ArithOp::Sub => left.wrapping_sub(right),
+                            ArithOp::Min => left.min(right),
+                            ArithOp::Max => left.max(right),
+                            ArithOp::SaturatingAdd => left.saturating_add(right),
+                            ArithOp::SaturatingSub => left.saturating_sub(right),
+                        }
                     }
-                    if index > 0 {
-                        assert!(items[index-1] < key, "pre-insert index value {} should be lower than search key {}", items[index-1], key);
+
+                    fn reference_abs(self) -> $t {
+                        self.wrapping_abs()
                     }
                 }
-            }
+            )*
+        };
+    }
+
+    scalar_arith!(i8, i16, i32, i64);
+
+    fn simdify_arith<R, A>(pairs: Vec<(A, A)>, scalar: A)
+    where
+        R: SimdRegister,
+        A: Ord + Copy + DefaultZero + Debug + ScalarArith + SimdArith<R>,
+    {
+        // The 128-bit arithmetic needs SSE4.2.
+        if !has_register::<R>()
+            || (size_of::<R>() == size_of::<__m128i>() && !is_x86_feature_detected!("sse4.2"))
+        {
+            return;
         }
 
-        #[test]
-        fn simdify_k_ary_search_present_i8_128(items in sorted_vec(num::i8::ANY, 1..1024), index in num::usize::ANY) {
-            simdify_k_ary_search_present_128(items, index)
+        let left: Vec<A> = pairs.iter().map(|pair| pair.0).collect();
+        let right: Vec<A> = pairs.iter().map(|pair| pair.1).collect();
+        let left_vec: SimdVec<A> = left.as_slice().into();
+        let right_vec: SimdVec<A> = right.as_slice().into();
+        for &op in &[
+            ArithOp::Add,
+            ArithOp::Sub,
+            ArithOp::Min,
+            ArithOp::Max,
+            ArithOp::SaturatingAdd,
+            ArithOp::SaturatingSub,
+        ] {
+            let expected: Vec<A> = pairs.iter().map(|&(l, r)| A::apply(op, l, r)).collect();
+            let result =
+                unsafe { kernels::arith_vec_with::<R, _, _, _>(&left_vec, &right_vec, op) };
+            assert_eq!(expected, result.to_vec(), "{:?}", op);
+            let result = kernels::arith_vec_fallback(&left_vec, &right_vec, op);
+            assert_eq!(expected, result.to_vec(), "{:?} fallback", op);
+            let expected: Vec<A> = left.iter().map(|&l| A::apply(op, l, scalar)).collect();
+            let result = unsafe { kernels::arith_scalar_with::<R, _, _>(&left_vec, scalar, op) };
+            assert_eq!(expected, result.to_vec(), "{:?} with scalar", op);
+            let result = kernels::arith_scalar_fallback(&left_vec, scalar, op);
+            assert_eq!(expected, result.to_vec(), "{:?} fallback with scalar", op);
         }
+        let expected: Vec<A> = left.iter().map(|&l| l.reference_abs()).collect();
+        let result = unsafe { kernels::abs_with::<R, _, _>(&left_vec) };
+        assert_eq!(expected, result.to_vec());
+        assert_eq!(expected, kernels::abs_fallback(&left_vec).to_vec());
+    }
 
-        #[test]
-        fn simdify_k_ary_search_any_i8_128(items in sorted_vec(num::i8::ANY, 1..1024), key in num::i8::ANY) {
-            simdify_k_ary_search_any_128(items,key)
+    fn simdify_compare<A>(pairs: Vec<(A, A)>, scalar: A)
+    where
+        A: Ord + Copy + DefaultZero + Debug + SimdOps<__m128i> + SimdOps<__m256i>,
+    {
+        let left: Vec<A> = pairs.iter().map(|pair| pair.0).collect();
+        let right: Vec<A> = pairs.iter().map(|pair| pair.1).collect();
+        let left_vec: SimdVec<A> = left.as_slice().into();
+        let right_vec: SimdVec<A> = right.as_slice().into();
+        let expect = |f: &dyn Fn(A, A) -> bool| -> Vec<bool> {
+            pairs.iter().map(|&(l, r)| f(l, r)).collect()
+        };
+        let expect_scalar = |f: &dyn Fn(A, A) -> bool| -> Vec<bool> {
+            left.iter().map(|&l| f(l, scalar)).collect()
+        };
+        let bits = |bitvec: SimdBitVec| -> Vec<bool> { bitvec.iter().collect() };
+        assert_eq!(expect(&|l, r| l == r), bits(left_vec.eq_vec(&right_vec)));
+        assert_eq!(expect(&|l, r| l != r), bits(left_vec.ne_vec(&right_vec)));
+        assert_eq!(expect(&|l, r| l < r), bits(left_vec.lt_vec(&right_vec)));
+        assert_eq!(expect(&|l, r| l <= r), bits(left_vec.le_vec(&right_vec)));
+        assert_eq!(expect(&|l, r| l > r), bits(left_vec.gt_vec(&right_vec)));
+        assert_eq!(expect(&|l, r| l >= r), bits(left_vec.ge_vec(&right_vec)));
+        assert_eq!(
+            expect_scalar(&|l, r| l == r),
+            bits(left_vec.eq_scalar(scalar))
+        );
+        assert_eq!(
+            expect_scalar(&|l, r| l != r),
+            bits(left_vec.ne_scalar(scalar))
+        );
+        assert_eq!(
+            expect_scalar(&|l, r| l < r),
+            bits(left_vec.lt_scalar(scalar))
+        );
+        assert_eq!(
+            expect_scalar(&|l, r| l <= r),
+            bits(left_vec.le_scalar(scalar))
+        );
+        assert_eq!(
+            expect_scalar(&|l, r| l > r),
+            bits(left_vec.gt_scalar(scalar))
+        );
+        assert_eq!(
+            expect_scalar(&|l, r| l >= r),
+            bits(left_vec.ge_scalar(scalar))
+        );
+        let ones = left.iter().filter(|&&l| l < scalar).count();
+        assert_eq!(ones, left_vec.lt_scalar(scalar).count_ones());
+        assert_eq!(
+            expect(&|l, r| l <= r),
+            bits(kernels::compare_vec_fallback(
+                &left_vec,
+                &right_vec,
+                CmpOp::Le
+            ))
+        );
+        assert_eq!(
+            expect_scalar(&|l, r| l > r),
+            bits(kernels::compare_scalar_fallback(
+                &left_vec,
+                scalar,
+                CmpOp::Gt
+            ))
+        );
+    }
+
+    #[test]
+    fn arith_on_array() {
+        let left: SimdArray<i32, U32> = [1, 2, 3, i32::MAX][..].into();
+        let right: SimdVec<i32> = [4, -5, 6, 1][..].into();
+        assert_eq!(&[5, -3, 9, i32::MIN], &*left.add_vec(&right));
+        assert_eq!(&[5, -3, 9, i32::MAX], &*left.saturating_add_vec(&right));
+        assert_eq!(&[3, 2, 3, 2], &*right.min_scalar(3).max_scalar(2));
+    }
+
+    #[test]
+    #[should_panic]
+    fn arith_length_mismatch() {
+        let left: SimdVec<i64> = [1, 2, 3][..].into();
+        let right: SimdVec<i64> = [1, 2][..].into();
+        left.sub_vec(&right);
+    }
+
+    #[test]
+    fn selection_bitmap() {
+        let data: SimdVec<i16> = [5, -1, 7, 3][..].into();
+        let selection = data.gt_scalar(3);
+        assert_eq!(4, selection.len());
+        assert_eq!(
+            vec![true, false, true, false],
+            selection.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(&[0b0101], selection.words());
+    }
+
+    fn simdify_filter<A>(items: Vec<A>, low: A, high: A, selection: Vec<bool>)
+    where
+        A: Ord + Copy + DefaultZero + Debug + SimdOps<__m128i> + SimdOps<__m256i>,
+    {
+        let vector: SimdVec<A> = items.as_slice().into();
+        let expected: Vec<A> = items.iter().copied().filter(|&item| item == low).collect();
+        assert_eq!(expected, vector.filter_eq(low).to_vec());
+        let expected: Vec<A> = items.iter().copied().filter(|&item| item > low).collect();
+        assert_eq!(expected, vector.filter_gt(low).to_vec());
+        assert_eq!(expected, vector.compact(&vector.gt_scalar(low)).to_vec());
+        let expected: Vec<A> = items
+            .iter()
+            .copied()
+            .filter(|&item| low <= item && item < high)
+            .collect();
+        assert_eq!(expected, vector.filter_range(low..high).to_vec());
+        let mut bits = SimdBitVec::new();
+        for index in 0..items.len() {
+            bits.push(selection.get(index).copied().unwrap_or(false));
         }
+        let expected: Vec<A> = items
+            .iter()
+            .zip(bits.iter())
+            .filter(|(_, selected)| *selected)
+            .map(|(item, _)| *item)
+            .collect();
+        assert_eq!(expected, vector.compact(&bits).to_vec());
+    }
 
-        #[test]
-        fn simdify_k_ary_search_present_i16_128(items in sorted_vec(num::i16::ANY, 1..512), index in num::usize::ANY) {
-            simdify_k_ary_search_present_128(items, index)
+    #[test]
+    fn filter_on_array() {
+        let data: SimdArray<i32, U32> = [9, 1, 8, 2, 7, 3][..].into();
+        assert_eq!(&[9, 8, 7], &*data.filter_gt(3));
+        assert_eq!(&[2, 3], &*data.filter_range(2..7));
+        assert!(data.filter_eq(5).is_empty());
+    }
+
+    fn simdify_sort<A>(mut items: Vec<A>)
+    where
+        A: SortKey + DefaultZero + Debug,
+    {
+        let mut vector: SimdVec<A> = items.as_slice().into();
+        vector.sort();
+        items.sort();
+        assert_eq!(items, vector.to_vec());
+    }
+
+    #[test]
+    fn sort_array() {
+        let mut array: SimdArray<i64, U32> = [5, -3, 9, 0, 5, i64::MIN, 2, i64::MAX, -1][..].into();
+        array.sort();
+        assert_eq!(&[i64::MIN, -3, -1, 0, 2, 5, 5, 9, i64::MAX], &*array);
+        let mut array: SimdArray<u64, U32> = [u64::MAX, 1 << 63, 0, (1 << 63) - 1, 7][..].into();
+        array.sort();
+        assert_eq!(&[0, 7, (1 << 63) - 1, 1 << 63, u64::MAX], &*array);
+    }
+
+    fn simdify_radix_sort<A>(mut items: Vec<A>, buffer: &mut SimdVec<A>)
+    where
+        A: Ord + Copy + DefaultZero + Debug + RadixKey,
+    {
+        let mut vector: SimdVec<A> = items.as_slice().into();
+        vector.radix_sort_with_buffer(buffer);
+        items.sort();
+        assert_eq!(items, vector.to_vec());
+    }
+
+    #[test]
+    fn radix_sort_signed_bounds() {
+        let mut vector: SimdVec<i64> = [0, -1, i64::MAX, i64::MIN, 1, i64::MIN + 1][..].into();
+        vector.radix_sort();
+        assert_eq!(&[i64::MIN, i64::MIN + 1, -1, 0, 1, i64::MAX], &*vector);
+    }
+
+    #[test]
+    fn radix_sort_reuses_buffer() {
+        let mut buffer = SimdVec::new();
+        for size in &[1000, 10, 0, 300] {
+            let items: Vec<u32> = (0..*size)
+                .rev()
+                .map(|i: u32| i.wrapping_mul(2_654_435_761))
+                .collect();
+            simdify_radix_sort(items, &mut buffer);
         }
+    }
 
-        #[test]
-        fn simdify_k_ary_search_any_i16_128(items in sorted_vec(num::i16::ANY, 1..512), key in num::i16::ANY) {
-            simdify_k_ary_search_any_128(items,key)
+    fn simdify_set_ops<A>(left: Vec<A>, right: Vec<A>)
+    where
+        A: Ord + Copy + DefaultZero + Debug + SimdOps<__m128i> + SimdOps<__m256i>,
+    {
+        let left_set: BTreeSet<A> = left.iter().copied().collect();
+        let right_set: BTreeSet<A> = right.iter().copied().collect();
+        let left: SimdVec<A> = left.as_slice().into();
+        let right: SimdVec<A> = right.as_slice().into();
+
+        let (mut left_bits, mut right_bits) = (
+            SimdBitVec::zeroed(left.len()),
+            SimdBitVec::zeroed(right.len()),
+        );
+        set_ops::matches_scalar(&left, &right, &mut left_bits, &mut right_bits);
+        assert_eq!(set_ops::matches(&left, &right), (left_bits, right_bits));
+
+        let expected: Vec<A> = left_set.intersection(&right_set).copied().collect();
+        assert_eq!(expected, left.intersect(&right).to_vec());
+        assert_eq!(expected.len(), left.intersect_count(&right));
+        let expected: Vec<A> = left_set.union(&right_set).copied().collect();
+        assert_eq!(expected, left.union(&right).to_vec());
+        assert_eq!(expected.len(), left.union_count(&right));
+        let expected: Vec<A> = left_set.difference(&right_set).copied().collect();
+        assert_eq!(expected, left.difference(&right).to_vec());
+        assert_eq!(expected.len(), left.difference_count(&right));
+        let expected: Vec<A> = left_set.symmetric_difference(&right_set).copied().collect();
+        assert_eq!(expected, left.symmetric_difference(&right).to_vec());
+        assert_eq!(expected.len(), left.symmetric_difference_count(&right));
+    }
+
+    #[test]
+    fn set_ops_gallop() {
+        let large: Vec<u32> = (0..10_000).map(|i| i * 3).collect();
+        simdify_set_ops(vec![0, 1, 299, 300, 29_997, 40_000], large.clone());
+        simdify_set_ops(large, vec![5, 6, 9]);
+    }
+
+    fn simdify_k_ary_search_range<R, A>(items: Vec<A>, key: A, start: usize, end: usize)
+    where
+        R: SimdRegister,
+        A: Ord + Copy + DefaultZero + Debug + SimdOps<R>,
+    {
+        if !has_register::<R>()
+            || (size_of::<R>() == size_of::<__m128i>() && !has_sse_compare::<A>())
+        {
+            return;
         }
+        let (start, end) = (start % (items.len() + 1), end % (items.len() + 1));
+        let (start, end) = (start.min(end), start.max(end));
+        let vector: SimdVec<A> = items.as_slice().into();
+        let expected = match items[start..end].binary_search(&key) {
+            Ok(index) => Ok(index + start),
+            Err(index) => Err(index + start),
+        };
+        assert_eq!(expected, unsafe {
+            vector.k_ary_search_range::<R>(key, start..end)
+        });
+    }
 
-        #[test]
-        fn simdify_k_ary_search_present_i32_128(items in sorted_vec(num::i32::ANY, 1..256), index in num::usize::ANY) {
-            simdify_k_ary_search_present_128(items, index)
+    fn simdify_merge<A>(mut runs: Vec<Vec<A>>)
+    where
+        A: SortKey + DefaultZero + Debug,
+    {
+        for run in &mut runs {
+            run.sort();
+        }
+        let vectors: Vec<SimdVec<A>> = runs.iter().map(|run| run.as_slice().into()).collect();
+        let refs: Vec<&SimdVec<A>> = vectors.iter().collect();
+        let mut expected: Vec<A> = runs.concat();
+        expected.sort();
+        assert_eq!(expected, SimdVec::merge_sorted_many(&refs).to_vec());
+        if let [left, right] = refs.as_slice() {
+            assert_eq!(expected, SimdVec::merge_sorted(left, right).to_vec());
         }
+    }
 
-        #[test]
-        fn simdify_k_ary_search_any_i32_128(items in sorted_vec(num::i32::ANY, 1..256), key in num::i32::ANY) {
-            simdify_k_ary_search_any_128(items,key)
+    fn simdify_gallop<A>(items: Vec<A>, key: A, hint: usize)
+    where
+        A: Ord + Copy + DefaultZero + Debug + SimdOps<__m128i> + SimdOps<__m256i>,
+    {
+        let vector: SimdVec<A> = items.as_slice().into();
+        assert_eq!(items.binary_search(&key), vector.search_from(hint, key));
+        let from = hint % (items.len() + 1);
+        let expected = match items[from..].binary_search(&key) {
+            Ok(index) => Ok(index + from),
+            Err(index) => Err(index + from),
+        };
+        assert_eq!(expected, vector.gallop_search(key, from));
+        assert_eq!(
+            expected,
+            array_ops::gallop_forward_scalar(&items, key, from)
+        );
+        if items[from..].first().is_none_or(|first| *first > key) {
+            assert_eq!(
+                items.binary_search(&key),
+                array_ops::gallop_backward_scalar(&items, key, from)
+            );
         }
+    }
 
-        #[test]
-        fn simdify_k_ary_search_present_i64_128(items in sorted_vec(num::i64::ANY, 1..128), index in num::usize::ANY) {
-            simdify_k_ary_search_present_128(items, index)
+    fn simdify_interpolation_search<A>(items: Vec<A>, key: A)
+    where
+        A: Ord + DefaultZero + Debug + KeyDistance + SimdOps<__m128i> + SimdOps<__m256i>,
+    {
+        let vector: SimdVec<A> = items.as_slice().into();
+        assert_eq!(items.binary_search(&key), vector.interpolation_search(key));
+    }
+
+    #[test]
+    fn interpolation_search_i128_extremes() {
+        let items = vec![i128::MIN, i128::MIN + 1, -1, 0, 1, i128::MAX - 1, i128::MAX];
+        for key in items.iter().copied().chain(vec![-2, 2]) {
+            simdify_interpolation_search(items.clone(), key);
         }
+    }
 
-        #[test]
-        fn simdify_k_ary_search_any_i64_128(items in sorted_vec(num::i64::ANY, 1..128), key in num::i64::ANY) {
-            simdify_k_ary_search_any_128(items,key)
+    #[test]
+    fn interpolation_search_u128_extremes() {
+        let items = vec![
+            0,
+            1,
+            u128::MAX / 2,
+            u128::MAX / 2 + 1,
+            u128::MAX - 1,
+            u128::MAX,
+        ];
+        for key in items.iter().copied().chain(vec![2, u128::MAX - 2]) {
+            simdify_interpolation_search(items.clone(), key);
         }
+    }
 
-        #[test]
-        fn simdify_k_ary_search_present_i8_256(items in sorted_vec(num::i8::ANY, 1..1024), index in num::usize::ANY) {
-            simdify_k_ary_search_present_256(items, index)
+    #[test]
+    fn interpolation_search_skewed() {
+        let items: Vec<u64> = (0..64).map(|i| 1 << i).collect();
+        for key in items
+            .iter()
+            .flat_map(|item| vec![item - 1, *item, item + 1])
+        {
+            simdify_interpolation_search(items.clone(), key);
         }
+    }
 
-        #[test]
-        fn simdify_k_ary_search_any_i8_256(items in sorted_vec(num::i8::ANY, 1..1024), key in num::i8::ANY) {
-            simdify_k_ary_search_any_256(items,key)
+    fn simdify_learned_index<A>(items: Vec<A>, keys: Vec<A>, epsilon: usize)
+    where
+        A: Ord + Copy + DefaultZero + Debug + Into<i128> + SimdOps<__m128i> + SimdOps<__m256i>,
+    {
+        let vector: SimdVec<A> = items.as_slice().into();
+        let index = SimdLearnedIndex::new(vector.clone(), epsilon);
+        for key in keys.into_iter().chain(items.iter().copied()) {
+            assert_eq!(vector.search(key), index.search(key));
+            assert_eq!(
+                items.partition_point(|item| *item < key),
+                index.lower_bound(key)
+            );
         }
+    }
 
-        #[test]
-        fn simdify_k_ary_search_present_i16_256(items in sorted_vec(num::i16::ANY, 1..512), index in num::usize::ANY) {
-            simdify_k_ary_search_present_256(items, index)
+    #[test]
+    fn learned_index_model_size() {
+        let items: Vec<u64> = (0..100_000).map(|i| i * 7).collect();
+        let index = SimdLearnedIndex::new(items.as_slice().into(), 16);
+        assert_eq!(1, index.segment_count());
+        assert_eq!(Ok(7000), index.search(49_000));
+        let items: Vec<u64> = (0..10_000).map(|i| i * i).collect();
+        let index = SimdLearnedIndex::new(items.as_slice().into(), 16);
+        assert!(index.segment_count() > 1);
+        assert!(index.model_size() < items.len() * size_of::<u64>() / 10);
+    }
+
+    #[test]
+    fn learned_index_i128_extremes() {
+        let items = vec![i128::MIN, i128::MIN + 1, -1, 0, 1, i128::MAX - 1, i128::MAX];
+        let keys = vec![i128::MIN + 2, -2, 2, i128::MAX - 2];
+        for epsilon in 0..4 {
+            simdify_learned_index(items.clone(), keys.clone(), epsilon);
         }
+    }
 
-        #[test]
-        fn simdify_k_ary_search_any_i16_256(items in sorted_vec(num::i16::ANY, 1..512), key in num::i16::ANY) {
-            simdify_k_ary_search_any_256(items,key)
+    fn simdify_rank<A>(mut items: Vec<A>, keys: Vec<A>)
+    where
+        A: Ord + Copy + DefaultZero + Debug + SimdOps<__m128i> + SimdOps<__m256i>,
+    {
+        items.sort();
+        let vector: SimdVec<A> = items.as_slice().into();
+        for key in keys.iter().chain(items.iter()) {
+            let rank = items.iter().filter(|item| *item < key).count();
+            assert_eq!(rank, vector.rank(*key));
+            assert_eq!(items.get(rank).copied(), vector.select(rank));
         }
+        for pair in keys.windows(2) {
+            let range = pair[0]..pair[1];
+            let count = items.iter().filter(|item| range.contains(item)).count();
+            assert_eq!(count, vector.count_range(range));
+        }
+    }
 
-        #[test]
-        fn simdify_k_ary_search_present_i32_256(items in sorted_vec(num::i32::ANY, 1..256), index in num::usize::ANY) {
-            simdify_k_ary_search_present_256(items, index)
+    fn simdify_rank_branchless<A, N>(mut items: Vec<A>, key: A)
+    where
+        A: Ord + Copy + DefaultZero + Debug + SimdOps<__m128i> + SimdOps<__m256i>,
+        N: generic_array::ArrayLength<__m256i>,
+    {
+        items.truncate(SimdArray::<A, N>::max_size());
+        items.sort();
+        let array: SimdArray<A, N> = items.as_slice().into();
+        let rank = items.iter().filter(|item| **item < key).count();
+        assert_eq!(rank, array.rank_branchless(key));
+        assert_eq!(rank, array.rank(key));
+    }
+
+    fn simdify_neighbours<A>(mut items: Vec<A>, keys: Vec<A>)
+    where
+        A: Ord
+            + DefaultZero
+            + Debug
+            + Into<i128>
+            + KeyDistance
+            + SimdOps<__m128i>
+            + SimdOps<__m256i>,
+    {
+        items.sort();
+        let vector: SimdVec<A> = items.as_slice().into();
+        let last = |index: Option<usize>| index.map(|index| (index, items[index]));
+        for key in keys.iter().chain(items.iter()).copied() {
+            let predecessor = last(items.iter().rposition(|item| *item < key));
+            let floor = last(items.iter().rposition(|item| *item <= key));
+            let successor = last(items.iter().position(|item| *item > key));
+            let ceil = last(items.iter().position(|item| *item >= key));
+            assert_eq!(predecessor, vector.predecessor(key));
+            assert_eq!(successor, vector.successor(key));
+            assert_eq!(floor, vector.floor(key));
+            assert_eq!(ceil, vector.ceil(key));
+            let distance = |(_, value): (usize, A)| (key.into() - value.into()).abs();
+            let nearest = match (floor, ceil) {
+                (Some(below), Some(above)) if distance(above) < distance(below) => Some(above),
+                (below, above) => below.or(above),
+            };
+            assert_eq!(nearest, vector.nearest(key));
         }
+    }
 
-        #[test]
-        fn simdify_k_ary_search_any_i32_256(items in sorted_vec(num::i32::ANY, 1..256), key in num::i32::ANY) {
-            simdify_k_ary_search_any_256(items,key)
+    #[test]
+    fn neighbours_at_edges() {
+        let vector: SimdVec<i32> = [1, 3, 3, 3, 7][..].into();
+        assert_eq!(None, vector.predecessor(1));
+        assert_eq!(Some((0, 1)), vector.predecessor(2));
+        assert_eq!(Some((0, 1)), vector.predecessor(3));
+        assert_eq!(Some((3, 3)), vector.predecessor(4));
+        assert_eq!(Some((1, 3)), vector.successor(1));
+        assert_eq!(Some((4, 7)), vector.successor(3));
+        assert_eq!(None, vector.successor(7));
+        assert_eq!(None, vector.floor(0));
+        assert_eq!(Some((3, 3)), vector.floor(3));
+        assert_eq!(Some((4, 7)), vector.floor(i32::MAX));
+        assert_eq!(Some((0, 1)), vector.ceil(i32::MIN));
+        assert_eq!(Some((1, 3)), vector.ceil(3));
+        assert_eq!(None, vector.ceil(8));
+        assert_eq!(Some((0, 1)), vector.nearest(2));
+        assert_eq!(Some((3, 3)), vector.nearest(5));
+        assert_eq!(Some((4, 7)), vector.nearest(6));
+        assert_eq!(None, SimdVec::<i32>::new().nearest(0));
+        let extremes: SimdVec<i128> = [i128::MIN, i128::MAX][..].into();
+        assert_eq!(Some((1, i128::MAX)), extremes.nearest(0));
+        assert_eq!(Some((0, i128::MIN)), extremes.nearest(-1));
+        assert_eq!(Some((0, i128::MIN)), extremes.nearest(i128::MIN));
+        assert_eq!(Some((1, i128::MAX)), extremes.nearest(i128::MAX));
+        let extremes: SimdVec<u128> = [0, u128::MAX][..].into();
+        assert_eq!(Some((0, 0)), extremes.nearest(u128::MAX / 2));
+        assert_eq!(Some((1, u128::MAX)), extremes.nearest(u128::MAX / 2 + 1));
+    }
+
+    #[test]
+    fn columns_search_by_key() {
+        let mut records: SimdColumns<u32, u32> =
+            vec![(5, 50), (1, 10), (3, 30)].into_iter().collect();
+        records.push(2, 20);
+        records.sort_by_key();
+        assert_eq!(&[1, 2, 3, 5], &**records.keys());
+        assert_eq!(&[10, 20, 30, 50], records.values());
+        assert_eq!(Ok(2), records.search(3));
+        assert_eq!(Err(3), records.search(4));
+        assert_eq!(Some(&50), records.get_by_key(5));
+        *records.get_by_key_mut(1).unwrap() += 1;
+        assert_eq!(Some((1, &11)), records.get(0));
+        assert_eq!(Some((5, 50)), records.pop());
+        assert_eq!(3, records.len());
+    }
+
+    fn simdify_columns<K>(mut pairs: Vec<(K, usize)>)
+    where
+        K: Ord + Copy + DefaultZero + Debug + SimdOps<__m128i> + SimdOps<__m256i>,
+    {
+        let mut records: SimdColumns<K, usize> = pairs.iter().copied().collect();
+        records.sort_by_key();
+        pairs.sort_by_key(|(key, _)| *key);
+        assert_eq!(
+            pairs,
+            records
+                .iter()
+                .map(|(key, value)| (key, *value))
+                .collect::<Vec<_>>()
+        );
+        for (key, _) in &pairs {
+            let value = records.get_by_key(*key).unwrap();
+            assert!(pairs.contains(&(*key, *value)));
         }
+    }
 
-        #[test]
-        fn simdify_k_ary_search_present_i64_256(items in sorted_vec(num::i64::ANY, 1..128), index in num::usize::ANY) {
-            simdify_k_ary_search_present_256(items, index)
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    #[repr(transparent)]
+    struct UserId(u32);
+    simd_newtype!(UserId(u32));
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    #[repr(transparent)]
+    struct Offset(i16);
+    simd_newtype!(Offset(i16));
+
+    #[test]
+    fn newtype_search() {
+        let ids: SimdVec<UserId> = [UserId(1), UserId(7), UserId(u32::MAX)][..].into();
+        assert_eq!(Ok(2), ids.search(UserId(u32::MAX)));
+        assert_eq!(Err(1), ids.search(UserId(2)));
+        assert_eq!(2, ids.rank(UserId(8)));
+        assert_eq!(&[UserId(7), UserId(u32::MAX)], &*ids.filter_gt(UserId(1)));
+    }
+
+    #[test]
+    fn newtype_sort() {
+        let mut ids: SimdVec<UserId> = [UserId(9), UserId(u32::MAX), UserId(0)][..].into();
+        ids.sort();
+        assert_eq!(&[UserId(0), UserId(9), UserId(u32::MAX)], &*ids);
+        let offsets: Vec<Offset> = (-40..40).rev().map(Offset).collect();
+        let mut vector: SimdVec<Offset> = offsets.as_slice().into();
+        vector.sort();
+        assert_eq!((-40..40).map(Offset).collect::<Vec<_>>(), vector.to_vec());
+    }
+
+    /// A wrapper sorted through the default `SortKey::min_max`, which is
+    /// built on `cmp_gt`.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    #[repr(transparent)]
+    struct Stamp<A>(A);
+
+    impl<A: SimdOps<__m256i>> SimdOps<__m256i> for Stamp<A> {
+        unsafe fn set(value: Stamp<A>) -> __m256i {
+            A::set(value.0)
+        }
+
+        unsafe fn cmp_eq(left: __m256i, right: __m256i) -> Bitmap<U32> {
+            A::cmp_eq(left, right)
+        }
+
+        unsafe fn cmp_gt(left: __m256i, right: __m256i) -> Bitmap<U32> {
+            A::cmp_gt(left, right)
         }
+    }
 
+    impl<A> DefaultZero for Stamp<A> {}
+
+    impl<A: Ord + Copy + SimdOps<__m256i>> SortKey for Stamp<A> {}
+
+    fn simdify_sort_stamped<A>(items: Vec<A>)
+    where
+        A: Ord + Copy + Debug + SimdOps<__m256i>,
+    {
+        simdify_sort(items.into_iter().map(Stamp).collect())
+    }
+
+    fn simdify_merge_stamped<A>(left: Vec<A>, right: Vec<A>)
+    where
+        A: Ord + Copy + Debug + SimdOps<__m256i>,
+    {
+        let stamp = |run: Vec<A>| run.into_iter().map(Stamp).collect();
+        simdify_merge(vec![stamp(left), stamp(right)])
+    }
+
+    proptest! {
         #[test]
-        fn simdify_k_ary_search_any_i64_256(items in sorted_vec(num::i64::ANY, 1..128), key in num::i64::ANY) {
-            simdify_k_ary_search_any_256(items,key)
+        fn simdify_newtype_offset(items in sorted_vec(num::i16::ANY, 0..512), key in num::i16::ANY) {
+            let wrapped: Vec<Offset> = items.iter().copied().map(Offset).collect();
+            let vector: SimdVec<Offset> = wrapped.as_slice().into();
+            assert_eq!(items.binary_search(&key), vector.search(Offset(key)));
+        }
+    }
+
+    fn simdify_composite<A, B>(mut items: Vec<(A, B)>, keys: Vec<(A, B)>)
+    where
+        A: Ord + Copy + DefaultZero + Debug + SimdOps<__m128i> + SimdOps<__m256i>,
+        B: Ord + Copy + DefaultZero + Debug + SimdOps<__m128i> + SimdOps<__m256i>,
+    {
+        items.sort();
+        let vector: SimdCompositeVec<A, B> = items.iter().copied().collect();
+        for key in keys.iter().chain(items.iter()) {
+            let lower = items.partition_point(|item| item < key);
+            let upper = items.partition_point(|item| item <= key);
+            assert_eq!(lower, vector.lower_bound(key));
+            assert_eq!(lower..upper, vector.equal_range(key));
+            let expected = if lower < upper { Ok(lower) } else { Err(lower) };
+            assert_eq!(expected, vector.search(key));
+        }
+    }
+
+    #[test]
+    fn composite_search() {
+        let vector: SimdCompositeVec<u32, u64> =
+            vec![(1, 5), (1, 9), (2, 0), (2, 0), (2, u64::MAX), (7, 3)]
+                .into_iter()
+                .collect();
+        assert_eq!(Ok(2), vector.search(&(2, 0)));
+        assert_eq!(2..4, vector.equal_range(&(2, 0)));
+        assert_eq!(Err(1), vector.search(&(1, 6)));
+        assert_eq!(5, vector.lower_bound(&(3, 0)));
+        assert_eq!(Err(0), vector.search(&(0, u64::MAX)));
+        assert_eq!(6..6, vector.equal_range(&(7, 4)));
+        assert_eq!(Some((7, 3)), vector.get(5));
+    }
+
+    /// 128-bit values clustered around the sign and 64-bit boundaries, where
+    /// the high and low halves interact.
+    fn boundary_i128() -> BoxedStrategy<i128> {
+        let bases = vec![
+            0,
+            i128::from(i64::MIN),
+            i128::from(i64::MAX),
+            i128::from(u64::MAX),
+            -i128::from(u64::MAX),
+            i128::MIN,
+            i128::MAX,
+        ];
+        prop_oneof![
+            (proptest::sample::select(bases), -3i128..=3)
+                .prop_map(|(base, delta)| base.saturating_add(delta)),
+            num::i128::ANY,
+        ]
+        .boxed()
+    }
+
+    fn simdify_str_index(mut strings: Vec<Vec<u8>>, keys: Vec<Vec<u8>>) {
+        let index: SimdStrIndex<Vec<u8>> = strings.iter().cloned().collect();
+        strings.sort();
+        assert_eq!(strings.as_slice(), index.strings());
+        for key in keys.iter().chain(strings.iter()) {
+            let lower = strings.partition_point(|string| string < key);
+            assert_eq!(lower, index.lower_bound(key));
+            match index.search(key) {
+                Ok(found) => assert_eq!(key, &strings[found]),
+                Err(insert) => assert_eq!(Err(insert), strings.binary_search(key)),
+            }
+            let range = index.prefix_range(key);
+            let expected: Vec<_> = strings
+                .iter()
+                .filter(|string| string.starts_with(key))
+                .collect();
+            assert_eq!(expected, strings[range].iter().collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn str_index_lookup() {
+        let index: SimdStrIndex<&str> = vec!["example.org", "a", "example.com", "b", "example"]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            &["a", "b", "example", "example.com", "example.org"],
+            index.strings()
+        );
+        assert_eq!(Ok(3), index.search("example.com"));
+        assert_eq!(Err(3), index.search("example.co"));
+        assert_eq!(2..5, index.prefix_range("example"));
+        assert_eq!(3..5, index.prefix_range("example."));
+        assert_eq!(0..5, index.prefix_range(""));
+        assert_eq!(5, index.lower_bound("z"));
+    }
+
+    fn simdify_art(ops: Vec<(bool, Vec<u8>)>, keys: Vec<Vec<u8>>) {
+        let mut art = SimdArt::new();
+        let mut expected = BTreeMap::new();
+        for (index, (insert, key)) in ops.into_iter().enumerate() {
+            if insert {
+                assert_eq!(expected.insert(key.clone(), index), art.insert(&key, index));
+            } else {
+                assert_eq!(expected.remove(&key), art.remove(&key));
+            }
+            assert_eq!(expected.len(), art.len());
+            assert_eq!(expected.get(&key), art.get(&key));
+        }
+        let entries: Vec<_> = expected
+            .iter()
+            .map(|(key, value)| (key.clone(), value))
+            .collect();
+        assert_eq!(entries, art.iter().collect::<Vec<_>>());
+        for key in keys.iter().chain(expected.keys()) {
+            assert_eq!(expected.get(key), art.get(key));
+            let prefixed: Vec<_> = entries
+                .iter()
+                .filter(|(entry, _)| entry.starts_with(key))
+                .cloned()
+                .collect();
+            assert_eq!(prefixed, art.prefix_iter(key).collect::<Vec<_>>());
+        }
+        for low in &keys {
+            for high in keys.iter().filter(|high| *high >= low) {
+                let ranged: Vec<_> = expected
+                    .range::<Vec<u8>, _>(low..high)
+                    .map(|(key, value)| (key.clone(), value))
+                    .collect();
+                assert_eq!(
+                    ranged,
+                    art.range(low.as_slice()..high.as_slice())
+                        .collect::<Vec<_>>()
+                );
+                let ranged: Vec<_> = expected
+                    .range::<Vec<u8>, _>(low..=high)
+                    .map(|(key, value)| (key.clone(), value))
+                    .collect();
+                assert_eq!(
+                    ranged,
+                    art.range(low.as_slice()..=high.as_slice())
+                        .collect::<Vec<_>>()
+                );
+                let ranged: Vec<_> = expected
+                    .range::<Vec<u8>, _>((Bound::Excluded(low), Bound::Unbounded))
+                    .map(|(key, value)| (key.clone(), value))
+                    .collect();
+                assert_eq!(
+                    ranged,
+                    art.range((Bound::Excluded(low.as_slice()), Bound::Unbounded))
+                        .collect::<Vec<_>>()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn art_node_sizes() {
+        let mut art = SimdArt::new();
+        // Grow the root through every node size, then shrink it back.
+        for byte in (0..=255u8).rev() {
+            assert_eq!(None, art.insert(&[byte, 1], byte));
+        }
+        assert_eq!(256, art.len());
+        for byte in 0..=255u8 {
+            assert_eq!(Some(&byte), art.get(&[byte, 1]));
+            assert_eq!(None, art.get(&[byte]));
+        }
+        let keys: Vec<_> = art.iter().map(|(key, _)| key).collect();
+        assert_eq!(
+            (0..=255u8).map(|byte| vec![byte, 1]).collect::<Vec<_>>(),
+            keys
+        );
+        for byte in (0..=255u8).filter(|byte| byte % 7 != 0) {
+            assert_eq!(Some(byte), art.remove(&[byte, 1]));
+        }
+        assert_eq!(
+            (0..=255u8).filter(|byte| byte % 7 == 0).collect::<Vec<_>>(),
+            art.iter().map(|(_, value)| *value).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn art_prefixes() {
+        let mut art = SimdArt::new();
+        for (value, key) in ["romane", "romanus", "romulus", "rubens", "ruber", "rom", ""]
+            .iter()
+            .enumerate()
+        {
+            art.insert(*key, value);
+        }
+        assert_eq!(Some(&5), art.get("rom"));
+        assert_eq!(None, art.get("ro"));
+        assert_eq!(
+            vec![
+                b"rom".to_vec(),
+                b"romane".to_vec(),
+                b"romanus".to_vec(),
+                b"romulus".to_vec()
+            ],
+            art.prefix_iter("rom")
+                .map(|(key, _)| key)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Some(5), art.remove("rom"));
+        assert_eq!(Some(&2), art.get("romulus"));
+        assert_eq!(
+            vec![b"rubens".to_vec()],
+            art.range(&b"rub"[..]..&b"ruber"[..])
+                .map(|(key, _)| key)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(7 - 1, art.len());
+        assert_eq!(Some(&6), art.get(""));
+    }
+
+    /// A hasher which sends every key to the same group and tag, so every
+    /// lookup probes through every collision.
+    #[derive(Clone, Default)]
+    struct CollidingHasher;
+
+    impl Hasher for CollidingHasher {
+        fn finish(&self) -> u64 {
+            0
+        }
+
+        fn write(&mut self, _bytes: &[u8]) {}
+    }
+
+    fn simdify_hash_map<S>(map: &mut SimdHashMap<u16, usize, S>, ops: Vec<(u8, u16)>)
+    where
+        S: BuildHasher,
+    {
+        let mut expected = HashMap::new();
+        for (index, (op, key)) in ops.into_iter().enumerate() {
+            match op % 4 {
+                0 | 1 => assert_eq!(expected.insert(key, index), map.insert(key, index)),
+                2 => assert_eq!(expected.remove(&key), map.remove(&key)),
+                _ => {
+                    if let Some(value) = map.get_mut(&key) {
+                        *value += 1;
+                    }
+                    if let Some(value) = expected.get_mut(&key) {
+                        *value += 1;
+                    }
+                }
+            }
+            assert_eq!(expected.len(), map.len());
+            assert_eq!(expected.get(&key), map.get(&key));
+            assert!(map.len() + map.tombstones() <= map.capacity());
+        }
+        for (key, value) in &expected {
+            assert_eq!(Some(value), map.get(key));
+        }
+        let mut entries: Vec<_> = map.iter().map(|(key, value)| (*key, *value)).collect();
+        entries.sort();
+        let mut expected: Vec<_> = expected.into_iter().collect();
+        expected.sort();
+        assert_eq!(expected, entries);
+    }
+
+    #[test]
+    fn hash_map_basics() {
+        let mut map: SimdHashMap<String, u32> = SimdHashMap::new();
+        assert_eq!(0, map.capacity());
+        assert_eq!(None, map.get("missing"));
+        assert_eq!(None, map.insert("one".to_string(), 1));
+        assert_eq!(Some(1), map.insert("one".to_string(), 2));
+        assert_eq!(Some(&2), map.get("one"));
+        assert!(map.contains_key("one"));
+        assert_eq!(Some(2), map.remove("one"));
+        assert_eq!(None, map.remove("one"));
+        assert!(map.is_empty());
+        map.extend((0..1000).map(|index| (index.to_string(), index)));
+        assert_eq!(1000, map.len());
+        assert_eq!(Some(&567), map.get("567"));
+        let copy: SimdHashMap<String, u32> = map
+            .iter()
+            .map(|(key, value)| (key.clone(), *value))
+            .collect();
+        assert_eq!(map, copy);
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(None, map.get("567"));
+    }
+
+    #[test]
+    fn hash_map_tombstones() {
+        let mut map = SimdHashMap::with_hasher(BuildHasherDefault::<CollidingHasher>::default());
+        for key in 0..200u16 {
+            map.insert(key, key);
+        }
+        // Every entry probes from the first group, so removing from the
+        // full groups there has to leave tombstones.
+        for key in 0..100u16 {
+            assert_eq!(Some(key), map.remove(&key));
+        }
+        assert!(map.tombstones() > 0);
+        for key in 100..200u16 {
+            assert_eq!(Some(&key), map.get(&key));
+        }
+        let capacity = map.capacity();
+        for key in 200..300u16 {
+            map.insert(key, key);
+        }
+        assert_eq!(capacity, map.capacity());
+        assert_eq!(200, map.len());
+    }
+
+    fn simdify_bloom(items: Vec<u64>, queries: Vec<u64>, seed: u64) {
+        let mut filter = SimdBloomFilter::with_rate(items.len(), 0.01, seed);
+        for item in &items {
+            filter.insert(item);
+        }
+        for item in &items {
+            assert!(filter.contains(item));
+        }
+        let batch: Vec<_> = items.iter().chain(&queries).copied().collect();
+        let found = filter.contains_many(&batch);
+        assert_eq!(batch.len(), found.len());
+        for (index, item) in batch.iter().enumerate() {
+            assert_eq!(Some(filter.contains(item)), found.get(index));
+        }
+        let copy = SimdBloomFilter::from_bytes(&filter.to_bytes(), seed).unwrap();
+        assert_eq!(filter, copy);
+        let copy = SimdBloomFilter::from_vec(&filter.to_vec(), seed).unwrap();
+        assert_eq!(filter, copy);
+        let mut other = SimdBloomFilter::new(filter.block_count(), seed);
+        for query in &queries {
+            other.insert(query);
+        }
+        other.union(&filter);
+        for item in items.iter().chain(&queries) {
+            assert!(other.contains(item));
+        }
+    }
+
+    #[test]
+    fn bloom_false_positive_rate() {
+        let mut filter = SimdBloomFilter::with_rate(10_000, 0.01, 7);
+        assert!(
+            SimdBloomFilter::expected_false_positive_rate(10_000, filter.block_count()) <= 0.01
+        );
+        for item in 0..10_000u64 {
+            filter.insert(&item);
+        }
+        let estimate = filter.false_positive_rate();
+        let positives = (10_000..110_000u64)
+            .filter(|item| filter.contains(item))
+            .count();
+        let observed = positives as f64 / 100_000.0;
+        assert!(observed < 0.015, "observed rate {}", observed);
+        assert!(
+            (estimate - observed).abs() < 0.005,
+            "estimate {} observed {}",
+            estimate,
+            observed
+        );
+        assert!(SimdBloomFilter::from_bytes(&[0; 33], 7).is_none());
+        assert!(SimdBloomFilter::from_bytes(&[], 7).is_none());
+        filter.clear();
+        assert_eq!(0.0, filter.false_positive_rate());
+    }
+
+    #[test]
+    fn seeded_hash_is_deterministic() {
+        let hash = |seed: u64, value: &str| SeededState::new(seed).hash_one(value);
+        assert_eq!(hash(1, "simdify"), hash(1, "simdify"));
+        assert_ne!(hash(1, "simdify"), hash(2, "simdify"));
+        assert_ne!(hash(1, "simdify"), hash(1, "simdifz"));
+        assert_ne!(
+            SeededState::new(0).hash_one(0u64),
+            SeededState::new(0).hash_one(1u64)
+        );
+    }
+
+    fn simdify_cuckoo(ops: Vec<(bool, u16)>, seed: u64) {
+        let mut filter = SimdCuckooFilter::new(256, seed);
+        let mut expected: BTreeMap<u16, usize> = BTreeMap::new();
+        for (insert, item) in ops {
+            if insert {
+                if filter.insert(&item) {
+                    *expected.entry(item).or_default() += 1;
+                } else {
+                    assert!(filter.load_factor() > 0.9);
+                }
+            } else if let Some(count) = expected.get_mut(&item) {
+                assert!(filter.remove(&item));
+                *count -= 1;
+                if *count == 0 {
+                    expected.remove(&item);
+                }
+            }
+            assert_eq!(expected.values().sum::<usize>(), filter.len());
+            if expected.contains_key(&item) {
+                assert!(filter.contains(&item));
+            }
+        }
+        // No false negatives, whatever was moved around.
+        for item in expected.keys() {
+            assert!(filter.contains(item));
+        }
+    }
+
+    #[test]
+    fn cuckoo_fill_and_drain() {
+        let mut filter = SimdCuckooFilter::new(4096, 11);
+        assert_eq!(4096, filter.capacity());
+        let inserted = (0..4096u32).take_while(|item| filter.insert(item)).count();
+        assert!(inserted > 3800, "only {} inserted", inserted);
+        assert!(filter.load_factor() > 0.9);
+        let positives = (10_000..110_000u32)
+            .filter(|item| filter.contains(item))
+            .count();
+        // Two buckets of eight 16-bit fingerprints give about 16 / 65536.
+        assert!(positives < 100, "{} false positives", positives);
+        for item in 0..inserted as u32 {
+            assert!(filter.remove(&item));
+        }
+        assert!(filter.is_empty());
+        assert_eq!(
+            0,
+            (0..inserted as u32)
+                .filter(|item| filter.contains(item))
+                .count()
+        );
+        // The same seed and operations give the same filter.
+        let mut left = SimdCuckooFilter::new(64, 3);
+        let mut right = SimdCuckooFilter::new(64, 3);
+        for item in 0..80u32 {
+            assert_eq!(left.insert(&item), right.insert(&item));
+        }
+        assert_eq!(left.len(), right.len());
+    }
+
+    fn simdify_bitvec(left: Vec<bool>, right: Vec<bool>, range: Range<usize>, value: bool) {
+        let len = std::cmp::min(left.len(), right.len());
+        let (left, right) = (&left[..len], &right[..len]);
+        let bits = SimdBitVec::from(left);
+        let other: SimdBitVec = right.iter().copied().collect();
+        assert_eq!(left, Vec::<bool>::from(&bits).as_slice());
+        let combine = |f: &dyn Fn(bool, bool) -> bool| -> Vec<bool> {
+            left.iter().zip(right).map(|(l, r)| f(*l, *r)).collect()
+        };
+        assert_eq!(combine(&|l, r| l & r), Vec::from(&(&bits & &other)));
+        assert_eq!(combine(&|l, r| l | r), Vec::from(&(&bits | &other)));
+        assert_eq!(combine(&|l, r| l ^ r), Vec::from(&(&bits ^ &other)));
+        let mut andnot = bits.clone();
+        andnot.andnot(&other);
+        assert_eq!(combine(&|l, r| l & !r), Vec::from(&andnot));
+        let ones: Vec<usize> = (0..len).filter(|index| left[*index]).collect();
+        assert_eq!(ones.len(), bits.count_ones());
+        assert_eq!(ones, bits.iter_ones().collect::<Vec<_>>());
+        for index in 0..=len {
+            assert_eq!(ones.partition_point(|one| *one < index), bits.rank(index));
+        }
+        for n in 0..=ones.len() {
+            assert_eq!(ones.get(n).copied(), bits.select(n));
+        }
+        let words = bits.clone().into_words();
+        assert_eq!(bits.words(), &words[..]);
+        assert_eq!(bits, SimdBitVec::from_words(words, len));
+        if len > 0 {
+            let range = range.start % len..range.end % (len + 1);
+            if range.start <= range.end {
+                let mut set = bits.clone();
+                set.set_range(range.clone(), value);
+                let mut expected = left.to_vec();
+                for bit in &mut expected[range] {
+                    *bit = value;
+                }
+                assert_eq!(expected, Vec::from(&set));
+                assert_eq!(
+                    expected.iter().filter(|bit| **bit).count(),
+                    set.count_ones()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn bitvec_words() {
+        let mut words = SimdVec::new();
+        words.extend(vec![u64::MAX; 9]);
+        // Only the first 70 bits survive, however many the words held.
+        let bits = SimdBitVec::from_words(words, 70);
+        assert_eq!(70, bits.count_ones());
+        assert_eq!(&[u64::MAX, 0x3f], bits.words());
+        assert_eq!(Some(69), bits.select(69));
+        assert_eq!(None, bits.select(70));
+        assert_eq!(64, bits.rank(64));
+    }
+
+    fn simdify_packed(mut values: Vec<u32>, keys: Vec<u32>) {
+        values.sort_unstable();
+        let packed = SimdPackedVec::new(&values);
+        assert_eq!(values.len(), packed.len());
+        assert_eq!(values, packed.iter().collect::<Vec<_>>());
+        assert_eq!(&values[..], &packed.to_vec()[..]);
+        for (index, value) in values.iter().enumerate().step_by(7) {
+            assert_eq!(Some(*value), packed.get(index));
+        }
+        assert_eq!(None, packed.get(values.len()));
+        for key in keys.iter().chain(values.iter().step_by(5)) {
+            let lower = values.partition_point(|value| value < key);
+            assert_eq!(lower, packed.lower_bound(*key));
+            assert_eq!(values.binary_search(key).is_ok(), packed.contains(*key));
+        }
+    }
+
+    #[test]
+    fn packed_vec_compresses() {
+        let ids: Vec<u32> = (0..100_000).map(|id| id * 3 + id % 2).collect();
+        let packed = SimdPackedVec::new(&ids);
+        assert_eq!(ids.len().div_ceil(128), packed.block_count());
+        // Gaps of 3 or 4 pack into 3 bits, against 32 for the raw values.
+        assert!(packed.size_in_bytes() * 8 < ids.len() * 4);
+        assert!(packed.contains(30_000));
+        assert!(!packed.contains(30_001));
+        assert_eq!(10_000, packed.lower_bound(30_000));
+        assert_eq!(ids.len(), packed.lower_bound(u32::MAX));
+        let empty = SimdPackedVec::new(&[]);
+        assert!(empty.is_empty());
+        assert_eq!(0, empty.lower_bound(5));
+        assert!(!empty.contains(0));
+    }
+
+    fn simdify_elias_fano(mut values: Vec<u64>, keys: Vec<u64>) {
+        values.sort_unstable();
+        let vec: SimdVec<u64> = values.as_slice().into();
+        let encoded = SimdEliasFano::from(&vec);
+        assert_eq!(values.len(), encoded.len());
+        assert_eq!(vec, encoded.to_vec());
+        for (index, value) in values.iter().enumerate() {
+            assert_eq!(Some(*value), encoded.get(index));
+        }
+        assert_eq!(None, encoded.get(values.len()));
+        for key in keys.iter().chain(&values) {
+            let lower = values.partition_point(|value| value < key);
+            assert_eq!(lower, encoded.lower_bound(*key));
+            assert_eq!(values.binary_search(key).is_ok(), encoded.contains(*key));
+        }
+    }
+
+    #[test]
+    fn elias_fano_sparse() {
+        let offsets: Vec<u64> = (0..10_000u64)
+            .map(|index| index * index * 1_000 + index % 3)
+            .collect();
+        let encoded = SimdEliasFano::new(&offsets);
+        assert!(encoded.size_in_bytes() < offsets.len() * 8 / 2);
+        assert_eq!(Some(offsets[9_999]), encoded.get(9_999));
+        assert_eq!(5_000, encoded.lower_bound(offsets[5_000]));
+        assert_eq!(5_001, encoded.lower_bound(offsets[5_000] + 1));
+        assert_eq!(10_000, encoded.lower_bound(u64::MAX));
+        let extremes = SimdEliasFano::new(&[0, 0, u64::MAX, u64::MAX]);
+        assert_eq!(
+            vec![0, 0, u64::MAX, u64::MAX],
+            extremes.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(2, extremes.lower_bound(1));
+        let empty = SimdEliasFano::new(&[]);
+        assert_eq!(0, empty.lower_bound(0));
+        assert_eq!(None, empty.get(0));
+    }
+
+    fn simdify_varint<A>(values: Vec<A>)
+    where
+        A: Varint + Debug + PartialEq,
+    {
+        let vec: SimdVec<A> = values.as_slice().into();
+        let leb128 = varint::encode_leb128(&values);
+        assert_eq!(Ok(vec.clone()), varint::decode_leb128(&leb128));
+        let stream = varint::encode_stream_vbyte(&values);
+        for ssse3 in [false, true] {
+            assert_eq!(
+                Ok(vec.clone()),
+                varint::decode_stream_vbyte(&stream, values.len(), ssse3)
+            );
+            if !values.is_empty() {
+                let truncated = &stream[..stream.len() - 1];
+                assert!(matches!(
+                    varint::decode_stream_vbyte::<A>(truncated, values.len(), ssse3),
+                    Err(VarintError::Truncated { .. })
+                ));
+            }
+        }
+        if leb128
+            .last()
+            .is_some_and(|_| leb128.len() > 1 && leb128[leb128.len() - 2] & 0x80 != 0)
+        {
+            assert_eq!(
+                Err(VarintError::Truncated {
+                    offset: leb128.len() - byte_len_leb128(values[values.len() - 1].into_u64()),
+                }),
+                varint::decode_leb128::<A>(&leb128[..leb128.len() - 1])
+            );
+        }
+    }
+
+    fn byte_len_leb128(value: u64) -> usize {
+        std::cmp::max(1, (64 - value.leading_zeros() as usize).div_ceil(7))
+    }
+
+    #[test]
+    fn varint_bytes() {
+        let values: SimdVec<u32> = [0, 1, 127, 128, 300, u32::MAX][..].into();
+        let bytes = values.to_varint_bytes();
+        assert_eq!(
+            vec![0, 1, 0x7f, 0x80, 0x01, 0xac, 0x02, 0xff, 0xff, 0xff, 0xff, 0x0f],
+            bytes
+        );
+        assert_eq!(
+            Ok(values.clone()),
+            SimdVec::<u32>::from_varint_bytes(&bytes)
+        );
+        assert_eq!(
+            Err(VarintError::Truncated { offset: 7 }),
+            SimdVec::<u32>::from_varint_bytes(&bytes[..10])
+        );
+        // A fifth byte with more than four bits, and a sixth byte.
+        assert_eq!(
+            Err(VarintError::Overflow { offset: 1 }),
+            SimdVec::<u32>::from_varint_bytes(&[0, 0xff, 0xff, 0xff, 0xff, 0x1f])
+        );
+        assert_eq!(
+            Err(VarintError::Overflow { offset: 0 }),
+            SimdVec::<u32>::from_varint_bytes(&[0x80, 0x80, 0x80, 0x80, 0x80, 0])
+        );
+        assert_eq!(
+            Ok([u64::MAX][..].into()),
+            SimdVec::<u64>::from_varint_bytes(
+                &[0xff; 9].iter().copied().chain([1]).collect::<Vec<_>>()
+            )
+        );
+        assert_eq!(
+            Err(VarintError::Overflow { offset: 16 }),
+            SimdVec::<u64>::from_varint_bytes(
+                &[0; 16]
+                    .iter()
+                    .copied()
+                    .chain([0xff; 20])
+                    .collect::<Vec<_>>()
+            )
+        );
+
+        let stream = values.to_stream_vbyte();
+        // Lengths 1, 1, 1, 1 and 2, 4, then the data.
+        assert_eq!(vec![0, 0b11_01], stream[..2].to_vec());
+        assert_eq!(2 + 1 + 1 + 1 + 1 + 2 + 4, stream.len());
+        assert_eq!(
+            Ok(values.clone()),
+            SimdVec::<u32>::from_stream_vbyte(&stream, 6)
+        );
+        assert_eq!(
+            Err(VarintError::Truncated { offset: 8 }),
+            SimdVec::<u32>::from_stream_vbyte(&stream[..11], 6)
+        );
+        assert_eq!(
+            Err(VarintError::Truncated { offset: 1 }),
+            SimdVec::<u32>::from_stream_vbyte(&stream[..1], 6)
+        );
+        // Four bit length codes past eight bytes can't be a `u64`.
+        assert_eq!(
+            Err(VarintError::Overflow { offset: 1 }),
+            SimdVec::<u64>::from_stream_vbyte(&[0x08; 17], 1)
+        );
+        assert_eq!(
+            "input ends inside the varint at byte 7",
+            VarintError::Truncated { offset: 7 }.to_string()
+        );
+    }
+
+    fn simdify_scan<A>(values: Vec<A>)
+    where
+        A: ScanInt + Debug + PartialEq,
+    {
+        for op in [ScanOp::Inclusive, ScanOp::Exclusive, ScanOp::Delta] {
+            let mut expected = values.clone();
+            scan::scan_scalar(&mut expected, op, A::default_zero());
+            let mut sse2 = values.clone();
+            unsafe { scan::scan_sse2(&mut sse2, op) };
+            assert_eq!(expected, sse2);
+            if is_x86_feature_detected!("avx2") {
+                let mut avx2 = values.clone();
+                unsafe { scan::scan_avx2(&mut avx2, op) };
+                assert_eq!(expected, avx2);
+            }
+        }
+        let mut round_trip = values.clone();
+        scan::scan(&mut round_trip, ScanOp::Delta);
+        scan::scan(&mut round_trip, ScanOp::Inclusive);
+        assert_eq!(values, round_trip);
+    }
+
+    #[test]
+    fn prefix_sums() {
+        let mut vec: SimdVec<u32> = (1..=20).collect::<Vec<_>>().as_slice().into();
+        vec.prefix_sum();
+        assert_eq!(
+            (1..=20u32).map(|n| n * (n + 1) / 2).collect::<Vec<_>>(),
+            vec.to_vec()
+        );
+        vec.delta_encode();
+        assert_eq!((1..=20).collect::<Vec<_>>(), vec.to_vec());
+        vec.exclusive_prefix_sum();
+        assert_eq!(
+            (0..20u32).map(|n| n * (n + 1) / 2).collect::<Vec<_>>(),
+            vec.to_vec()
+        );
+
+        let mut offsets: SimdVec<i64> = [3, -1, 4, -1, 5][..].into();
+        offsets.checked_exclusive_prefix_sum().unwrap();
+        assert_eq!(vec![0, 3, 2, 6, 5], offsets.to_vec());
+        offsets.checked_delta_encode().unwrap();
+        assert_eq!(vec![0, 3, -1, 4, -1], offsets.to_vec());
+        offsets.checked_delta_decode().unwrap();
+        assert_eq!(vec![0, 3, 2, 6, 5], offsets.to_vec());
+
+        let mut overflowing: SimdVec<u32> = [1, 2, u32::MAX, 3][..].into();
+        let original = overflowing.clone();
+        assert_eq!(
+            Err(OverflowError { index: 2 }),
+            overflowing.checked_prefix_sum()
+        );
+        assert_eq!(
+            Err(OverflowError { index: 3 }),
+            overflowing.checked_exclusive_prefix_sum()
+        );
+        assert_eq!(
+            Err(OverflowError { index: 3 }),
+            overflowing.checked_delta_encode()
+        );
+        assert_eq!(original, overflowing);
+        overflowing.prefix_sum();
+        assert_eq!(vec![1, 3, 2, 5], overflowing.to_vec());
+        // The total of every element isn't part of an exclusive sum.
+        let mut last: SimdVec<i32> = [i32::MAX, 1][..].into();
+        last.checked_exclusive_prefix_sum().unwrap();
+        assert_eq!(vec![0, i32::MAX], last.to_vec());
+        let mut signed: SimdVec<i32> = [i32::MIN, i32::MAX][..].into();
+        assert_eq!(
+            Err(OverflowError { index: 1 }),
+            signed.checked_delta_encode()
+        );
+        signed.delta_encode();
+        assert_eq!(vec![i32::MIN, -1], signed.to_vec());
+    }
+
+    proptest! {
+        #[test]
+        fn plain_binary_search_present(items in sorted_vec(num::i8::ANY, 1..1024), index in num::usize::ANY) {
+            let index = index % items.len();
+            let item = items[index];
+            assert_eq!(Ok(index), items.binary_search(&item))
+        }
+
+        #[test]
+        fn plain_binary_search_any(items in sorted_vec(num::i8::ANY, 1..1024), key in num::i8::ANY) {
+            match items.binary_search(&key) {
+                Ok(index) => assert_eq!(items[index], key),
+                Err(index) => {
+                    if index < items.len() {
+                        assert!(items[index] > key, "insert index value {} should be higher than search key {}", items[index], key);
+                    }
+                    if index > 0 {
+                        assert!(items[index-1] < key, "pre-insert index value {} should be lower than search key {}", items[index-1], key);
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn simdify_k_ary_search_present_i8_128(items in sorted_vec(num::i8::ANY, 1..1024), index in num::usize::ANY) {
+            simdify_k_ary_search_present_128(items, index)
+        }
+
+        #[test]
+        fn simdify_k_ary_search_any_i8_128(items in sorted_vec(num::i8::ANY, 1..1024), key in num::i8::ANY) {
+            simdify_k_ary_search_any_128(items,key)
+        }
+
+        #[test]
+        fn simdify_k_ary_search_present_i16_128(items in sorted_vec(num::i16::ANY, 1..512), index in num::usize::ANY) {
+            simdify_k_ary_search_present_128(items, index)
+        }
+
+        #[test]
+        fn simdify_k_ary_search_any_i16_128(items in sorted_vec(num::i16::ANY, 1..512), key in num::i16::ANY) {
+            simdify_k_ary_search_any_128(items,key)
+        }
+
+        #[test]
+        fn simdify_k_ary_search_present_i32_128(items in sorted_vec(num::i32::ANY, 1..256), index in num::usize::ANY) {
+            simdify_k_ary_search_present_128(items, index)
+        }
+
+        #[test]
+        fn simdify_k_ary_search_any_i32_128(items in sorted_vec(num::i32::ANY, 1..256), key in num::i32::ANY) {
+            simdify_k_ary_search_any_128(items,key)
+        }
+
+        #[test]
+        fn simdify_k_ary_search_present_i64_128(items in sorted_vec(num::i64::ANY, 1..128), index in num::usize::ANY) {
+            simdify_k_ary_search_present_128(items, index)
+        }
+
+        #[test]
+        fn simdify_k_ary_search_any_i64_128(items in sorted_vec(num::i64::ANY, 1..128), key in num::i64::ANY) {
+            simdify_k_ary_search_any_128(items,key)
+        }
+
+        #[test]
+        fn simdify_k_ary_search_present_i8_256(items in sorted_vec(num::i8::ANY, 1..1024), index in num::usize::ANY) {
+            simdify_k_ary_search_present_256(items, index)
+        }
+
+        #[test]
+        fn simdify_k_ary_search_any_i8_256(items in sorted_vec(num::i8::ANY, 1..1024), key in num::i8::ANY) {
+            simdify_k_ary_search_any_256(items,key)
+        }
+
+        #[test]
+        fn simdify_k_ary_search_present_i16_256(items in sorted_vec(num::i16::ANY, 1..512), index in num::usize::ANY) {
+            simdify_k_ary_search_present_256(items, index)
+        }
+
+        #[test]
+        fn simdify_k_ary_search_any_i16_256(items in sorted_vec(num::i16::ANY, 1..512), key in num::i16::ANY) {
+            simdify_k_ary_search_any_256(items,key)
+        }
+
+        #[test]
+        fn simdify_k_ary_search_present_i32_256(items in sorted_vec(num::i32::ANY, 1..256), index in num::usize::ANY) {
+            simdify_k_ary_search_present_256(items, index)
+        }
+
+        #[test]
+        fn simdify_k_ary_search_any_i32_256(items in sorted_vec(num::i32::ANY, 1..256), key in num::i32::ANY) {
+            simdify_k_ary_search_any_256(items,key)
+        }
+
+        #[test]
+        fn simdify_k_ary_search_present_i64_256(items in sorted_vec(num::i64::ANY, 1..128), index in num::usize::ANY) {
+            simdify_k_ary_search_present_256(items, index)
+        }
+
+        #[test]
+        fn simdify_k_ary_search_any_i64_256(items in sorted_vec(num::i64::ANY, 1..128), key in num::i64::ANY) {
+            simdify_k_ary_search_any_256(items,key)
+        }
+
+        #[test]
+        fn simdify_arith_i8_128(pairs in vec((num::i8::ANY, num::i8::ANY), 0..300), scalar in num::i8::ANY) {
+            simdify_arith::<__m128i, i8>(pairs, scalar)
+        }
+
+        #[test]
+        fn simdify_arith_i8_256(pairs in vec((num::i8::ANY, num::i8::ANY), 0..300), scalar in num::i8::ANY) {
+            simdify_arith::<__m256i, i8>(pairs, scalar)
+        }
+
+        #[test]
+        fn simdify_arith_i16_128(pairs in vec((num::i16::ANY, num::i16::ANY), 0..300), scalar in num::i16::ANY) {
+            simdify_arith::<__m128i, i16>(pairs, scalar)
+        }
+
+        #[test]
+        fn simdify_arith_i16_256(pairs in vec((num::i16::ANY, num::i16::ANY), 0..300), scalar in num::i16::ANY) {
+            simdify_arith::<__m256i, i16>(pairs, scalar)
+        }
+
+        #[test]
+        fn simdify_arith_i32_128(pairs in vec((num::i32::ANY, num::i32::ANY), 0..300), scalar in num::i32::ANY) {
+            simdify_arith::<__m128i, i32>(pairs, scalar)
+        }
+
+        #[test]
+        fn simdify_arith_i32_256(pairs in vec((num::i32::ANY, num::i32::ANY), 0..300), scalar in num::i32::ANY) {
+            simdify_arith::<__m256i, i32>(pairs, scalar)
+        }
+
+        #[test]
+        fn simdify_arith_i64_128(pairs in vec((num::i64::ANY, num::i64::ANY), 0..300), scalar in num::i64::ANY) {
+            simdify_arith::<__m128i, i64>(pairs, scalar)
+        }
+
+        #[test]
+        fn simdify_arith_i64_256(pairs in vec((num::i64::ANY, num::i64::ANY), 0..300), scalar in num::i64::ANY) {
+            simdify_arith::<__m256i, i64>(pairs, scalar)
+        }
+
+        #[test]
+        fn simdify_compare_i8(pairs in vec((num::i8::ANY, num::i8::ANY), 0..300), scalar in num::i8::ANY) {
+            simdify_compare(pairs, scalar)
+        }
+
+        #[test]
+        fn simdify_compare_i16(pairs in vec((num::i16::ANY, num::i16::ANY), 0..300), scalar in num::i16::ANY) {
+            simdify_compare(pairs, scalar)
+        }
+
+        #[test]
+        fn simdify_compare_i32(pairs in vec((num::i32::ANY, num::i32::ANY), 0..300), scalar in num::i32::ANY) {
+            simdify_compare(pairs, scalar)
+        }
+
+        #[test]
+        fn simdify_compare_i64(pairs in vec((num::i64::ANY, num::i64::ANY), 0..300), scalar in num::i64::ANY) {
+            simdify_compare(pairs, scalar)
+        }
+
+        #[test]
+        fn simdify_filter_i8(items in vec(num::i8::ANY, 0..300), low in num::i8::ANY, high in num::i8::ANY, selection in vec(proptest::bool::ANY, 0..300)) {
+            simdify_filter(items, low, high, selection)
+        }
+
+        #[test]
+        fn simdify_filter_i16(items in vec(num::i16::ANY, 0..300), low in num::i16::ANY, high in num::i16::ANY, selection in vec(proptest::bool::ANY, 0..300)) {
+            simdify_filter(items, low, high, selection)
+        }
+
+        #[test]
+        fn simdify_filter_i32(items in vec(num::i32::ANY, 0..300), low in num::i32::ANY, high in num::i32::ANY, selection in vec(proptest::bool::ANY, 0..300)) {
+            simdify_filter(items, low, high, selection)
+        }
+
+        #[test]
+        fn simdify_filter_i64(items in vec(num::i64::ANY, 0..300), low in num::i64::ANY, high in num::i64::ANY, selection in vec(proptest::bool::ANY, 0..300)) {
+            simdify_filter(items, low, high, selection)
+        }
+
+        #[test]
+        fn simdify_sort_i8(items in vec(num::i8::ANY, 0..2048)) {
+            simdify_sort(items)
+        }
+
+        #[test]
+        fn simdify_sort_i16(items in vec(num::i16::ANY, 0..1024)) {
+            simdify_sort(items)
+        }
+
+        #[test]
+        fn simdify_sort_i32(items in vec(num::i32::ANY, 0..1024)) {
+            simdify_sort(items)
+        }
+
+        #[test]
+        fn simdify_sort_i64(items in vec(num::i64::ANY, 0..1024)) {
+            simdify_sort(items)
+        }
+
+        #[test]
+        fn simdify_sort_u8(items in vec(num::u8::ANY, 0..2048)) {
+            simdify_sort(items)
+        }
+
+        #[test]
+        fn simdify_sort_u16(items in vec(num::u16::ANY, 0..1024)) {
+            simdify_sort(items)
+        }
+
+        #[test]
+        fn simdify_sort_u32(items in vec(num::u32::ANY, 0..1024)) {
+            simdify_sort(items)
+        }
+
+        #[test]
+        fn simdify_sort_u64(items in vec(num::u64::ANY, 0..1024)) {
+            simdify_sort(items)
+        }
+
+        #[test]
+        fn simdify_sort_usize(items in vec(num::usize::ANY, 0..1024)) {
+            simdify_sort(items)
+        }
+
+        #[test]
+        fn simdify_sort_i128(items in vec(num::i128::ANY, 0..512)) {
+            simdify_sort(items)
+        }
+
+        #[test]
+        fn simdify_sort_stamped_u8(items in vec(num::u8::ANY, 0..1024)) {
+            simdify_sort_stamped(items)
+        }
+
+        #[test]
+        fn simdify_sort_stamped_i16(items in vec(num::i16::ANY, 0..1024)) {
+            simdify_sort_stamped(items)
+        }
+
+        #[test]
+        fn simdify_sort_stamped_u32(items in vec(num::u32::ANY, 0..1024)) {
+            simdify_sort_stamped(items)
+        }
+
+        #[test]
+        fn simdify_sort_stamped_i64(items in vec(num::i64::ANY, 0..1024)) {
+            simdify_sort_stamped(items)
+        }
+
+        #[test]
+        fn simdify_sort_stamped_u64(items in vec(num::u64::ANY, 0..1024)) {
+            simdify_sort_stamped(items)
+        }
+
+        #[test]
+        fn simdify_sort_u128(items in vec(num::u128::ANY, 0..512)) {
+            simdify_sort(items)
+        }
+
+        #[test]
+        fn simdify_radix_sort_i8(items in vec(num::i8::ANY, 0..1024)) {
+            simdify_radix_sort(items, &mut SimdVec::new())
+        }
+
+        #[test]
+        fn simdify_radix_sort_i16(items in vec(num::i16::ANY, 0..1024)) {
+            simdify_radix_sort(items, &mut SimdVec::new())
+        }
+
+        #[test]
+        fn simdify_radix_sort_i32(items in vec(num::i32::ANY, 0..1024)) {
+            simdify_radix_sort(items, &mut SimdVec::new())
+        }
+
+        #[test]
+        fn simdify_radix_sort_i64(items in vec(num::i64::ANY, 0..1024)) {
+            simdify_radix_sort(items, &mut SimdVec::new())
+        }
+
+        #[test]
+        fn simdify_radix_sort_u32(items in vec(num::u32::ANY, 0..1024)) {
+            simdify_radix_sort(items, &mut SimdVec::new())
+        }
+
+        #[test]
+        fn simdify_radix_sort_u64(items in vec(num::u64::ANY, 0..1024)) {
+            simdify_radix_sort(items, &mut SimdVec::new())
+        }
+
+        #[test]
+        fn simdify_set_ops_i8(left in sorted_vec(num::i8::ANY, 0..200), right in sorted_vec(num::i8::ANY, 0..200)) {
+            simdify_set_ops(left, right)
+        }
+
+        #[test]
+        fn simdify_set_ops_i64(left in sorted_vec(-500i64..500, 0..300), right in sorted_vec(-500i64..500, 0..300)) {
+            simdify_set_ops(left, right)
+        }
+
+        #[test]
+        fn simdify_set_ops_u32(left in sorted_vec(0u32..1000, 0..500), right in sorted_vec(0u32..1000, 0..500)) {
+            simdify_set_ops(left, right)
+        }
+
+        #[test]
+        fn simdify_set_ops_u32_unbalanced(left in sorted_vec(0u32..5000, 0..20), right in sorted_vec(0u32..5000, 0..2000)) {
+            simdify_set_ops(left.clone(), right.clone());
+            simdify_set_ops(right, left)
+        }
+
+        #[test]
+        fn simdify_set_ops_u64(left in sorted_vec(num::u64::ANY, 0..100), right in sorted_vec(num::u64::ANY, 0..100)) {
+            simdify_set_ops(left, right)
+        }
+
+        #[test]
+        fn simdify_k_ary_search_range_u16_128(items in sorted_vec(num::u16::ANY, 0..512), key in num::u16::ANY, start in num::usize::ANY, end in num::usize::ANY) {
+            simdify_k_ary_search_range::<__m128i, _>(items, key, start, end)
+        }
+
+        #[test]
+        fn simdify_k_ary_search_range_u32_256(items in sorted_vec(0u32..1000, 0..256), key in 0u32..1000, start in num::usize::ANY, end in num::usize::ANY) {
+            simdify_k_ary_search_range::<__m256i, _>(items, key, start, end)
+        }
+
+        #[test]
+        fn simdify_merge_i8(left in vec(num::i8::ANY, 0..300), right in vec(num::i8::ANY, 0..300)) {
+            simdify_merge(vec![left, right])
+        }
+
+        #[test]
+        fn simdify_merge_i16(left in vec(num::i16::ANY, 0..300), right in vec(num::i16::ANY, 0..300)) {
+            simdify_merge(vec![left, right])
+        }
+
+        #[test]
+        fn simdify_merge_i32(left in vec(-50i32..50, 0..300), right in vec(-50i32..50, 0..300)) {
+            simdify_merge(vec![left, right])
+        }
+
+        #[test]
+        fn simdify_merge_i64(left in vec(num::i64::ANY, 0..300), right in vec(num::i64::ANY, 0..300)) {
+            simdify_merge(vec![left, right])
+        }
+
+        #[test]
+        fn simdify_merge_u8(left in vec(num::u8::ANY, 0..300), right in vec(num::u8::ANY, 0..300)) {
+            simdify_merge(vec![left, right])
+        }
+
+        #[test]
+        fn simdify_merge_u16(left in vec(num::u16::ANY, 0..300), right in vec(num::u16::ANY, 0..300)) {
+            simdify_merge(vec![left, right])
+        }
+
+        #[test]
+        fn simdify_merge_u32(left in vec(num::u32::ANY, 0..300), right in vec(num::u32::ANY, 0..300)) {
+            simdify_merge(vec![left, right])
+        }
+
+        #[test]
+        fn simdify_merge_stamped_u16(left in vec(num::u16::ANY, 0..300), right in vec(num::u16::ANY, 0..300)) {
+            simdify_merge_stamped(left, right)
+        }
+
+        #[test]
+        fn simdify_merge_stamped_u64(left in vec(num::u64::ANY, 0..300), right in vec(num::u64::ANY, 0..300)) {
+            simdify_merge_stamped(left, right)
+        }
+
+        #[test]
+        fn simdify_merge_u64(left in vec(num::u64::ANY, 0..300), right in vec(num::u64::ANY, 0..300)) {
+            simdify_merge(vec![left, right])
+        }
+
+        #[test]
+        fn simdify_merge_u128(left in vec(num::u128::ANY, 0..100), right in vec(num::u128::ANY, 0..100)) {
+            simdify_merge(vec![left, right])
+        }
+
+        #[test]
+        fn simdify_merge_many_u32(runs in vec(vec(num::u32::ANY, 0..100), 0..10)) {
+            simdify_merge(runs)
+        }
+
+        #[test]
+        fn simdify_merge_many_i64(runs in vec(vec(-100i64..100, 0..100), 0..10)) {
+            simdify_merge(runs)
+        }
+
+        #[test]
+        fn simdify_gallop_i8(items in sorted_vec(num::i8::ANY, 0..256), key in num::i8::ANY, hint in 0usize..300) {
+            simdify_gallop(items, key, hint)
+        }
+
+        #[test]
+        fn simdify_gallop_i32(items in sorted_vec(num::i32::ANY, 0..1024), key in num::i32::ANY, hint in 0usize..1100) {
+            simdify_gallop(items, key, hint)
+        }
+
+        #[test]
+        fn simdify_gallop_u64(items in sorted_vec(0u64..2000, 0..1024), key in 0u64..2000, hint in 0usize..1100) {
+            simdify_gallop(items, key, hint)
+        }
+
+        #[test]
+        fn simdify_interpolation_search_i8(items in sorted_vec(num::i8::ANY, 0..256), key in num::i8::ANY) {
+            simdify_interpolation_search(items, key)
+        }
+
+        #[test]
+        fn simdify_interpolation_search_i32(items in sorted_vec(num::i32::ANY, 0..1024), key in num::i32::ANY) {
+            simdify_interpolation_search(items, key)
+        }
+
+        #[test]
+        fn simdify_interpolation_search_u64(items in sorted_vec(num::u64::ANY, 0..1024), key in num::u64::ANY) {
+            simdify_interpolation_search(items, key)
+        }
+
+        #[test]
+        fn simdify_interpolation_search_u64_present(items in sorted_vec(num::u64::ANY, 1..1024), index in num::usize::ANY) {
+            let key = items[index % items.len()];
+            simdify_interpolation_search(items, key)
+        }
+
+        #[test]
+        fn simdify_interpolation_search_i128(items in sorted_vec(num::i128::ANY, 0..1024), key in num::i128::ANY) {
+            simdify_interpolation_search(items, key)
+        }
+
+        #[test]
+        fn simdify_interpolation_search_i128_present(items in sorted_vec(num::i128::ANY, 1..1024), index in num::usize::ANY) {
+            let key = items[index % items.len()];
+            simdify_interpolation_search(items, key)
+        }
+
+        #[test]
+        fn simdify_interpolation_search_u128(items in sorted_vec(num::u128::ANY, 0..1024), key in num::u128::ANY) {
+            simdify_interpolation_search(items, key)
+        }
+
+        #[test]
+        fn simdify_learned_index_i16(items in sorted_vec(num::i16::ANY, 0..1024), keys in vec(num::i16::ANY, 0..32), epsilon in 0usize..32) {
+            simdify_learned_index(items, keys, epsilon)
+        }
+
+        #[test]
+        fn simdify_learned_index_i64(items in sorted_vec(num::i64::ANY, 0..1024), keys in vec(num::i64::ANY, 0..32), epsilon in 0usize..32) {
+            simdify_learned_index(items, keys, epsilon)
+        }
+
+        #[test]
+        fn simdify_learned_index_i128(items in sorted_vec(num::i128::ANY, 0..1024), keys in vec(num::i128::ANY, 0..32), epsilon in 0usize..32) {
+            simdify_learned_index(items, keys, epsilon)
+        }
+
+        #[test]
+        fn simdify_learned_index_u64_duplicates(mut items in vec(0u64..300, 0..1024), keys in vec(0u64..300, 0..32), epsilon in 0usize..32) {
+            items.sort();
+            let vector: SimdVec<u64> = items.as_slice().into();
+            let index = SimdLearnedIndex::new(vector, epsilon);
+            for key in keys {
+                let lower = items.partition_point(|item| *item < key);
+                assert_eq!(lower, index.lower_bound(key));
+                match index.search(key) {
+                    Ok(found) => assert_eq!((lower, key), (found, items[found])),
+                    Err(insert) => assert_eq!(Err(insert), items.binary_search(&key)),
+                }
+            }
+        }
+
+        #[test]
+        fn simdify_rank_i8(items in vec(num::i8::ANY, 0..300), keys in vec(num::i8::ANY, 0..16)) {
+            simdify_rank(items, keys)
+        }
+
+        #[test]
+        fn simdify_rank_i32(items in vec(-100i32..100, 0..300), keys in vec(-110i32..110, 0..16)) {
+            simdify_rank(items, keys)
+        }
+
+        #[test]
+        fn simdify_rank_u64(items in vec(num::u64::ANY, 0..300), keys in vec(num::u64::ANY, 0..16)) {
+            simdify_rank(items, keys)
+        }
+
+        #[test]
+        fn simdify_rank_branchless_i16(items in vec(num::i16::ANY, 0..32), key in num::i16::ANY) {
+            simdify_rank_branchless::<_, typenum::U1>(items.clone(), key);
+            simdify_rank_branchless::<_, typenum::U2>(items, key)
+        }
+
+        #[test]
+        fn simdify_rank_branchless_u32(items in vec(0u32..50, 0..16), key in 0u32..50) {
+            simdify_rank_branchless::<_, typenum::U1>(items.clone(), key);
+            simdify_rank_branchless::<_, typenum::U2>(items, key)
+        }
+
+        #[test]
+        fn simdify_neighbours_i8(items in vec(num::i8::ANY, 0..300), keys in vec(num::i8::ANY, 0..16)) {
+            simdify_neighbours(items, keys)
+        }
+
+        #[test]
+        fn simdify_neighbours_i32(items in vec(-100i32..100, 0..300), keys in vec(-110i32..110, 0..16)) {
+            simdify_neighbours(items, keys)
+        }
+
+        #[test]
+        fn simdify_neighbours_u64(items in vec(num::u64::ANY, 0..300), keys in vec(num::u64::ANY, 0..16)) {
+            simdify_neighbours(items, keys)
+        }
+
+        #[test]
+        fn simdify_columns_u32(keys in vec(0u32..100, 0..300)) {
+            simdify_columns(keys.into_iter().enumerate().map(|(index, key)| (key, index)).collect())
+        }
+
+        #[test]
+        fn simdify_columns_i64(keys in vec(num::i64::ANY, 0..300)) {
+            simdify_columns(keys.into_iter().enumerate().map(|(index, key)| (key, index)).collect())
+        }
+
+        #[test]
+        fn simdify_composite_u32_u64(items in vec((0u32..8, 0u64..16), 0..300), keys in vec((0u32..9, 0u64..17), 0..16)) {
+            simdify_composite(items, keys)
+        }
+
+        #[test]
+        fn simdify_composite_i8_i32(items in vec((num::i8::ANY, -20i32..20), 0..300), keys in vec((num::i8::ANY, -20i32..20), 0..16)) {
+            simdify_composite(items, keys)
+        }
+
+        #[test]
+        fn simdify_k_ary_search_present_i128_128(items in btree_set(boundary_i128(), 1..128), index in num::usize::ANY) {
+            simdify_k_ary_search_present_128(items.into_iter().collect(), index)
+        }
+
+        #[test]
+        fn simdify_k_ary_search_any_i128_128(items in btree_set(boundary_i128(), 1..128), key in boundary_i128()) {
+            simdify_k_ary_search_any_128(items.into_iter().collect(), key)
+        }
+
+        #[test]
+        fn simdify_k_ary_search_present_i128_256(items in btree_set(boundary_i128(), 1..128), index in num::usize::ANY) {
+            simdify_k_ary_search_present_256(items.into_iter().collect(), index)
+        }
+
+        #[test]
+        fn simdify_k_ary_search_any_i128_256(items in btree_set(boundary_i128(), 1..128), key in boundary_i128()) {
+            simdify_k_ary_search_any_256(items.into_iter().collect(), key)
+        }
+
+        #[test]
+        fn simdify_compare_i128(pairs in vec((boundary_i128(), boundary_i128()), 0..100), scalar in boundary_i128()) {
+            simdify_compare(pairs, scalar)
+        }
+
+        #[test]
+        fn simdify_k_ary_search_present_u128_128(items in btree_set(boundary_i128().prop_map(|value| value as u128), 1..128), index in num::usize::ANY) {
+            simdify_k_ary_search_present_128(items.into_iter().collect(), index)
+        }
+
+        #[test]
+        fn simdify_k_ary_search_any_u128_128(items in btree_set(boundary_i128().prop_map(|value| value as u128), 1..128), key in boundary_i128().prop_map(|value| value as u128)) {
+            simdify_k_ary_search_any_128(items.into_iter().collect(), key)
+        }
+
+        #[test]
+        fn simdify_k_ary_search_present_u128_256(items in btree_set(boundary_i128().prop_map(|value| value as u128), 1..128), index in num::usize::ANY) {
+            simdify_k_ary_search_present_256(items.into_iter().collect(), index)
+        }
+
+        #[test]
+        fn simdify_k_ary_search_any_u128_256(items in btree_set(boundary_i128().prop_map(|value| value as u128), 1..128), key in boundary_i128().prop_map(|value| value as u128)) {
+            simdify_k_ary_search_any_256(items.into_iter().collect(), key)
+        }
+
+        #[test]
+        fn simdify_compare_u128(pairs in vec((boundary_i128().prop_map(|value| value as u128), boundary_i128().prop_map(|value| value as u128)), 0..100), scalar in boundary_i128().prop_map(|value| value as u128)) {
+            simdify_compare(pairs, scalar)
+        }
+
+        #[test]
+        fn simdify_str_index_bytes(strings in vec(vec(0u8..4, 0..12), 0..200), keys in vec(vec(0u8..4, 0..12), 0..16)) {
+            simdify_str_index(strings, keys)
+        }
+
+        #[test]
+        fn simdify_str_index_any(strings in vec(vec(num::u8::ANY, 0..20), 0..200), keys in vec(vec(num::u8::ANY, 0..20), 0..16)) {
+            simdify_str_index(strings, keys)
+        }
+
+        #[test]
+        fn simdify_art_small(ops in vec((proptest::bool::ANY, vec(0u8..3, 0..6)), 0..300), keys in vec(vec(0u8..3, 0..6), 0..8)) {
+            simdify_art(ops, keys)
+        }
+
+        #[test]
+        fn simdify_art_wide(ops in vec((proptest::bool::weighted(0.7), vec(num::u8::ANY, 0..4)), 0..600), keys in vec(vec(num::u8::ANY, 0..4), 0..8)) {
+            simdify_art(ops, keys)
+        }
+
+        #[test]
+        fn simdify_hash_map_random(ops in vec((num::u8::ANY, 0u16..512), 0..2000)) {
+            simdify_hash_map(&mut SimdHashMap::new(), ops)
+        }
+
+        #[test]
+        fn simdify_hash_map_colliding(ops in vec((num::u8::ANY, 0u16..64), 0..300)) {
+            simdify_hash_map(&mut SimdHashMap::with_hasher(BuildHasherDefault::<CollidingHasher>::default()), ops)
+        }
+
+        #[test]
+        fn simdify_hash_map_group_match(bytes in vec(num::u8::ANY, 16), byte in num::u8::ANY) {
+            let mut group = hash_map::Group([0; 16]);
+            group.0.copy_from_slice(&bytes);
+            unsafe {
+                assert_eq!(group.match_byte_scalar(byte), group.match_byte_simd(byte));
+                assert_eq!(group.match_free_scalar(), group.match_free_simd());
+            }
+        }
+
+        #[test]
+        fn simdify_bloom_filter(items in vec(num::u64::ANY, 0..2000), queries in vec(num::u64::ANY, 0..200), seed in num::u64::ANY) {
+            simdify_bloom(items, queries, seed)
+        }
+
+        #[test]
+        fn simdify_bloom_block(words in vec(num::u32::ANY, 8), key in num::u32::ANY) {
+            if is_x86_feature_detected!("avx2") {
+                let mut scalar = __m256i::default_zero();
+                unsafe {
+                    std::slice::from_raw_parts_mut(&mut scalar as *mut __m256i as *mut u32, 8).copy_from_slice(&words);
+                }
+                let mut simd = scalar;
+                unsafe {
+                    assert_eq!(bloom::contains_scalar(&scalar, key), bloom::contains_avx2(&simd, key));
+                    bloom::insert_scalar(&mut scalar, key);
+                    bloom::insert_avx2(&mut simd, key);
+                    assert!(bloom::contains_avx2(&simd, key));
+                }
+                let bytes = |block: &__m256i| unsafe { std::slice::from_raw_parts(block as *const __m256i as *const u32, 8).to_vec() };
+                assert_eq!(bytes(&scalar), bytes(&simd));
+            }
+        }
+
+        #[test]
+        fn simdify_cuckoo_filter(ops in vec((proptest::bool::weighted(0.7), 0u16..600), 0..1500), seed in num::u64::ANY) {
+            simdify_cuckoo(ops, seed)
+        }
+
+        #[test]
+        fn simdify_bitvec_ops(left in vec(proptest::bool::ANY, 0..2000), right in vec(proptest::bool::ANY, 0..2000), range in (num::usize::ANY, num::usize::ANY).prop_map(|(start, end)| start..end), value in proptest::bool::ANY) {
+            simdify_bitvec(left, right, range, value)
+        }
+
+        #[test]
+        fn simdify_bitvec_sparse(left in vec(proptest::bool::weighted(0.02), 0..5000), right in vec(proptest::bool::weighted(0.5), 0..5000), range in (num::usize::ANY, num::usize::ANY).prop_map(|(start, end)| start..end), value in proptest::bool::ANY) {
+            simdify_bitvec(left, right, range, value)
+        }
+
+        #[test]
+        fn simdify_packed_any(values in vec(num::u32::ANY, 0..1000), keys in vec(num::u32::ANY, 0..32)) {
+            simdify_packed(values, keys)
+        }
+
+        #[test]
+        fn simdify_packed_dense(values in vec(0u32..2000, 0..1000), keys in vec(0u32..2100, 0..32)) {
+            simdify_packed(values, keys)
+        }
+
+        #[test]
+        fn simdify_packed_unpack(deltas in vec(num::u32::ANY, 128), width in 0u32..=32, base in num::u32::ANY) {
+            let mask = if width == 32 { u32::MAX } else { (1 << width) - 1 };
+            let mut block = [0; 128];
+            for (delta, value) in block.iter_mut().zip(&deltas) {
+                *delta = value & mask;
+            }
+            let mut packed = Vec::new();
+            packed::pack(&block, width, &mut packed);
+            let mut scalar = packed::Decoded::default();
+            let mut simd = packed::Decoded::default();
+            packed::unpack_scalar(&packed, width, base, &mut scalar);
+            unsafe { packed::unpack_sse2(&packed, width, base, &mut simd) };
+            let mut expected = base;
+            for (index, delta) in block.iter().enumerate() {
+                expected = expected.wrapping_add(*delta);
+                assert_eq!(expected, scalar.0[index]);
+            }
+            assert_eq!(&scalar.0[..], &simd.0[..]);
+        }
+
+        #[test]
+        fn simdify_elias_fano_any(values in vec(num::u64::ANY, 0..1000), keys in vec(num::u64::ANY, 0..32)) {
+            simdify_elias_fano(values, keys)
+        }
+
+        #[test]
+        fn simdify_elias_fano_dense(values in vec(0u64..3000, 0..1500), keys in vec(0u64..3100, 0..32)) {
+            simdify_elias_fano(values, keys)
+        }
+
+        #[test]
+        fn simdify_elias_fano_clustered(values in vec((0u64..4, num::u64::ANY), 0..1000), keys in vec(num::u64::ANY, 0..32)) {
+            let values = values.into_iter().map(|(high, low)| (high << 60) | (low % 5000)).collect();
+            simdify_elias_fano(values, keys)
+        }
+
+        #[test]
+        fn simdify_varint_u32(values in vec(num::u32::ANY, 0..300)) {
+            simdify_varint(values)
+        }
+
+        #[test]
+        fn simdify_varint_u32_small(values in vec(0u32..300, 0..300)) {
+            simdify_varint(values)
+        }
+
+        #[test]
+        fn simdify_varint_u32_bytes(values in vec(0u32..128, 0..300), large in vec((0usize..300, num::u32::ANY), 0..3)) {
+            let mut values = values;
+            for (index, value) in large {
+                if index < values.len() {
+                    values[index] = value;
+                }
+            }
+            simdify_varint(values)
+        }
+
+        #[test]
+        fn simdify_varint_u64(values in vec(num::u64::ANY, 0..300)) {
+            simdify_varint(values)
+        }
+
+        #[test]
+        fn simdify_varint_u64_mixed(values in vec((0u32..64, num::u64::ANY), 0..300)) {
+            simdify_varint(values.into_iter().map(|(shift, value)| value >> shift).collect())
+        }
+
+        #[test]
+        fn simdify_scan_i32(values in vec(num::i32::ANY, 0..100)) {
+            simdify_scan(values)
+        }
+
+        #[test]
+        fn simdify_scan_u32(values in vec(num::u32::ANY, 0..100)) {
+            simdify_scan(values)
+        }
+
+        #[test]
+        fn simdify_scan_i64(values in vec(num::i64::ANY, 0..100)) {
+            simdify_scan(values)
+        }
+
+        #[test]
+        fn simdify_scan_u64(values in vec(num::u64::ANY, 0..100)) {
+            simdify_scan(values)
+        }
+
+        #[test]
+        fn simdify_checked_scan(values in vec(0u32..1 << 26, 0..100)) {
+            let mut vec: SimdVec<u32> = values.as_slice().into();
+            let total = values.iter().try_fold(0u32, |total, value| total.checked_add(*value));
+            assert_eq!(total.is_some(), vec.checked_prefix_sum().is_ok());
+            if total.is_some() {
+                assert_eq!(Ok(()), vec.checked_delta_encode());
+                assert_eq!(values, vec.to_vec());
+            }
         }
     }
 }
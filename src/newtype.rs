@@ -0,0 +1,124 @@
+// Items used by exported macros, which can't rely on the caller depending on
+// the same crates we do.
+#[doc(hidden)]
+pub mod __private {
+    pub use bitmaps::Bitmap;
+}
+
+/// Implement `SimdOps<__m128i>`, `SimdOps<__m256i>`, `SortKey` and
+/// `DefaultZero` for a `#[repr(transparent)]` newtype by delegating to the
+/// wrapped type.
+///
+/// The wrapper must be a tuple struct whose only non-zero-sized field is
+/// the first one. This is checked at compile time: the field must have the
+/// given type, and the wrapper must have the same size and alignment as it.
+/// The wrapper must implement `Ord`, and deriving it gives the same ordering
+/// as the SIMD comparisons.
+///
+/// ```
+/// use simdify::{simd_newtype, SimdArrayOps, SimdVec};
+///
+/// #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+/// #[repr(transparent)]
+/// struct UserId(u32);
+///
+/// simd_newtype!(UserId(u32));
+///
+/// let ids: SimdVec<UserId> = [UserId(1), UserId(5), UserId(9)][..].into();
+/// assert_eq!(Ok(1), ids.search(UserId(5)));
+/// ```
+///
+/// A wrapper which doesn't match the inner type is rejected:
+///
+/// ```compile_fail
+/// use simdify::simd_newtype;
+///
+/// #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// struct Padded(u32, u32);
+///
+/// simd_newtype!(Padded(u32));
+/// ```
+#[macro_export]
+macro_rules! simd_newtype {
+    ($wrapper:ident($inner:ty)) => {
+        const _: () = {
+            use ::std::arch::x86_64::{__m128i, __m256i};
+            use ::std::mem::{align_of, size_of};
+            use $crate::__private::Bitmap;
+            use $crate::{DefaultZero, SimdOps, SimdRegister, SortKey};
+
+            // The wrapper must hold the inner type and nothing more.
+            let _: fn($wrapper) -> $inner = |wrapper| wrapper.0;
+            assert!(
+                size_of::<$wrapper>() == size_of::<$inner>(),
+                "simd_newtype: wrapper and inner type differ in size"
+            );
+            assert!(
+                align_of::<$wrapper>() == align_of::<$inner>(),
+                "simd_newtype: wrapper and inner type differ in alignment"
+            );
+
+            impl SimdOps<__m128i> for $wrapper {
+                #[inline]
+                unsafe fn set(value: $wrapper) -> __m128i {
+                    <$inner as SimdOps<__m128i>>::set(value.0)
+                }
+
+                #[inline]
+                unsafe fn cmp_eq(
+                    left: __m128i,
+                    right: __m128i,
+                ) -> Bitmap<<__m128i as SimdRegister>::MovemaskSize> {
+                    <$inner as SimdOps<__m128i>>::cmp_eq(left, right)
+                }
+
+                #[inline]
+                unsafe fn cmp_gt(
+                    left: __m128i,
+                    right: __m128i,
+                ) -> Bitmap<<__m128i as SimdRegister>::MovemaskSize> {
+                    <$inner as SimdOps<__m128i>>::cmp_gt(left, right)
+                }
+            }
+
+            impl SimdOps<__m256i> for $wrapper {
+                #[inline]
+                unsafe fn set(value: $wrapper) -> __m256i {
+                    <$inner as SimdOps<__m256i>>::set(value.0)
+                }
+
+                #[inline]
+                unsafe fn cmp_eq(
+                    left: __m256i,
+                    right: __m256i,
+                ) -> Bitmap<<__m256i as SimdRegister>::MovemaskSize> {
+                    <$inner as SimdOps<__m256i>>::cmp_eq(left, right)
+                }
+
+                #[inline]
+                unsafe fn cmp_gt(
+                    left: __m256i,
+                    right: __m256i,
+                ) -> Bitmap<<__m256i as SimdRegister>::MovemaskSize> {
+                    <$inner as SimdOps<__m256i>>::cmp_gt(left, right)
+                }
+            }
+
+            impl SortKey for $wrapper {
+                const NETWORK: bool = <$inner as SortKey>::NETWORK;
+
+                #[inline]
+                unsafe fn min_max(left: __m256i, right: __m256i) -> (__m256i, __m256i) {
+                    <$inner as SortKey>::min_max(left, right)
+                }
+            }
+
+            // Zeroed memory is only a valid default if it is for the inner
+            // type.
+            fn inner_is_default_zero<T: DefaultZero>() {}
+            let _ = inner_is_default_zero::<$inner>;
+
+            impl DefaultZero for $wrapper {}
+        };
+    };
+}
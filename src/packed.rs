@@ -0,0 +1,372 @@
+use std::arch::x86_64 as arch;
+use std::arch::x86_64::__m128i;
+use std::fmt::{Debug, Error, Formatter};
+use std::marker::PhantomData;
+
+use crate::{SimdArrayOps, SimdVec};
+
+/// The number of values in a packed block.
+pub(crate) const BLOCK_LEN: usize = 128;
+const LANES: usize = 4;
+const ROWS: usize = BLOCK_LEN / LANES;
+
+/// A decoded block of values.
+#[derive(Clone, Copy)]
+#[repr(align(16))]
+pub(crate) struct Decoded(pub(crate) [u32; BLOCK_LEN]);
+
+impl Default for Decoded {
+    fn default() -> Self {
+        Decoded([0; BLOCK_LEN])
+    }
+}
+
+fn width_mask(width: u32) -> u32 {
+    if width == 32 {
+        u32::MAX
+    } else {
+        (1 << width) - 1
+    }
+}
+
+/// Bit-pack 128 deltas of at most `width` bits each, in the vertical
+/// layout: delta `i` goes to 32-bit lane `i % 4`, and each lane packs its
+/// 32 deltas into `width` words, least significant bits first.
+pub(crate) fn pack(deltas: &[u32; BLOCK_LEN], width: u32, out: &mut Vec<__m128i>) {
+    if width == 0 {
+        return;
+    }
+    let mut words = vec![0u32; width as usize * LANES];
+    for (index, delta) in deltas.iter().enumerate() {
+        let (row, lane) = (index / LANES, index % LANES);
+        let bit = row as u32 * width;
+        let (word, shift) = ((bit / 32) as usize, bit % 32);
+        words[word * LANES + lane] |= delta << shift;
+        if shift + width > 32 {
+            words[(word + 1) * LANES + lane] |= delta >> (32 - shift);
+        }
+    }
+    for chunk in words.chunks_exact(LANES) {
+        out.push(unsafe { arch::_mm_loadu_si128(chunk.as_ptr() as *const __m128i) });
+    }
+}
+
+/// Unpack a block and turn its deltas into values, starting from `base`.
+///
+/// Each row of four deltas is shifted out of the packed words with the
+/// same bit offset in every lane, then prefix summed in register: shifting
+/// the register left by one and two lanes and adding gives the running sum
+/// within the row, and the last lane is broadcast with a shuffle to carry
+/// into the next row.
+#[target_feature(enable = "sse2")]
+pub(crate) unsafe fn unpack_sse2(packed: &[__m128i], width: u32, base: u32, out: &mut Decoded) {
+    let mask = arch::_mm_set1_epi32(width_mask(width) as i32);
+    let mut carry = arch::_mm_set1_epi32(base as i32);
+    for row in 0..ROWS {
+        let mut deltas = arch::_mm_setzero_si128();
+        if width > 0 {
+            let bit = row as u32 * width;
+            let (word, shift) = ((bit / 32) as usize, bit % 32);
+            deltas = arch::_mm_srl_epi32(packed[word], arch::_mm_cvtsi32_si128(shift as i32));
+            if shift + width > 32 {
+                let high = arch::_mm_sll_epi32(
+                    packed[word + 1],
+                    arch::_mm_cvtsi32_si128((32 - shift) as i32),
+                );
+                deltas = arch::_mm_or_si128(deltas, high);
+            }
+            deltas = arch::_mm_and_si128(deltas, mask);
+        }
+        deltas = arch::_mm_add_epi32(deltas, arch::_mm_slli_si128(deltas, 4));
+        deltas = arch::_mm_add_epi32(deltas, arch::_mm_slli_si128(deltas, 8));
+        let values = arch::_mm_add_epi32(deltas, carry);
+        arch::_mm_store_si128(out.0.as_mut_ptr().add(row * LANES) as *mut __m128i, values);
+        carry = arch::_mm_shuffle_epi32(values, 0xff);
+    }
+}
+
+pub(crate) fn unpack_scalar(packed: &[__m128i], width: u32, base: u32, out: &mut Decoded) {
+    let words =
+        unsafe { std::slice::from_raw_parts(packed.as_ptr() as *const u32, packed.len() * LANES) };
+    let mut value = base;
+    for (index, out) in out.0.iter_mut().enumerate() {
+        let (row, lane) = (index / LANES, index % LANES);
+        let mut delta = 0;
+        if width > 0 {
+            let bit = row as u32 * width;
+            let (word, shift) = ((bit / 32) as usize, bit % 32);
+            delta = words[word * LANES + lane] >> shift;
+            if shift + width > 32 {
+                delta |= words[(word + 1) * LANES + lane] << (32 - shift);
+            }
+            delta &= width_mask(width);
+        }
+        value = value.wrapping_add(delta);
+        *out = value;
+    }
+}
+
+/// A compressed vector of sorted integers.
+///
+/// Values are split into blocks of 128. Each block stores the differences
+/// between consecutive values, bit-packed with just enough bits for its
+/// largest difference, in the vertical layout of SIMD-BP128 so that four
+/// values are unpacked at once. See ['Decoding billions of integers per
+/// second through vectorization,' Lemire and Boytsov,
+/// 2012](https://arxiv.org/abs/1209.2137). The last value of every block is
+/// kept in a `SimdVec` skip index, so a lookup searches the skip index with
+/// `SimdArrayOps::search` and then unpacks a single block.
+///
+/// Only `u32` values are supported.
+pub struct SimdPackedVec<A> {
+    phantom: PhantomData<A>,
+    len: usize,
+    packed: Vec<__m128i>,
+    /// The index in `packed` of the first word of each block.
+    offsets: Vec<usize>,
+    /// The bit width of each block's deltas.
+    widths: Vec<u8>,
+    /// The last value of each block.
+    maxima: SimdVec<u32>,
+}
+
+impl SimdPackedVec<u32> {
+    /// Compress a sorted slice of values.
+    ///
+    /// Panics if the values are not sorted.
+    pub fn new(values: &[u32]) -> Self {
+        if let Some(index) = values.windows(2).position(|pair| pair[0] > pair[1]) {
+            panic!(
+                "SimdPackedVec::new: values are not sorted at index {}",
+                index + 1
+            )
+        }
+        let mut out = SimdPackedVec {
+            phantom: PhantomData,
+            len: values.len(),
+            packed: Vec::new(),
+            offsets: Vec::with_capacity(values.len().div_ceil(BLOCK_LEN)),
+            widths: Vec::with_capacity(values.len().div_ceil(BLOCK_LEN)),
+            maxima: SimdVec::with_capacity(values.len().div_ceil(BLOCK_LEN)),
+        };
+        let mut base = 0;
+        let mut deltas = [0; BLOCK_LEN];
+        for chunk in values.chunks(BLOCK_LEN) {
+            // Padding deltas are zero, so cost no bits and decode to copies
+            // of the last value.
+            deltas.fill(0);
+            let mut previous = base;
+            for (delta, value) in deltas.iter_mut().zip(chunk) {
+                *delta = value - previous;
+                previous = *value;
+            }
+            let width = 32
+                - deltas
+                    .iter()
+                    .fold(0, |acc, delta| acc | delta)
+                    .leading_zeros();
+            out.offsets.push(out.packed.len());
+            out.widths.push(width as u8);
+            out.maxima.push(previous);
+            pack(&deltas, width, &mut out.packed);
+            base = previous;
+        }
+        out
+    }
+
+    /// Get the number of values.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Test if the vector is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get the number of blocks.
+    pub fn block_count(&self) -> usize {
+        self.widths.len()
+    }
+
+    /// Get the skip index: the last value of each block.
+    pub fn maxima(&self) -> &SimdVec<u32> {
+        &self.maxima
+    }
+
+    /// Get the number of bytes used by the packed blocks and their index.
+    pub fn size_in_bytes(&self) -> usize {
+        std::mem::size_of_val(self.packed.as_slice())
+            + std::mem::size_of_val(self.offsets.as_slice())
+            + self.widths.len()
+            + std::mem::size_of_val(self.maxima.data_m256())
+    }
+
+    /// Unpack a whole block, including any padding past the last value.
+    pub(crate) fn decode_block(&self, block: usize, out: &mut Decoded) {
+        let start = self.offsets[block];
+        let width = u32::from(self.widths[block]);
+        let packed = &self.packed[start..start + width as usize];
+        let base = if block == 0 {
+            0
+        } else {
+            self.maxima[block - 1]
+        };
+        if is_x86_feature_detected!("sse2") {
+            unsafe { unpack_sse2(packed, width, base, out) }
+        } else {
+            unpack_scalar(packed, width, base, out)
+        }
+    }
+
+    /// Get the number of values in a block.
+    fn block_len(&self, block: usize) -> usize {
+        std::cmp::min(BLOCK_LEN, self.len - block * BLOCK_LEN)
+    }
+
+    /// Get the value at a given index.
+    ///
+    /// Returns `None` if the index is out of bounds.
+    pub fn get(&self, index: usize) -> Option<u32> {
+        if index >= self.len {
+            return None;
+        }
+        let mut decoded = Decoded::default();
+        self.decode_block(index / BLOCK_LEN, &mut decoded);
+        Some(decoded.0[index % BLOCK_LEN])
+    }
+
+    /// Iterate over the values, unpacking a block at a time.
+    pub fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        Iter {
+            vec: self,
+            decoded: Decoded::default(),
+            index: 0,
+        }
+    }
+
+    /// Unpack every value into a `SimdVec`.
+    pub fn to_vec(&self) -> SimdVec<u32> {
+        let mut out = SimdVec::with_capacity(self.len);
+        let mut decoded = Decoded::default();
+        for block in 0..self.block_count() {
+            self.decode_block(block, &mut decoded);
+            out.extend(decoded.0[..self.block_len(block)].iter().copied());
+        }
+        out
+    }
+
+    /// Find the first block whose last value is not less than `value`.
+    fn find_block(&self, value: u32) -> usize {
+        match self.maxima.search(value) {
+            // Runs of equal values can span blocks, so step back to the
+            // first block ending in `value`.
+            Ok(mut block) => {
+                while block > 0 && self.maxima[block - 1] == value {
+                    block -= 1;
+                }
+                block
+            }
+            Err(block) => block,
+        }
+    }
+
+    /// Find the index of the first value not less than `value`.
+    ///
+    /// Only one block is unpacked.
+    pub fn lower_bound(&self, value: u32) -> usize {
+        let block = self.find_block(value);
+        if block == self.block_count() {
+            return self.len;
+        }
+        let mut decoded = Decoded::default();
+        self.decode_block(block, &mut decoded);
+        let values = &decoded.0[..self.block_len(block)];
+        block * BLOCK_LEN + values.partition_point(|item| *item < value)
+    }
+
+    /// Test if the vector holds `value`.
+    ///
+    /// Only one block is unpacked.
+    pub fn contains(&self, value: u32) -> bool {
+        let block = self.find_block(value);
+        if block == self.block_count() {
+            return false;
+        }
+        let mut decoded = Decoded::default();
+        self.decode_block(block, &mut decoded);
+        decoded.0[..self.block_len(block)]
+            .binary_search(&value)
+            .is_ok()
+    }
+}
+
+impl From<&SimdVec<u32>> for SimdPackedVec<u32> {
+    fn from(vec: &SimdVec<u32>) -> Self {
+        Self::new(vec)
+    }
+}
+
+impl Clone for SimdPackedVec<u32> {
+    fn clone(&self) -> Self {
+        SimdPackedVec {
+            phantom: PhantomData,
+            len: self.len,
+            packed: self.packed.clone(),
+            offsets: self.offsets.clone(),
+            widths: self.widths.clone(),
+            maxima: self.maxima.clone(),
+        }
+    }
+}
+
+impl Debug for SimdPackedVec<u32> {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl PartialEq for SimdPackedVec<u32> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for SimdPackedVec<u32> {}
+
+impl Default for SimdPackedVec<u32> {
+    fn default() -> Self {
+        Self::new(&[])
+    }
+}
+
+/// An iterator over the values of a `SimdPackedVec`, unpacking each block
+/// into a reused buffer as it's reached.
+struct Iter<'a> {
+    vec: &'a SimdPackedVec<u32>,
+    decoded: Decoded,
+    /// The index of the next value.
+    index: usize,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        if self.index >= self.vec.len {
+            return None;
+        }
+        let offset = self.index % BLOCK_LEN;
+        if offset == 0 {
+            self.vec
+                .decode_block(self.index / BLOCK_LEN, &mut self.decoded);
+        }
+        self.index += 1;
+        Some(self.decoded.0[offset])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.vec.len - self.index;
+        (remaining, Some(remaining))
+    }
+}
@@ -0,0 +1,148 @@
+use std::arch::x86_64::{self as arch, __m256i};
+use std::mem::{size_of, size_of_val};
+
+/// Integer types which can be sorted a byte at a time.
+///
+/// The key is the value's little endian byte representation, with the sign
+/// bit flipped for signed types so that negative values sort first.
+///
+/// # Safety
+///
+/// The sort reads and writes the raw bytes of each value, so implementors
+/// must be primitive integers: no padding, every bit pattern valid, and
+/// ordered by their little endian bytes as described above. `SIGNED` must be
+/// `true` exactly when the type is two's complement signed.
+pub unsafe trait RadixKey: Copy {
+    /// Whether the type's most significant bit is a sign bit.
+    const SIGNED: bool;
+}
+
+unsafe impl RadixKey for i8 {
+    const SIGNED: bool = true;
+}
+unsafe impl RadixKey for i16 {
+    const SIGNED: bool = true;
+}
+unsafe impl RadixKey for i32 {
+    const SIGNED: bool = true;
+}
+unsafe impl RadixKey for i64 {
+    const SIGNED: bool = true;
+}
+unsafe impl RadixKey for i128 {
+    const SIGNED: bool = true;
+}
+unsafe impl RadixKey for isize {
+    const SIGNED: bool = true;
+}
+unsafe impl RadixKey for u8 {
+    const SIGNED: bool = false;
+}
+unsafe impl RadixKey for u16 {
+    const SIGNED: bool = false;
+}
+unsafe impl RadixKey for u32 {
+    const SIGNED: bool = false;
+}
+unsafe impl RadixKey for u64 {
+    const SIGNED: bool = false;
+}
+unsafe impl RadixKey for u128 {
+    const SIGNED: bool = false;
+}
+unsafe impl RadixKey for usize {
+    const SIGNED: bool = false;
+}
+
+/// Flip the sign bit of every `A` in `blocks`.
+#[target_feature(enable = "avx2")]
+unsafe fn flip_sign_bits<A>(blocks: &mut [__m256i]) {
+    let mut mask = [0u8; 32];
+    for (index, byte) in mask.iter_mut().enumerate() {
+        if index % size_of::<A>() == size_of::<A>() - 1 {
+            *byte = 0x80;
+        }
+    }
+    let mask = arch::_mm256_loadu_si256(mask.as_ptr() as *const __m256i);
+    for block in blocks {
+        *block = arch::_mm256_xor_si256(*block, mask);
+    }
+}
+
+fn flip_sign_bits_scalar<A>(blocks: &mut [__m256i]) {
+    let bytes = unsafe {
+        std::slice::from_raw_parts_mut(blocks.as_mut_ptr() as *mut u8, size_of_val(blocks))
+    };
+    for byte in bytes
+        .iter_mut()
+        .skip(size_of::<A>() - 1)
+        .step_by(size_of::<A>())
+    {
+        *byte ^= 0x80;
+    }
+}
+
+fn toggle_sign<A: RadixKey>(blocks: &mut [__m256i]) {
+    if A::SIGNED {
+        if is_x86_feature_detected!("avx2") {
+            unsafe { flip_sign_bits::<A>(blocks) }
+        } else {
+            flip_sign_bits_scalar::<A>(blocks)
+        }
+    }
+}
+
+/// Sort the first `len` elements in `blocks` with an LSD radix sort, using
+/// `buffer` as scratch space. `buffer` must hold at least `len` elements.
+///
+/// Signed values are sorted as unsigned by flipping their sign bits in place
+/// before the sort and back afterwards. Passes over bytes which are the same
+/// for every element are skipped.
+pub(crate) fn radix_sort<A>(blocks: &mut [__m256i], buffer: &mut [__m256i], len: usize)
+where
+    A: RadixKey,
+{
+    let size = size_of::<A>();
+    debug_assert!(size_of_val(buffer) >= len * size);
+    toggle_sign::<A>(blocks);
+
+    let mut counts = vec![[0usize; 256]; size];
+    {
+        let bytes = unsafe { std::slice::from_raw_parts(blocks.as_ptr() as *const u8, len * size) };
+        for element in bytes.chunks_exact(size) {
+            for (digit, byte) in element.iter().enumerate() {
+                counts[digit][*byte as usize] += 1;
+            }
+        }
+    }
+
+    let mut source = blocks.as_mut_ptr() as *mut A;
+    let mut target = buffer.as_mut_ptr() as *mut A;
+    let mut sorted_in_blocks = true;
+    for (digit, counts) in counts.iter().enumerate() {
+        if counts.contains(&len) {
+            continue;
+        }
+        let mut offsets = [0usize; 256];
+        let mut total = 0;
+        for (offset, count) in offsets.iter_mut().zip(counts.iter()) {
+            *offset = total;
+            total += count;
+        }
+        unsafe {
+            let source_bytes = source as *const u8;
+            for index in 0..len {
+                let byte = *source_bytes.add(index * size + digit) as usize;
+                *target.add(offsets[byte]) = *source.add(index);
+                offsets[byte] += 1;
+            }
+        }
+        std::mem::swap(&mut source, &mut target);
+        sorted_in_blocks = !sorted_in_blocks;
+    }
+    if !sorted_in_blocks {
+        unsafe { std::ptr::copy_nonoverlapping(source, target, len) };
+    }
+
+    toggle_sign::<A>(blocks);
+}
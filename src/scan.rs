@@ -0,0 +1,402 @@
+use std::arch::x86_64 as arch;
+use std::arch::x86_64::{__m128i, __m256i};
+use std::fmt::{Display, Formatter};
+use std::mem::size_of;
+
+use crate::{DefaultZero, SimdVec};
+
+/// An error for an in-place operation whose result doesn't fit the element
+/// type.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct OverflowError {
+    /// The index of the first element which would overflow.
+    pub index: usize,
+}
+
+impl Display for OverflowError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        write!(f, "arithmetic overflow at index {}", self.index)
+    }
+}
+
+impl std::error::Error for OverflowError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ScanOp {
+    /// Each element becomes the sum of itself and those before it.
+    Inclusive,
+    /// Each element becomes the sum of those before it.
+    Exclusive,
+    /// Each element becomes its difference from the one before it.
+    Delta,
+}
+
+/// A 32-bit or 64-bit integer which can be scanned.
+pub(crate) trait ScanInt: Copy + DefaultZero {
+    fn wrapping_add(self, other: Self) -> Self;
+    fn wrapping_sub(self, other: Self) -> Self;
+    fn checked_add(self, other: Self) -> Option<Self>;
+    fn checked_sub(self, other: Self) -> Option<Self>;
+}
+
+macro_rules! scan_int {
+    ($($type:ty),*) => {
+        $(
+            impl ScanInt for $type {
+                fn wrapping_add(self, other: Self) -> Self {
+                    <$type>::wrapping_add(self, other)
+                }
+
+                fn wrapping_sub(self, other: Self) -> Self {
+                    <$type>::wrapping_sub(self, other)
+                }
+
+                fn checked_add(self, other: Self) -> Option<Self> {
+                    <$type>::checked_add(self, other)
+                }
+
+                fn checked_sub(self, other: Self) -> Option<Self> {
+                    <$type>::checked_sub(self, other)
+                }
+            }
+        )*
+    };
+}
+
+scan_int!(i32, u32, i64, u64);
+
+// Wrapping addition is the same for signed and unsigned lanes, so the
+// kernels only need to tell lane widths apart.
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn add_avx2<A>(left: __m256i, right: __m256i) -> __m256i {
+    if size_of::<A>() == 4 {
+        arch::_mm256_add_epi32(left, right)
+    } else {
+        arch::_mm256_add_epi64(left, right)
+    }
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn sub_avx2<A>(left: __m256i, right: __m256i) -> __m256i {
+    if size_of::<A>() == 4 {
+        arch::_mm256_sub_epi32(left, right)
+    } else {
+        arch::_mm256_sub_epi64(left, right)
+    }
+}
+
+/// Sum each lane of a register into the lanes after it.
+///
+/// Adding the register to itself shifted by one, then two lanes, sums within
+/// each 128-bit half, as byte shifts don't cross halves. The low half's last
+/// lane is then broadcast and moved up to add to the high half.
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn sums_avx2<A>(values: __m256i) -> __m256i {
+    if size_of::<A>() == 4 {
+        let sums = arch::_mm256_add_epi32(values, arch::_mm256_slli_si256(values, 4));
+        let sums = arch::_mm256_add_epi32(sums, arch::_mm256_slli_si256(sums, 8));
+        let last = arch::_mm256_shuffle_epi32(sums, 0xff);
+        arch::_mm256_add_epi32(sums, arch::_mm256_permute2x128_si256(last, last, 0x08))
+    } else {
+        let sums = arch::_mm256_add_epi64(values, arch::_mm256_slli_si256(values, 8));
+        let last = arch::_mm256_shuffle_epi32(sums, 0xee);
+        arch::_mm256_add_epi64(sums, arch::_mm256_permute2x128_si256(last, last, 0x08))
+    }
+}
+
+/// Broadcast the last lane of a register.
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn last_avx2<A>(values: __m256i) -> __m256i {
+    if size_of::<A>() == 4 {
+        arch::_mm256_permutevar8x32_epi32(values, arch::_mm256_set1_epi32(7))
+    } else {
+        arch::_mm256_permute4x64_epi64(values, 0xff)
+    }
+}
+
+/// Shift a register up one lane, bringing in the last lane of `previous`.
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn previous_avx2<A>(values: __m256i, previous: __m256i) -> __m256i {
+    // The high half of `previous` and the low half of `values`, to align
+    // each half of `values` against the half before it.
+    let before = arch::_mm256_permute2x128_si256(previous, values, 0x21);
+    if size_of::<A>() == 4 {
+        arch::_mm256_alignr_epi8(values, before, 12)
+    } else {
+        arch::_mm256_alignr_epi8(values, before, 8)
+    }
+}
+
+#[inline]
+#[target_feature(enable = "sse2")]
+unsafe fn add_sse2<A>(left: __m128i, right: __m128i) -> __m128i {
+    if size_of::<A>() == 4 {
+        arch::_mm_add_epi32(left, right)
+    } else {
+        arch::_mm_add_epi64(left, right)
+    }
+}
+
+#[inline]
+#[target_feature(enable = "sse2")]
+unsafe fn sub_sse2<A>(left: __m128i, right: __m128i) -> __m128i {
+    if size_of::<A>() == 4 {
+        arch::_mm_sub_epi32(left, right)
+    } else {
+        arch::_mm_sub_epi64(left, right)
+    }
+}
+
+#[inline]
+#[target_feature(enable = "sse2")]
+unsafe fn sums_sse2<A>(values: __m128i) -> __m128i {
+    if size_of::<A>() == 4 {
+        let sums = arch::_mm_add_epi32(values, arch::_mm_slli_si128(values, 4));
+        arch::_mm_add_epi32(sums, arch::_mm_slli_si128(sums, 8))
+    } else {
+        arch::_mm_add_epi64(values, arch::_mm_slli_si128(values, 8))
+    }
+}
+
+#[inline]
+#[target_feature(enable = "sse2")]
+unsafe fn last_sse2<A>(values: __m128i) -> __m128i {
+    if size_of::<A>() == 4 {
+        arch::_mm_shuffle_epi32(values, 0xff)
+    } else {
+        arch::_mm_shuffle_epi32(values, 0xee)
+    }
+}
+
+#[inline]
+#[target_feature(enable = "sse2")]
+unsafe fn previous_sse2<A>(values: __m128i, previous: __m128i) -> __m128i {
+    if size_of::<A>() == 4 {
+        arch::_mm_or_si128(
+            arch::_mm_slli_si128(values, 4),
+            arch::_mm_srli_si128(previous, 12),
+        )
+    } else {
+        arch::_mm_or_si128(
+            arch::_mm_slli_si128(values, 8),
+            arch::_mm_srli_si128(previous, 8),
+        )
+    }
+}
+
+/// Read the last lane of a register.
+unsafe fn last_lane<R, A: Copy>(register: &R) -> A {
+    let lanes = size_of::<R>() / size_of::<A>();
+    (register as *const R as *const A).add(lanes - 1).read()
+}
+
+/// Scan a register at a time, carrying the running total, or the last
+/// element for deltas, from one register to the next, and finish the
+/// elements past the last whole register in scalar code.
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn scan_avx2<A: ScanInt>(values: &mut [A], op: ScanOp) {
+    let lanes = size_of::<__m256i>() / size_of::<A>();
+    let full = values.len() / lanes * lanes;
+    let mut carry = arch::_mm256_setzero_si256();
+    for chunk in values[..full].chunks_exact_mut(lanes) {
+        let target = chunk.as_mut_ptr() as *mut __m256i;
+        let block = arch::_mm256_loadu_si256(target);
+        let out = match op {
+            ScanOp::Inclusive | ScanOp::Exclusive => {
+                let sums = add_avx2::<A>(sums_avx2::<A>(block), carry);
+                carry = last_avx2::<A>(sums);
+                if op == ScanOp::Exclusive {
+                    sub_avx2::<A>(sums, block)
+                } else {
+                    sums
+                }
+            }
+            ScanOp::Delta => {
+                let out = sub_avx2::<A>(block, previous_avx2::<A>(block, carry));
+                carry = block;
+                out
+            }
+        };
+        arch::_mm256_storeu_si256(target, out);
+    }
+    scan_scalar(&mut values[full..], op, last_lane(&carry));
+}
+
+#[target_feature(enable = "sse2")]
+pub(crate) unsafe fn scan_sse2<A: ScanInt>(values: &mut [A], op: ScanOp) {
+    let lanes = size_of::<__m128i>() / size_of::<A>();
+    let full = values.len() / lanes * lanes;
+    let mut carry = arch::_mm_setzero_si128();
+    for chunk in values[..full].chunks_exact_mut(lanes) {
+        let target = chunk.as_mut_ptr() as *mut __m128i;
+        let block = arch::_mm_loadu_si128(target);
+        let out = match op {
+            ScanOp::Inclusive | ScanOp::Exclusive => {
+                let sums = add_sse2::<A>(sums_sse2::<A>(block), carry);
+                carry = last_sse2::<A>(sums);
+                if op == ScanOp::Exclusive {
+                    sub_sse2::<A>(sums, block)
+                } else {
+                    sums
+                }
+            }
+            ScanOp::Delta => {
+                let out = sub_sse2::<A>(block, previous_sse2::<A>(block, carry));
+                carry = block;
+                out
+            }
+        };
+        arch::_mm_storeu_si128(target, out);
+    }
+    scan_scalar(&mut values[full..], op, last_lane(&carry));
+}
+
+/// Scan in scalar code, starting from a running total, or a previous
+/// element for deltas, of `carry`.
+pub(crate) fn scan_scalar<A: ScanInt>(values: &mut [A], op: ScanOp, mut carry: A) {
+    for value in values {
+        let current = *value;
+        match op {
+            ScanOp::Inclusive => {
+                carry = carry.wrapping_add(current);
+                *value = carry;
+            }
+            ScanOp::Exclusive => {
+                *value = carry;
+                carry = carry.wrapping_add(current);
+            }
+            ScanOp::Delta => {
+                *value = current.wrapping_sub(carry);
+                carry = current;
+            }
+        }
+    }
+}
+
+/// Scan in place, wrapping on overflow.
+pub(crate) fn scan<A: ScanInt>(values: &mut [A], op: ScanOp) {
+    if is_x86_feature_detected!("avx2") {
+        unsafe { scan_avx2(values, op) }
+    } else {
+        // SSE2 is part of the x86_64 baseline.
+        unsafe { scan_sse2(values, op) }
+    }
+}
+
+/// Find the first element a scan would overflow at, without changing
+/// anything.
+pub(crate) fn check<A: ScanInt>(values: &[A], op: ScanOp) -> Result<(), OverflowError> {
+    let mut total = A::default_zero();
+    let overflows = |value: &A| match total.checked_add(*value) {
+        Some(sum) => {
+            total = sum;
+            false
+        }
+        None => true,
+    };
+    let index = match op {
+        ScanOp::Inclusive => values.iter().position(overflows),
+        // The total of every element isn't part of the result.
+        ScanOp::Exclusive => values[..values.len().saturating_sub(1)]
+            .iter()
+            .position(overflows)
+            .map(|index| index + 1),
+        ScanOp::Delta => values
+            .windows(2)
+            .position(|pair| pair[1].checked_sub(pair[0]).is_none())
+            .map(|index| index + 1),
+    };
+    match index {
+        Some(index) => Err(OverflowError { index }),
+        None => Ok(()),
+    }
+}
+
+macro_rules! scan_vec {
+    ($type:ty) => {
+        impl SimdVec<$type> {
+            /// Replace each element with the sum of itself and every
+            /// element before it, wrapping on overflow.
+            ///
+            /// Sums are taken a register at a time by adding shifted copies
+            /// of the register to itself, with the running total carried
+            /// from one register to the next.
+            pub fn prefix_sum(&mut self) {
+                scan(self, ScanOp::Inclusive)
+            }
+
+            /// Replace each element with the sum of itself and every
+            /// element before it.
+            ///
+            /// Fails, leaving the vector unchanged, if a sum overflows.
+            pub fn checked_prefix_sum(&mut self) -> Result<(), OverflowError> {
+                check(self, ScanOp::Inclusive)?;
+                scan(self, ScanOp::Inclusive);
+                Ok(())
+            }
+
+            /// Replace each element with the sum of every element before
+            /// it, wrapping on overflow. The first element becomes zero.
+            pub fn exclusive_prefix_sum(&mut self) {
+                scan(self, ScanOp::Exclusive)
+            }
+
+            /// Replace each element with the sum of every element before
+            /// it. The first element becomes zero.
+            ///
+            /// Fails, leaving the vector unchanged, if a sum overflows.
+            pub fn checked_exclusive_prefix_sum(&mut self) -> Result<(), OverflowError> {
+                check(self, ScanOp::Exclusive)?;
+                scan(self, ScanOp::Exclusive);
+                Ok(())
+            }
+
+            /// Replace each element after the first with its difference
+            /// from the element before it, wrapping on overflow.
+            ///
+            /// Undone by `delta_decode`.
+            pub fn delta_encode(&mut self) {
+                scan(self, ScanOp::Delta)
+            }
+
+            /// Replace each element after the first with its difference
+            /// from the element before it.
+            ///
+            /// Fails, leaving the vector unchanged, if a difference
+            /// overflows, which for unsigned elements means they weren't
+            /// sorted.
+            pub fn checked_delta_encode(&mut self) -> Result<(), OverflowError> {
+                check(self, ScanOp::Delta)?;
+                scan(self, ScanOp::Delta);
+                Ok(())
+            }
+
+            /// Undo `delta_encode`, wrapping on overflow.
+            ///
+            /// This is the same as `prefix_sum`.
+            pub fn delta_decode(&mut self) {
+                scan(self, ScanOp::Inclusive)
+            }
+
+            /// Undo `delta_encode`.
+            ///
+            /// Fails, leaving the vector unchanged, if a value overflows.
+            pub fn checked_delta_decode(&mut self) -> Result<(), OverflowError> {
+                check(self, ScanOp::Inclusive)?;
+                scan(self, ScanOp::Inclusive);
+                Ok(())
+            }
+        }
+    };
+}
+
+scan_vec!(i32);
+scan_vec!(u32);
+scan_vec!(i64);
+scan_vec!(u64);
@@ -0,0 +1,282 @@
+use std::arch::x86_64::{__m128i, __m256i};
+use std::cmp::Ordering;
+
+use bitmaps::Bitmap;
+
+use crate::kernels::pack_mask;
+use crate::simd_ops::has_sse_compare;
+use crate::{compact, DefaultZero, SimdArrayOps, SimdBitVec, SimdOps, SimdRegister, SimdVec};
+
+/// Gallop through the larger array when it is at least this many times
+/// longer than the smaller one.
+const GALLOP_RATIO: usize = 32;
+
+/// Mark the elements of two sorted arrays which also occur in the other,
+/// returning one selection bitmap for each.
+pub(crate) fn matches<A, L, T>(left: &L, right: &T) -> (SimdBitVec, SimdBitVec)
+where
+    A: Copy + Ord + SimdOps<__m256i> + SimdOps<__m128i>,
+    L: SimdArrayOps<A> + ?Sized,
+    T: SimdArrayOps<A> + ?Sized,
+{
+    let mut left_bits = SimdBitVec::zeroed(left.len());
+    let mut right_bits = SimdBitVec::zeroed(right.len());
+    if left.is_empty() || right.is_empty() {
+        return (left_bits, right_bits);
+    }
+    if is_x86_feature_detected!("avx2") {
+        unsafe { matches_with::<__m256i, _, _, _>(left, right, &mut left_bits, &mut right_bits) }
+    } else if has_sse_compare::<A>() {
+        unsafe { matches_with::<__m128i, _, _, _>(left, right, &mut left_bits, &mut right_bits) }
+    } else {
+        matches_scalar(left, right, &mut left_bits, &mut right_bits);
+    }
+    (left_bits, right_bits)
+}
+
+/// Mark the matching elements with a plain merge of the two arrays, for
+/// CPUs without the compares `matches_with` needs.
+pub(crate) fn matches_scalar<A>(
+    left: &[A],
+    right: &[A],
+    left_bits: &mut SimdBitVec,
+    right_bits: &mut SimdBitVec,
+) where
+    A: Ord,
+{
+    let (mut left_pos, mut right_pos) = (0, 0);
+    while left_pos < left.len() && right_pos < right.len() {
+        match left[left_pos].cmp(&right[right_pos]) {
+            Ordering::Less => left_pos += 1,
+            Ordering::Greater => right_pos += 1,
+            Ordering::Equal => {
+                left_bits.set(left_pos, true);
+                right_bits.set(right_pos, true);
+                left_pos += 1;
+                right_pos += 1;
+            }
+        }
+    }
+}
+
+unsafe fn matches_with<R, A, L, T>(
+    left: &L,
+    right: &T,
+    left_bits: &mut SimdBitVec,
+    right_bits: &mut SimdBitVec,
+) where
+    R: SimdRegister,
+    A: Copy + Ord + SimdOps<R>,
+    L: SimdArrayOps<A> + ?Sized,
+    T: SimdArrayOps<A> + ?Sized,
+{
+    if right.len() >= GALLOP_RATIO * left.len() {
+        gallop::<R, _, _, _>(left, right, left_bits, right_bits);
+    } else if left.len() >= GALLOP_RATIO * right.len() {
+        gallop::<R, _, _, _>(right, left, right_bits, left_bits);
+    } else {
+        intersect_blocks::<R, _, _, _>(left, right, left_bits, right_bits);
+    }
+}
+
+/// Walk both arrays a register at a time, comparing every element of the
+/// current `left` register with every element of the current `right`
+/// register, and advancing whichever register ends with the smaller element.
+///
+/// This is the approach described in ['Fast Sorted-Set Intersection using
+/// SIMD Instructions,'
+/// 2011](https://www.adms-conf.org/2011/p1-schlegel.pdf).
+unsafe fn intersect_blocks<R, A, L, T>(
+    left: &L,
+    right: &T,
+    left_bits: &mut SimdBitVec,
+    right_bits: &mut SimdBitVec,
+) where
+    R: SimdRegister,
+    A: Copy + Ord + SimdOps<R>,
+    L: SimdArrayOps<A> + ?Sized,
+    T: SimdArrayOps<A> + ?Sized,
+{
+    let (mut left_pos, mut right_pos) = (0, 0);
+    while left_pos < left.len() && right_pos < right.len() {
+        let left_end = std::cmp::min(left_pos + A::ALIGNMENT, left.len());
+        let right_end = std::cmp::min(right_pos + A::ALIGNMENT, right.len());
+        let data = left.load::<R>(left_pos);
+        let valid = if left_end - left_pos < A::ALIGNMENT {
+            Bitmap::mask((left_end - left_pos) * A::BITS_PER_CMP)
+        } else {
+            !Bitmap::new()
+        };
+        let mut found = Bitmap::new();
+        for index in right_pos..right_end {
+            let eq = A::cmp_eq(data, A::set(right[index])) & valid;
+            if !eq.is_empty() {
+                found |= eq;
+                right_bits.set(index, true);
+            }
+        }
+        let bits = pack_mask(R::mask_value(found), A::BITS_PER_CMP);
+        left_bits.words_mut()[left_pos / 64] |= u64::from(bits) << (left_pos % 64);
+
+        let (left_last, right_last) = (left[left_end - 1], right[right_end - 1]);
+        if left_last <= right_last {
+            left_pos += A::ALIGNMENT;
+        }
+        if right_last <= left_last {
+            right_pos += A::ALIGNMENT;
+        }
+    }
+}
+
+/// Look up each element of `small` in `large`, galloping ahead from the
+/// previous match a register at a time before a bounded k-ary search.
+unsafe fn gallop<R, A, S, L>(
+    small: &S,
+    large: &L,
+    small_bits: &mut SimdBitVec,
+    large_bits: &mut SimdBitVec,
+) where
+    R: SimdRegister,
+    A: Copy + Ord + SimdOps<R>,
+    S: SimdArrayOps<A> + ?Sized,
+    L: SimdArrayOps<A> + ?Sized,
+{
+    let mut cursor = 0;
+    for (index, key) in small.iter().enumerate() {
+        if cursor >= large.len() {
+            break;
+        }
+        let mut bound = cursor;
+        let mut step = A::ALIGNMENT;
+        while bound + step < large.len() && large[bound + step] < *key {
+            bound += step;
+            step *= 2;
+        }
+        let end = std::cmp::min(bound + step + 1, large.len());
+        match large.k_ary_search_range::<R>(*key, bound..end) {
+            Ok(found) => {
+                small_bits.set(index, true);
+                large_bits.set(found, true);
+                cursor = found + 1;
+            }
+            Err(insert) => cursor = insert,
+        }
+    }
+}
+
+fn invert(bits: &mut SimdBitVec) {
+    for word in bits.words_mut() {
+        *word = !*word;
+    }
+    bits.clear_padding();
+}
+
+/// Merge two sorted slices with no elements in common.
+fn merge_disjoint<A>(left: &[A], right: &[A]) -> SimdVec<A>
+where
+    A: Copy + Ord + DefaultZero,
+{
+    let mut out = SimdVec::with_capacity(left.len() + right.len());
+    let (mut left, mut right) = (left.iter().peekable(), right.iter().peekable());
+    loop {
+        let next = match (left.peek(), right.peek()) {
+            (Some(l), Some(r)) if l < r => left.next(),
+            (Some(_), Some(_)) => right.next(),
+            (Some(_), None) => left.next(),
+            (None, Some(_)) => right.next(),
+            (None, None) => break,
+        };
+        out.push(*next.unwrap());
+    }
+    out
+}
+
+/// Count the elements common to two sorted arrays.
+pub(crate) fn intersect_count<A, L, T>(left: &L, right: &T) -> usize
+where
+    A: Copy + Ord + SimdOps<__m256i> + SimdOps<__m128i>,
+    L: SimdArrayOps<A> + ?Sized,
+    T: SimdArrayOps<A> + ?Sized,
+{
+    matches(left, right).0.count_ones()
+}
+
+/// Collect the elements common to two sorted arrays.
+pub(crate) fn intersect<A, L, T>(left: &L, right: &T) -> SimdVec<A>
+where
+    A: Copy + Ord + DefaultZero + SimdOps<__m256i> + SimdOps<__m128i>,
+    L: SimdArrayOps<A> + ?Sized,
+    T: SimdArrayOps<A> + ?Sized,
+{
+    compact::compact(left, &matches(left, right).0)
+}
+
+/// Count the elements of `left` which are not in `right`.
+pub(crate) fn difference_count<A, L, T>(left: &L, right: &T) -> usize
+where
+    A: Copy + Ord + SimdOps<__m256i> + SimdOps<__m128i>,
+    L: SimdArrayOps<A> + ?Sized,
+    T: SimdArrayOps<A> + ?Sized,
+{
+    left.len() - intersect_count(left, right)
+}
+
+/// Collect the elements of `left` which are not in `right`.
+pub(crate) fn difference<A, L, T>(left: &L, right: &T) -> SimdVec<A>
+where
+    A: Copy + Ord + DefaultZero + SimdOps<__m256i> + SimdOps<__m128i>,
+    L: SimdArrayOps<A> + ?Sized,
+    T: SimdArrayOps<A> + ?Sized,
+{
+    let mut selection = matches(left, right).0;
+    invert(&mut selection);
+    compact::compact(left, &selection)
+}
+
+/// Count the elements in either of two sorted arrays.
+pub(crate) fn union_count<A, L, T>(left: &L, right: &T) -> usize
+where
+    A: Copy + Ord + SimdOps<__m256i> + SimdOps<__m128i>,
+    L: SimdArrayOps<A> + ?Sized,
+    T: SimdArrayOps<A> + ?Sized,
+{
+    left.len() + right.len() - intersect_count(left, right)
+}
+
+/// Collect the elements in either of two sorted arrays.
+pub(crate) fn union<A, L, T>(left: &L, right: &T) -> SimdVec<A>
+where
+    A: Copy + Ord + DefaultZero + SimdOps<__m256i> + SimdOps<__m128i>,
+    L: SimdArrayOps<A> + ?Sized,
+    T: SimdArrayOps<A> + ?Sized,
+{
+    let mut selection = matches(left, right).1;
+    invert(&mut selection);
+    merge_disjoint(left, &compact::compact(right, &selection))
+}
+
+/// Count the elements in exactly one of two sorted arrays.
+pub(crate) fn symmetric_difference_count<A, L, T>(left: &L, right: &T) -> usize
+where
+    A: Copy + Ord + SimdOps<__m256i> + SimdOps<__m128i>,
+    L: SimdArrayOps<A> + ?Sized,
+    T: SimdArrayOps<A> + ?Sized,
+{
+    left.len() + right.len() - 2 * intersect_count(left, right)
+}
+
+/// Collect the elements in exactly one of two sorted arrays.
+pub(crate) fn symmetric_difference<A, L, T>(left: &L, right: &T) -> SimdVec<A>
+where
+    A: Copy + Ord + DefaultZero + SimdOps<__m256i> + SimdOps<__m128i>,
+    L: SimdArrayOps<A> + ?Sized,
+    T: SimdArrayOps<A> + ?Sized,
+{
+    let (mut left_selection, mut right_selection) = matches(left, right);
+    invert(&mut left_selection);
+    invert(&mut right_selection);
+    merge_disjoint(
+        &compact::compact(left, &left_selection),
+        &compact::compact(right, &right_selection),
+    )
+}
@@ -0,0 +1,636 @@
+use std::arch::x86_64::{self as arch, __m128i, __m256i};
+
+use crate::simd_ops::{SimdOps, SimdRegister};
+
+mod sealed {
+    /// The scalar equivalents of the `SimdArith` operations, for CPUs
+    /// without the instructions they need.
+    pub trait ScalarArith: Copy {
+        fn scalar_add(self, other: Self) -> Self;
+        fn scalar_sub(self, other: Self) -> Self;
+        fn scalar_abs(self) -> Self;
+        fn scalar_saturating_add(self, other: Self) -> Self;
+        fn scalar_saturating_sub(self, other: Self) -> Self;
+    }
+
+    macro_rules! scalar_arith {
+        ($($type:ty),*) => {
+            $(
+                impl ScalarArith for $type {
+                    fn scalar_add(self, other: Self) -> Self {
+                        <$type>::wrapping_add(self, other)
+                    }
+
+                    fn scalar_sub(self, other: Self) -> Self {
+                        <$type>::wrapping_sub(self, other)
+                    }
+
+                    fn scalar_abs(self) -> Self {
+                        <$type>::wrapping_abs(self)
+                    }
+
+                    fn scalar_saturating_add(self, other: Self) -> Self {
+                        <$type>::saturating_add(self, other)
+                    }
+
+                    fn scalar_saturating_sub(self, other: Self) -> Self {
+                        <$type>::saturating_sub(self, other)
+                    }
+                }
+            )*
+        };
+    }
+
+    scalar_arith!(i8, i16, i32, i64, isize);
+}
+
+pub(crate) use self::sealed::ScalarArith;
+
+/// Arithmetic on datatypes stored in SIMD registers.
+///
+/// Plain arithmetic wraps on overflow, like `wrapping_add` and friends.
+/// The `__m128i` implementations need up to SSE4.2, beyond the SSE2 of the
+/// x86_64 baseline.
+///
+/// This trait is sealed: it's implemented for the signed integer types.
+pub trait SimdArith<R: SimdRegister>: SimdOps<R> + ScalarArith {
+    /// Add two registers lane by lane, wrapping on overflow.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support the instruction set for the register type.
+    unsafe fn add(left: R, right: R) -> R;
+
+    /// Subtract two registers lane by lane, wrapping on overflow.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support the instruction set for the register type.
+    unsafe fn sub(left: R, right: R) -> R;
+
+    /// Take the lane by lane minimum of two registers.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support the instruction set for the register type.
+    unsafe fn min(left: R, right: R) -> R;
+
+    /// Take the lane by lane maximum of two registers.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support the instruction set for the register type.
+    unsafe fn max(left: R, right: R) -> R;
+
+    /// Take the absolute value of each lane, wrapping on overflow.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support the instruction set for the register type.
+    unsafe fn abs(value: R) -> R;
+
+    /// Add two registers lane by lane, saturating at the numeric bounds.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support the instruction set for the register type.
+    unsafe fn saturating_add(left: R, right: R) -> R;
+
+    /// Subtract two registers lane by lane, saturating at the numeric bounds.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support the instruction set for the register type.
+    unsafe fn saturating_sub(left: R, right: R) -> R;
+}
+
+// Lane selection and saturation helpers for the lane widths which have no
+// native instructions.
+
+#[inline]
+#[target_feature(enable = "sse2")]
+unsafe fn select_128(mask: __m128i, if_true: __m128i, if_false: __m128i) -> __m128i {
+    arch::_mm_or_si128(
+        arch::_mm_and_si128(mask, if_true),
+        arch::_mm_andnot_si128(mask, if_false),
+    )
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn select_256(mask: __m256i, if_true: __m256i, if_false: __m256i) -> __m256i {
+    arch::_mm256_blendv_epi8(if_false, if_true, mask)
+}
+
+#[inline]
+#[target_feature(enable = "sse2")]
+unsafe fn saturate_i32_128(left: __m128i, result: __m128i, overflow: __m128i) -> __m128i {
+    let overflow = arch::_mm_srai_epi32(overflow, 31);
+    let bound = arch::_mm_xor_si128(
+        arch::_mm_srai_epi32(left, 31),
+        arch::_mm_set1_epi32(i32::MAX),
+    );
+    select_128(overflow, bound, result)
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn saturate_i32_256(left: __m256i, result: __m256i, overflow: __m256i) -> __m256i {
+    let overflow = arch::_mm256_srai_epi32(overflow, 31);
+    let bound = arch::_mm256_xor_si256(
+        arch::_mm256_srai_epi32(left, 31),
+        arch::_mm256_set1_epi32(i32::MAX),
+    );
+    select_256(overflow, bound, result)
+}
+
+#[inline]
+#[target_feature(enable = "sse4.2")]
+unsafe fn saturate_i64_128(left: __m128i, result: __m128i, overflow: __m128i) -> __m128i {
+    let zero = arch::_mm_setzero_si128();
+    let overflow = arch::_mm_cmpgt_epi64(zero, overflow);
+    let bound = arch::_mm_xor_si128(
+        arch::_mm_cmpgt_epi64(zero, left),
+        arch::_mm_set1_epi64x(i64::MAX),
+    );
+    select_128(overflow, bound, result)
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn saturate_i64_256(left: __m256i, result: __m256i, overflow: __m256i) -> __m256i {
+    let zero = arch::_mm256_setzero_si256();
+    let overflow = arch::_mm256_cmpgt_epi64(zero, overflow);
+    let bound = arch::_mm256_xor_si256(
+        arch::_mm256_cmpgt_epi64(zero, left),
+        arch::_mm256_set1_epi64x(i64::MAX),
+    );
+    select_256(overflow, bound, result)
+}
+
+impl SimdArith<__m128i> for i8 {
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    unsafe fn add(left: __m128i, right: __m128i) -> __m128i {
+        arch::_mm_add_epi8(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    unsafe fn sub(left: __m128i, right: __m128i) -> __m128i {
+        arch::_mm_sub_epi8(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse4.1")]
+    unsafe fn min(left: __m128i, right: __m128i) -> __m128i {
+        arch::_mm_min_epi8(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse4.1")]
+    unsafe fn max(left: __m128i, right: __m128i) -> __m128i {
+        arch::_mm_max_epi8(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "ssse3")]
+    unsafe fn abs(value: __m128i) -> __m128i {
+        arch::_mm_abs_epi8(value)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    unsafe fn saturating_add(left: __m128i, right: __m128i) -> __m128i {
+        arch::_mm_adds_epi8(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    unsafe fn saturating_sub(left: __m128i, right: __m128i) -> __m128i {
+        arch::_mm_subs_epi8(left, right)
+    }
+}
+
+impl SimdArith<__m256i> for i8 {
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn add(left: __m256i, right: __m256i) -> __m256i {
+        arch::_mm256_add_epi8(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn sub(left: __m256i, right: __m256i) -> __m256i {
+        arch::_mm256_sub_epi8(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn min(left: __m256i, right: __m256i) -> __m256i {
+        arch::_mm256_min_epi8(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn max(left: __m256i, right: __m256i) -> __m256i {
+        arch::_mm256_max_epi8(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn abs(value: __m256i) -> __m256i {
+        arch::_mm256_abs_epi8(value)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn saturating_add(left: __m256i, right: __m256i) -> __m256i {
+        arch::_mm256_adds_epi8(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn saturating_sub(left: __m256i, right: __m256i) -> __m256i {
+        arch::_mm256_subs_epi8(left, right)
+    }
+}
+
+impl SimdArith<__m128i> for i16 {
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    unsafe fn add(left: __m128i, right: __m128i) -> __m128i {
+        arch::_mm_add_epi16(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    unsafe fn sub(left: __m128i, right: __m128i) -> __m128i {
+        arch::_mm_sub_epi16(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    unsafe fn min(left: __m128i, right: __m128i) -> __m128i {
+        arch::_mm_min_epi16(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    unsafe fn max(left: __m128i, right: __m128i) -> __m128i {
+        arch::_mm_max_epi16(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "ssse3")]
+    unsafe fn abs(value: __m128i) -> __m128i {
+        arch::_mm_abs_epi16(value)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    unsafe fn saturating_add(left: __m128i, right: __m128i) -> __m128i {
+        arch::_mm_adds_epi16(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    unsafe fn saturating_sub(left: __m128i, right: __m128i) -> __m128i {
+        arch::_mm_subs_epi16(left, right)
+    }
+}
+
+impl SimdArith<__m256i> for i16 {
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn add(left: __m256i, right: __m256i) -> __m256i {
+        arch::_mm256_add_epi16(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn sub(left: __m256i, right: __m256i) -> __m256i {
+        arch::_mm256_sub_epi16(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn min(left: __m256i, right: __m256i) -> __m256i {
+        arch::_mm256_min_epi16(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn max(left: __m256i, right: __m256i) -> __m256i {
+        arch::_mm256_max_epi16(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn abs(value: __m256i) -> __m256i {
+        arch::_mm256_abs_epi16(value)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn saturating_add(left: __m256i, right: __m256i) -> __m256i {
+        arch::_mm256_adds_epi16(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn saturating_sub(left: __m256i, right: __m256i) -> __m256i {
+        arch::_mm256_subs_epi16(left, right)
+    }
+}
+
+impl SimdArith<__m128i> for i32 {
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    unsafe fn add(left: __m128i, right: __m128i) -> __m128i {
+        arch::_mm_add_epi32(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    unsafe fn sub(left: __m128i, right: __m128i) -> __m128i {
+        arch::_mm_sub_epi32(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse4.1")]
+    unsafe fn min(left: __m128i, right: __m128i) -> __m128i {
+        arch::_mm_min_epi32(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse4.1")]
+    unsafe fn max(left: __m128i, right: __m128i) -> __m128i {
+        arch::_mm_max_epi32(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "ssse3")]
+    unsafe fn abs(value: __m128i) -> __m128i {
+        arch::_mm_abs_epi32(value)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    unsafe fn saturating_add(left: __m128i, right: __m128i) -> __m128i {
+        let result = arch::_mm_add_epi32(left, right);
+        // Overflow iff the operands share a sign which the result lacks.
+        let overflow = arch::_mm_andnot_si128(
+            arch::_mm_xor_si128(left, right),
+            arch::_mm_xor_si128(left, result),
+        );
+        saturate_i32_128(left, result, overflow)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    unsafe fn saturating_sub(left: __m128i, right: __m128i) -> __m128i {
+        let result = arch::_mm_sub_epi32(left, right);
+        // Overflow iff the operands differ in sign and the result differs from the left.
+        let overflow = arch::_mm_and_si128(
+            arch::_mm_xor_si128(left, right),
+            arch::_mm_xor_si128(left, result),
+        );
+        saturate_i32_128(left, result, overflow)
+    }
+}
+
+impl SimdArith<__m256i> for i32 {
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn add(left: __m256i, right: __m256i) -> __m256i {
+        arch::_mm256_add_epi32(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn sub(left: __m256i, right: __m256i) -> __m256i {
+        arch::_mm256_sub_epi32(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn min(left: __m256i, right: __m256i) -> __m256i {
+        arch::_mm256_min_epi32(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn max(left: __m256i, right: __m256i) -> __m256i {
+        arch::_mm256_max_epi32(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn abs(value: __m256i) -> __m256i {
+        arch::_mm256_abs_epi32(value)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn saturating_add(left: __m256i, right: __m256i) -> __m256i {
+        let result = arch::_mm256_add_epi32(left, right);
+        let overflow = arch::_mm256_andnot_si256(
+            arch::_mm256_xor_si256(left, right),
+            arch::_mm256_xor_si256(left, result),
+        );
+        saturate_i32_256(left, result, overflow)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn saturating_sub(left: __m256i, right: __m256i) -> __m256i {
+        let result = arch::_mm256_sub_epi32(left, right);
+        let overflow = arch::_mm256_and_si256(
+            arch::_mm256_xor_si256(left, right),
+            arch::_mm256_xor_si256(left, result),
+        );
+        saturate_i32_256(left, result, overflow)
+    }
+}
+
+impl SimdArith<__m128i> for i64 {
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    unsafe fn add(left: __m128i, right: __m128i) -> __m128i {
+        arch::_mm_add_epi64(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    unsafe fn sub(left: __m128i, right: __m128i) -> __m128i {
+        arch::_mm_sub_epi64(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse4.2")]
+    unsafe fn min(left: __m128i, right: __m128i) -> __m128i {
+        select_128(arch::_mm_cmpgt_epi64(left, right), right, left)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse4.2")]
+    unsafe fn max(left: __m128i, right: __m128i) -> __m128i {
+        select_128(arch::_mm_cmpgt_epi64(left, right), left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse4.2")]
+    unsafe fn abs(value: __m128i) -> __m128i {
+        let sign = arch::_mm_cmpgt_epi64(arch::_mm_setzero_si128(), value);
+        arch::_mm_sub_epi64(arch::_mm_xor_si128(value, sign), sign)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse4.2")]
+    unsafe fn saturating_add(left: __m128i, right: __m128i) -> __m128i {
+        let result = arch::_mm_add_epi64(left, right);
+        let overflow = arch::_mm_andnot_si128(
+            arch::_mm_xor_si128(left, right),
+            arch::_mm_xor_si128(left, result),
+        );
+        saturate_i64_128(left, result, overflow)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse4.2")]
+    unsafe fn saturating_sub(left: __m128i, right: __m128i) -> __m128i {
+        let result = arch::_mm_sub_epi64(left, right);
+        let overflow = arch::_mm_and_si128(
+            arch::_mm_xor_si128(left, right),
+            arch::_mm_xor_si128(left, result),
+        );
+        saturate_i64_128(left, result, overflow)
+    }
+}
+
+impl SimdArith<__m256i> for i64 {
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn add(left: __m256i, right: __m256i) -> __m256i {
+        arch::_mm256_add_epi64(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn sub(left: __m256i, right: __m256i) -> __m256i {
+        arch::_mm256_sub_epi64(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn min(left: __m256i, right: __m256i) -> __m256i {
+        select_256(arch::_mm256_cmpgt_epi64(left, right), right, left)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn max(left: __m256i, right: __m256i) -> __m256i {
+        select_256(arch::_mm256_cmpgt_epi64(left, right), left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn abs(value: __m256i) -> __m256i {
+        let sign = arch::_mm256_cmpgt_epi64(arch::_mm256_setzero_si256(), value);
+        arch::_mm256_sub_epi64(arch::_mm256_xor_si256(value, sign), sign)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn saturating_add(left: __m256i, right: __m256i) -> __m256i {
+        let result = arch::_mm256_add_epi64(left, right);
+        let overflow = arch::_mm256_andnot_si256(
+            arch::_mm256_xor_si256(left, right),
+            arch::_mm256_xor_si256(left, result),
+        );
+        saturate_i64_256(left, result, overflow)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn saturating_sub(left: __m256i, right: __m256i) -> __m256i {
+        let result = arch::_mm256_sub_epi64(left, right);
+        let overflow = arch::_mm256_and_si256(
+            arch::_mm256_xor_si256(left, right),
+            arch::_mm256_xor_si256(left, result),
+        );
+        saturate_i64_256(left, result, overflow)
+    }
+}
+
+impl<R> SimdArith<R> for isize
+where
+    R: SimdRegister,
+    isize: SimdOps<R>,
+    i64: SimdArith<R>,
+    i32: SimdArith<R>,
+{
+    #[inline]
+    unsafe fn add(left: R, right: R) -> R {
+        if std::mem::size_of::<isize>() == 8 {
+            <i64 as SimdArith<R>>::add(left, right)
+        } else {
+            <i32 as SimdArith<R>>::add(left, right)
+        }
+    }
+
+    #[inline]
+    unsafe fn sub(left: R, right: R) -> R {
+        if std::mem::size_of::<isize>() == 8 {
+            <i64 as SimdArith<R>>::sub(left, right)
+        } else {
+            <i32 as SimdArith<R>>::sub(left, right)
+        }
+    }
+
+    #[inline]
+    unsafe fn min(left: R, right: R) -> R {
+        if std::mem::size_of::<isize>() == 8 {
+            <i64 as SimdArith<R>>::min(left, right)
+        } else {
+            <i32 as SimdArith<R>>::min(left, right)
+        }
+    }
+
+    #[inline]
+    unsafe fn max(left: R, right: R) -> R {
+        if std::mem::size_of::<isize>() == 8 {
+            <i64 as SimdArith<R>>::max(left, right)
+        } else {
+            <i32 as SimdArith<R>>::max(left, right)
+        }
+    }
+
+    #[inline]
+    unsafe fn abs(value: R) -> R {
+        if std::mem::size_of::<isize>() == 8 {
+            <i64 as SimdArith<R>>::abs(value)
+        } else {
+            <i32 as SimdArith<R>>::abs(value)
+        }
+    }
+
+    #[inline]
+    unsafe fn saturating_add(left: R, right: R) -> R {
+        if std::mem::size_of::<isize>() == 8 {
+            <i64 as SimdArith<R>>::saturating_add(left, right)
+        } else {
+            <i32 as SimdArith<R>>::saturating_add(left, right)
+        }
+    }
+
+    #[inline]
+    unsafe fn saturating_sub(left: R, right: R) -> R {
+        if std::mem::size_of::<isize>() == 8 {
+            <i64 as SimdArith<R>>::saturating_sub(left, right)
+        } else {
+            <i32 as SimdArith<R>>::saturating_sub(left, right)
+        }
+    }
+}
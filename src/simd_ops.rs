@@ -8,12 +8,45 @@ use typenum::{U16, U32};
 pub trait SimdRegister: Copy + Sized {
     type MovemaskSize: Bits;
 
+    /// Reinterpret a slice of 256-bit blocks as a slice of this register type.
+    ///
+    /// # Safety
+    ///
+    /// The register type must evenly divide a 256-bit block.
     #[inline]
     unsafe fn from_m256i(slice256: &[__m256i]) -> &[Self] {
         let len = slice256.len() * (size_of::<__m256i>() / size_of::<Self>());
         std::slice::from_raw_parts(slice256.as_ptr() as *const Self, len)
     }
+
+    /// Reinterpret a mutable slice of 256-bit blocks as a slice of this
+    /// register type.
+    ///
+    /// # Safety
+    ///
+    /// The register type must evenly divide a 256-bit block.
+    #[inline]
+    unsafe fn from_m256i_mut(slice256: &mut [__m256i]) -> &mut [Self] {
+        let len = slice256.len() * (size_of::<__m256i>() / size_of::<Self>());
+        std::slice::from_raw_parts_mut(slice256.as_mut_ptr() as *mut Self, len)
+    }
+
+    /// Load a register from aligned memory.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support the instruction set for this register type.
     unsafe fn load(&self) -> Self;
+
+    /// Store a register to aligned memory.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support the instruction set for this register type.
+    unsafe fn store(self, target: &mut Self);
+
+    /// Get the raw bits of a movemask result.
+    fn mask_value(mask: Bitmap<Self::MovemaskSize>) -> u32;
 }
 
 impl SimdRegister for __m128i {
@@ -24,6 +57,17 @@ impl SimdRegister for __m128i {
     unsafe fn load(&self) -> Self {
         arch::_mm_load_si128(self)
     }
+
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    unsafe fn store(self, target: &mut Self) {
+        arch::_mm_store_si128(target, self)
+    }
+
+    #[inline]
+    fn mask_value(mask: Bitmap<U16>) -> u32 {
+        u32::from(mask.into_value())
+    }
 }
 
 impl SimdRegister for __m256i {
@@ -34,11 +78,39 @@ impl SimdRegister for __m256i {
         slice
     }
 
+    #[inline]
+    unsafe fn from_m256i_mut(slice: &mut [__m256i]) -> &mut [Self] {
+        slice
+    }
+
     #[inline]
     #[target_feature(enable = "avx2")]
     unsafe fn load(&self) -> Self {
         arch::_mm256_load_si256(self)
     }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn store(self, target: &mut Self) {
+        arch::_mm256_store_si256(target, self)
+    }
+
+    #[inline]
+    fn mask_value(mask: Bitmap<U32>) -> u32 {
+        mask.into_value()
+    }
+}
+
+/// Test if the CPU supports the `__m128i` compares for `A`.
+///
+/// Lanes of 64 bits or more compare with SSE4.1 and SSE4.2 instructions,
+/// which aren't part of the x86_64 baseline.
+pub(crate) fn has_sse_compare<A>() -> bool {
+    if size_of::<A>() < 8 {
+        is_x86_feature_detected!("sse2")
+    } else {
+        is_x86_feature_detected!("sse4.2")
+    }
 }
 
 /// Operations on datatypes stored in SIMD registers.
@@ -46,8 +118,31 @@ pub trait SimdOps<R: SimdRegister>: Sized {
     const ALIGNMENT: usize = size_of::<R>() / size_of::<Self>();
     const BITS_PER_CMP: usize = size_of::<Self>();
 
+    /// Broadcast a value into every lane of a register.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support the instruction set for the register type.
     unsafe fn set(value: Self) -> R;
+
+    /// Compare two registers for equality, lane by lane.
+    ///
+    /// The result has `BITS_PER_CMP` bits set for each lane that compared
+    /// equal.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support the instruction set for the register type.
     unsafe fn cmp_eq(left: R, right: R) -> Bitmap<R::MovemaskSize>;
+
+    /// Compare two registers, lane by lane, for `left > right`.
+    ///
+    /// The result has `BITS_PER_CMP` bits set for each lane where the left
+    /// value was greater.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support the instruction set for the register type.
     unsafe fn cmp_gt(left: R, right: R) -> Bitmap<R::MovemaskSize>;
 }
 
@@ -179,13 +274,13 @@ impl SimdOps<__m128i> for i64 {
     }
 
     #[inline]
-    #[target_feature(enable = "sse2")]
+    #[target_feature(enable = "sse4.1")]
     unsafe fn cmp_eq(left: __m128i, right: __m128i) -> Bitmap<U16> {
         Bitmap::from_value(arch::_mm_movemask_epi8(arch::_mm_cmpeq_epi64(left, right)) as u16)
     }
 
     #[inline]
-    #[target_feature(enable = "sse2")]
+    #[target_feature(enable = "sse4.2")]
     unsafe fn cmp_gt(left: __m128i, right: __m128i) -> Bitmap<U16> {
         Bitmap::from_value(arch::_mm_movemask_epi8(arch::_mm_cmpgt_epi64(left, right)) as u16)
     }
@@ -228,7 +323,7 @@ impl SimdOps<__m128i> for isize {
     }
 
     #[inline]
-    #[target_feature(enable = "sse2")]
+    #[target_feature(enable = "sse4.1")]
     unsafe fn cmp_eq(left: __m128i, right: __m128i) -> Bitmap<U16> {
         if std::mem::size_of::<isize>() == 8 {
             Bitmap::from_value(arch::_mm_movemask_epi8(arch::_mm_cmpeq_epi64(left, right)) as u16)
@@ -243,7 +338,7 @@ impl SimdOps<__m128i> for isize {
     }
 
     #[inline]
-    #[target_feature(enable = "sse2")]
+    #[target_feature(enable = "sse4.2")]
     unsafe fn cmp_gt(left: __m128i, right: __m128i) -> Bitmap<U16> {
         if std::mem::size_of::<isize>() == 8 {
             Bitmap::from_value(arch::_mm_movemask_epi8(arch::_mm_cmpgt_epi64(left, right)) as u16)
@@ -312,3 +407,212 @@ impl SimdOps<__m256i> for isize {
         }
     }
 }
+
+// Unsigned types share the signed equality comparisons, and compare for
+// ordering by flipping the sign bit of both sides first. The 128-bit
+// compares use the same instructions as the signed type's, so need the same
+// target features.
+macro_rules! unsigned_ops {
+    ($unsigned:ty, $signed:ty, $eq_feature:literal, $gt_feature:literal) => {
+        impl SimdOps<__m128i> for $unsigned {
+            #[inline]
+            #[target_feature(enable = "sse2")]
+            unsafe fn set(value: $unsigned) -> __m128i {
+                <$signed as SimdOps<__m128i>>::set(value as $signed)
+            }
+
+            #[inline]
+            #[target_feature(enable = $eq_feature)]
+            unsafe fn cmp_eq(left: __m128i, right: __m128i) -> Bitmap<U16> {
+                <$signed as SimdOps<__m128i>>::cmp_eq(left, right)
+            }
+
+            #[inline]
+            #[target_feature(enable = $gt_feature)]
+            unsafe fn cmp_gt(left: __m128i, right: __m128i) -> Bitmap<U16> {
+                let bias = <$signed as SimdOps<__m128i>>::set(<$signed>::MIN);
+                <$signed as SimdOps<__m128i>>::cmp_gt(
+                    arch::_mm_xor_si128(left, bias),
+                    arch::_mm_xor_si128(right, bias),
+                )
+            }
+        }
+
+        impl SimdOps<__m256i> for $unsigned {
+            #[inline]
+            #[target_feature(enable = "avx2")]
+            unsafe fn set(value: $unsigned) -> __m256i {
+                <$signed as SimdOps<__m256i>>::set(value as $signed)
+            }
+
+            #[inline]
+            #[target_feature(enable = "avx2")]
+            unsafe fn cmp_eq(left: __m256i, right: __m256i) -> Bitmap<U32> {
+                <$signed as SimdOps<__m256i>>::cmp_eq(left, right)
+            }
+
+            #[inline]
+            #[target_feature(enable = "avx2")]
+            unsafe fn cmp_gt(left: __m256i, right: __m256i) -> Bitmap<U32> {
+                let bias = <$signed as SimdOps<__m256i>>::set(<$signed>::MIN);
+                <$signed as SimdOps<__m256i>>::cmp_gt(
+                    arch::_mm256_xor_si256(left, bias),
+                    arch::_mm256_xor_si256(right, bias),
+                )
+            }
+        }
+    };
+}
+
+unsigned_ops!(u8, i8, "sse2", "sse2");
+unsigned_ops!(u16, i16, "sse2", "sse2");
+unsigned_ops!(u32, i32, "sse2", "sse2");
+unsigned_ops!(u64, i64, "sse4.1", "sse4.2");
+unsigned_ops!(usize, isize, "sse4.1", "sse4.2");
+
+// 128-bit integers take up a whole 128-bit lane, as a low and a high 64-bit
+// half. Equality needs both halves to match. Ordering compares the high
+// halves, and falls back to an unsigned compare of the low halves when the
+// high halves are equal. Each result is then copied to both halves of its
+// lane, so the movemask has all 16 bits set for a matching lane. As with
+// 64-bit lanes, the `__m128i` compares need SSE4.1 and SSE4.2.
+
+/// Flip the sign bit of each 64-bit half, so signed compares order the
+/// halves as unsigned.
+#[inline]
+#[target_feature(enable = "sse2")]
+unsafe fn unsigned_halves_128(value: __m128i) -> __m128i {
+    arch::_mm_xor_si128(value, arch::_mm_set1_epi64x(i64::MIN))
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn unsigned_halves_256(value: __m256i) -> __m256i {
+    arch::_mm256_xor_si256(value, arch::_mm256_set1_epi64x(i64::MIN))
+}
+
+#[inline]
+#[target_feature(enable = "sse4.1")]
+unsafe fn cmp_eq_128x1(left: __m128i, right: __m128i) -> Bitmap<U16> {
+    let eq = arch::_mm_cmpeq_epi64(left, right);
+    let both = arch::_mm_and_si128(eq, arch::_mm_shuffle_epi32(eq, 0x4e));
+    Bitmap::from_value(arch::_mm_movemask_epi8(both) as u16)
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn cmp_eq_128x2(left: __m256i, right: __m256i) -> Bitmap<U32> {
+    let eq = arch::_mm256_cmpeq_epi64(left, right);
+    let both = arch::_mm256_and_si256(eq, arch::_mm256_shuffle_epi32(eq, 0x4e));
+    Bitmap::from_value(arch::_mm256_movemask_epi8(both) as u32)
+}
+
+/// Combine the compare of the high halves, `high_gt`, with the unsigned
+/// compare of the low halves.
+#[inline]
+#[target_feature(enable = "sse4.2")]
+unsafe fn cmp_gt_128x1(left: __m128i, right: __m128i, high_gt: __m128i) -> Bitmap<U16> {
+    let eq = arch::_mm_cmpeq_epi64(left, right);
+    let low_gt = arch::_mm_cmpgt_epi64(unsigned_halves_128(left), unsigned_halves_128(right));
+    // Move the low half results up into the high halves
+    let low_gt = arch::_mm_shuffle_epi32(low_gt, 0x44);
+    let gt = arch::_mm_or_si128(high_gt, arch::_mm_and_si128(eq, low_gt));
+    // Copy the high half results down into the low halves
+    let gt = arch::_mm_shuffle_epi32(gt, 0xee);
+    Bitmap::from_value(arch::_mm_movemask_epi8(gt) as u16)
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn cmp_gt_128x2(left: __m256i, right: __m256i, high_gt: __m256i) -> Bitmap<U32> {
+    let eq = arch::_mm256_cmpeq_epi64(left, right);
+    let low_gt = arch::_mm256_cmpgt_epi64(unsigned_halves_256(left), unsigned_halves_256(right));
+    let low_gt = arch::_mm256_shuffle_epi32(low_gt, 0x44);
+    let gt = arch::_mm256_or_si256(high_gt, arch::_mm256_and_si256(eq, low_gt));
+    let gt = arch::_mm256_shuffle_epi32(gt, 0xee);
+    Bitmap::from_value(arch::_mm256_movemask_epi8(gt) as u32)
+}
+
+impl SimdOps<__m128i> for i128 {
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    unsafe fn set(value: i128) -> __m128i {
+        arch::_mm_set_epi64x((value >> 64) as i64, value as i64)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse4.1")]
+    unsafe fn cmp_eq(left: __m128i, right: __m128i) -> Bitmap<U16> {
+        cmp_eq_128x1(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse4.2")]
+    unsafe fn cmp_gt(left: __m128i, right: __m128i) -> Bitmap<U16> {
+        cmp_gt_128x1(left, right, arch::_mm_cmpgt_epi64(left, right))
+    }
+}
+
+impl SimdOps<__m256i> for i128 {
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn set(value: i128) -> __m256i {
+        let (high, low) = ((value >> 64) as i64, value as i64);
+        arch::_mm256_set_epi64x(high, low, high, low)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn cmp_eq(left: __m256i, right: __m256i) -> Bitmap<U32> {
+        cmp_eq_128x2(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn cmp_gt(left: __m256i, right: __m256i) -> Bitmap<U32> {
+        cmp_gt_128x2(left, right, arch::_mm256_cmpgt_epi64(left, right))
+    }
+}
+
+impl SimdOps<__m128i> for u128 {
+    #[inline]
+    #[target_feature(enable = "sse2")]
+    unsafe fn set(value: u128) -> __m128i {
+        <i128 as SimdOps<__m128i>>::set(value as i128)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse4.1")]
+    unsafe fn cmp_eq(left: __m128i, right: __m128i) -> Bitmap<U16> {
+        cmp_eq_128x1(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "sse4.2")]
+    unsafe fn cmp_gt(left: __m128i, right: __m128i) -> Bitmap<U16> {
+        let high_gt = arch::_mm_cmpgt_epi64(unsigned_halves_128(left), unsigned_halves_128(right));
+        cmp_gt_128x1(left, right, high_gt)
+    }
+}
+
+impl SimdOps<__m256i> for u128 {
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn set(value: u128) -> __m256i {
+        <i128 as SimdOps<__m256i>>::set(value as i128)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn cmp_eq(left: __m256i, right: __m256i) -> Bitmap<U32> {
+        cmp_eq_128x2(left, right)
+    }
+
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn cmp_gt(left: __m256i, right: __m256i) -> Bitmap<U32> {
+        let high_gt =
+            arch::_mm256_cmpgt_epi64(unsigned_halves_256(left), unsigned_halves_256(right));
+        cmp_gt_128x2(left, right, high_gt)
+    }
+}
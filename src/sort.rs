@@ -0,0 +1,462 @@
+use std::arch::x86_64::{self as arch, __m256i};
+use std::mem::size_of;
+
+use bitmaps::Bitmap;
+use typenum::U32;
+
+use crate::{DefaultZero, SimdArith, SimdArrayOps, SimdOps, SimdRegister, SimdVec};
+
+// Bitonic sorting networks over 256-bit registers.
+//
+// Every compare-exchange step in a bitonic network pairs lane `i` with lane
+// `i ^ d` for some power of two `d`. Measured in bytes rather than lanes, the
+// same shuffles serve every lane width, so the networks below only need the
+// lane width to know where to stop, and `SortKey::min_max` to do the
+// comparisons.
+
+/// Types which `SimdVec::sort`, `SimdArray::sort` and `SimdVec::merge_sorted`
+/// can sort.
+///
+/// It's implemented for the primitive integer types, and for newtypes made
+/// with `simd_newtype!`. Any other type with `SimdOps<__m256i>` can use the
+/// defaults, which build the sorting networks on `SimdOps::cmp_gt`:
+///
+/// ```
+/// # use simdify::{simd_newtype, SortKey, SimdVec};
+/// # #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+/// # #[repr(transparent)]
+/// # struct Score(u16);
+/// # simd_newtype!(Score(u16));
+/// let mut scores: SimdVec<Score> = [Score(5), Score(1), Score(3)][..].into();
+/// scores.sort();
+/// assert_eq!(&[Score(1), Score(3), Score(5)], &*scores);
+/// ```
+///
+/// The type's `Ord` must agree with its SIMD comparisons.
+pub trait SortKey: Ord + Copy + SimdOps<__m256i> {
+    /// Whether the networks are worth using. With only two lanes a
+    /// register, 128-bit types are sorted in scalar code.
+    const NETWORK: bool = size_of::<Self>() <= 8;
+
+    /// Take the lane by lane minimum and maximum of two registers.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn min_max(left: __m256i, right: __m256i) -> (__m256i, __m256i) {
+        let greater = expand_mask(Self::cmp_gt(left, right));
+        (
+            arch::_mm256_blendv_epi8(left, right, greater),
+            arch::_mm256_blendv_epi8(right, left, greater),
+        )
+    }
+}
+
+/// Turn a movemask result back into a register, with every byte whose bit
+/// is set filled with ones.
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn expand_mask(mask: Bitmap<U32>) -> __m256i {
+    let bits = arch::_mm256_set1_epi32(__m256i::mask_value(mask) as i32);
+    // Give each byte a copy of the mask byte holding its bit, then test it.
+    let bytes = arch::_mm256_shuffle_epi8(
+        bits,
+        arch::_mm256_setr_epi8(
+            0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2, 3, 3, 3, 3, 3,
+            3, 3, 3,
+        ),
+    );
+    let select = arch::_mm256_set1_epi64x(0x8040_2010_0804_0201u64 as i64);
+    arch::_mm256_cmpeq_epi8(arch::_mm256_and_si256(bytes, select), select)
+}
+
+macro_rules! sort_key_signed {
+    ($($type:ty),*) => {
+        $(
+            impl SortKey for $type {
+                #[inline]
+                #[target_feature(enable = "avx2")]
+                unsafe fn min_max(left: __m256i, right: __m256i) -> (__m256i, __m256i) {
+                    (
+                        <$type as SimdArith<__m256i>>::min(left, right),
+                        <$type as SimdArith<__m256i>>::max(left, right),
+                    )
+                }
+            }
+        )*
+    };
+}
+
+sort_key_signed!(i8, i16, i32, i64, isize);
+
+macro_rules! sort_key_unsigned {
+    ($($type:ty => $min:ident, $max:ident);*) => {
+        $(
+            impl SortKey for $type {
+                #[inline]
+                #[target_feature(enable = "avx2")]
+                unsafe fn min_max(left: __m256i, right: __m256i) -> (__m256i, __m256i) {
+                    (arch::$min(left, right), arch::$max(left, right))
+                }
+            }
+        )*
+    };
+}
+
+sort_key_unsigned!(
+    u8 => _mm256_min_epu8, _mm256_max_epu8;
+    u16 => _mm256_min_epu16, _mm256_max_epu16;
+    u32 => _mm256_min_epu32, _mm256_max_epu32
+);
+
+impl SortKey for u64 {
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn min_max(left: __m256i, right: __m256i) -> (__m256i, __m256i) {
+        // There's no unsigned 64-bit compare, so flip the sign bits and
+        // compare as signed.
+        let sign = arch::_mm256_set1_epi64x(i64::MIN);
+        let gt = arch::_mm256_cmpgt_epi64(
+            arch::_mm256_xor_si256(left, sign),
+            arch::_mm256_xor_si256(right, sign),
+        );
+        (
+            arch::_mm256_blendv_epi8(left, right, gt),
+            arch::_mm256_blendv_epi8(right, left, gt),
+        )
+    }
+}
+
+impl SortKey for usize {
+    #[inline]
+    #[target_feature(enable = "avx2")]
+    unsafe fn min_max(left: __m256i, right: __m256i) -> (__m256i, __m256i) {
+        if size_of::<usize>() == 8 {
+            <u64 as SortKey>::min_max(left, right)
+        } else {
+            <u32 as SortKey>::min_max(left, right)
+        }
+    }
+}
+
+impl SortKey for i128 {}
+impl SortKey for u128 {}
+
+const fn blend_masks() -> [[u8; 32]; 5] {
+    let mut masks = [[0; 32]; 5];
+    let mut shift = 0;
+    while shift < 5 {
+        let mut index = 0;
+        while index < 32 {
+            if index & (1 << shift) != 0 {
+                masks[shift][index] = 0xff;
+            }
+            index += 1;
+        }
+        shift += 1;
+    }
+    masks
+}
+
+/// For each byte distance `d`, a mask selecting the bytes whose index has bit
+/// `d` set, which are the upper halves of each compare-exchange pair.
+static BLEND_MASKS: [[u8; 32]; 5] = blend_masks();
+
+/// Swap each byte `i` with byte `i ^ distance`.
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn swap_lanes(value: __m256i, distance: usize) -> __m256i {
+    match distance {
+        1 => arch::_mm256_shuffle_epi8(
+            value,
+            arch::_mm256_setr_epi8(
+                1, 0, 3, 2, 5, 4, 7, 6, 9, 8, 11, 10, 13, 12, 15, 14, 1, 0, 3, 2, 5, 4, 7, 6, 9, 8,
+                11, 10, 13, 12, 15, 14,
+            ),
+        ),
+        2 => arch::_mm256_shufflehi_epi16(arch::_mm256_shufflelo_epi16(value, 0xb1), 0xb1),
+        4 => arch::_mm256_shuffle_epi32(value, 0xb1),
+        8 => arch::_mm256_shuffle_epi32(value, 0x4e),
+        16 => arch::_mm256_permute2x128_si256(value, value, 0x01),
+        _ => unreachable!("invalid lane distance {}", distance),
+    }
+}
+
+/// Reverse the order of the lanes within each group of `2 * half` bytes.
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn flip<A>(mut value: __m256i, half: usize) -> __m256i {
+    let mut distance = size_of::<A>();
+    while distance <= half {
+        value = swap_lanes(value, distance);
+        distance *= 2;
+    }
+    value
+}
+
+/// Compare each lane with its partner `distance` bytes away, keeping the
+/// minimum in the lower lane and the maximum in the upper lane.
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn exchange<A>(value: __m256i, partner: __m256i, distance: usize) -> __m256i
+where
+    A: SortKey,
+{
+    let mask = arch::_mm256_loadu_si256(
+        BLEND_MASKS[distance.trailing_zeros() as usize].as_ptr() as *const _
+    );
+    let (min, max) = A::min_max(value, partner);
+    arch::_mm256_blendv_epi8(min, max, mask)
+}
+
+/// Sort a bitonic register.
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn clean<A>(mut value: __m256i) -> __m256i
+where
+    A: SortKey,
+{
+    let mut distance = size_of::<__m256i>() / 2;
+    while distance >= size_of::<A>() {
+        value = exchange::<A>(value, swap_lanes(value, distance), distance);
+        distance /= 2;
+    }
+    value
+}
+
+/// Sort the lanes of a register.
+#[target_feature(enable = "avx2")]
+unsafe fn sort_register<A>(mut value: __m256i) -> __m256i
+where
+    A: SortKey,
+{
+    let mut width = 2 * size_of::<A>();
+    while width <= size_of::<__m256i>() {
+        let half = width / 2;
+        value = exchange::<A>(value, flip::<A>(value, half), half);
+        let mut distance = half / 2;
+        while distance >= size_of::<A>() {
+            value = exchange::<A>(value, swap_lanes(value, distance), distance);
+            distance /= 2;
+        }
+        width *= 2;
+    }
+    value
+}
+
+/// Merge two sorted registers, returning the lower and upper halves of the
+/// result, each sorted.
+#[inline]
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn merge_registers<A>(left: __m256i, right: __m256i) -> (__m256i, __m256i)
+where
+    A: SortKey,
+{
+    let right = flip::<A>(right, size_of::<__m256i>() / 2);
+    let (min, max) = A::min_max(left, right);
+    (clean::<A>(min), clean::<A>(max))
+}
+
+#[inline]
+fn first<A: Copy>(block: &__m256i) -> A {
+    unsafe { *(block as *const __m256i as *const A) }
+}
+
+/// Merge two non-empty sorted runs of whole registers into `out`.
+#[target_feature(enable = "avx2")]
+pub(crate) unsafe fn merge_runs<A>(left: &[__m256i], right: &[__m256i], out: &mut [__m256i])
+where
+    A: SortKey,
+{
+    debug_assert_eq!(left.len() + right.len(), out.len());
+    let (lower, mut upper) = merge_registers::<A>(left[0].load(), right[0].load());
+    lower.store(&mut out[0]);
+    let (mut left_index, mut right_index) = (1, 1);
+    let last = out.len() - 1;
+    for target in &mut out[1..last] {
+        // Feed the network from whichever run has the smaller next element.
+        let next = if right_index == right.len()
+            || (left_index < left.len()
+                && first::<A>(&left[left_index]) <= first(&right[right_index]))
+        {
+            left_index += 1;
+            &left[left_index - 1]
+        } else {
+            right_index += 1;
+            &right[right_index - 1]
+        };
+        let (lower, next_upper) = merge_registers::<A>(upper, next.load());
+        lower.store(target);
+        upper = next_upper;
+    }
+    upper.store(&mut out[last]);
+}
+
+/// Merge neighbouring runs of `width` registers from `source` into `target`.
+#[target_feature(enable = "avx2")]
+unsafe fn merge_pass<A>(source: &[__m256i], target: &mut [__m256i], width: usize)
+where
+    A: SortKey,
+{
+    let len = source.len();
+    let mut start = 0;
+    while start < len {
+        let middle = std::cmp::min(start + width, len);
+        let end = std::cmp::min(start + 2 * width, len);
+        if middle == end {
+            target[start..end].copy_from_slice(&source[start..end]);
+        } else {
+            merge_runs::<A>(
+                &source[start..middle],
+                &source[middle..end],
+                &mut target[start..end],
+            );
+        }
+        start = end;
+    }
+}
+
+/// Sort the elements of a slice of registers, treating it as one array.
+#[target_feature(enable = "avx2")]
+unsafe fn sort_registers<A>(data: &mut [__m256i])
+where
+    A: SortKey,
+{
+    for block in data.iter_mut() {
+        sort_register::<A>(block.load()).store(block);
+    }
+    let mut scratch = vec![__m256i::default_zero(); data.len()];
+    let mut width = 1;
+    let mut sorted_in_data = true;
+    while width < data.len() {
+        if sorted_in_data {
+            merge_pass::<A>(data, &mut scratch, width);
+        } else {
+            merge_pass::<A>(&scratch, data, width);
+        }
+        sorted_in_data = !sorted_in_data;
+        width *= 2;
+    }
+    if !sorted_in_data {
+        data.copy_from_slice(&scratch);
+    }
+}
+
+/// Merge the sorted elements from `middle` onwards into the sorted elements
+/// before it.
+fn merge_tail<A>(slice: &mut [A], middle: usize)
+where
+    A: Ord + Copy,
+{
+    let tail = slice[middle..].to_vec();
+    let mut head = middle;
+    let mut rest = tail.len();
+    for target in (0..slice.len()).rev() {
+        if rest == 0 {
+            break;
+        }
+        if head > 0 && slice[head - 1] > tail[rest - 1] {
+            slice[target] = slice[head - 1];
+            head -= 1;
+        } else {
+            slice[target] = tail[rest - 1];
+            rest -= 1;
+        }
+    }
+}
+
+/// Sort the first `len` elements stored in `blocks`.
+///
+/// Whole registers are sorted with bitonic networks and merged with a
+/// bitonic merge network. A partial register at the end is sorted on its own
+/// and merged in afterwards.
+pub(crate) fn sort<A>(blocks: &mut [__m256i], len: usize)
+where
+    A: SortKey,
+{
+    let lanes = size_of::<__m256i>() / size_of::<A>();
+    let whole = len / lanes;
+    let simd = A::NETWORK && is_x86_feature_detected!("avx2");
+    if simd {
+        unsafe { sort_registers::<A>(&mut blocks[..whole]) };
+    }
+    let slice = unsafe { std::slice::from_raw_parts_mut(blocks.as_mut_ptr() as *mut A, len) };
+    if !simd {
+        slice.sort_unstable();
+    } else if whole * lanes < len {
+        slice[whole * lanes..].sort_unstable();
+        merge_tail(slice, whole * lanes);
+    }
+}
+
+/// Merge two sorted arrays into a new vector, keeping duplicates.
+///
+/// Whole registers from both arrays are merged with the bitonic merge network
+/// straight into the output blocks. The partial registers at the ends are
+/// merged together and then into the result.
+pub(crate) fn merge<A, L, T>(left: &L, right: &T) -> SimdVec<A>
+where
+    A: SortKey + DefaultZero,
+    L: SimdArrayOps<A> + ?Sized,
+    T: SimdArrayOps<A> + ?Sized,
+{
+    let lanes = size_of::<__m256i>() / size_of::<A>();
+    let len = left.len() + right.len();
+    let mut out = vec![__m256i::default_zero(); len.div_ceil(lanes)];
+    let (mut left_whole, mut right_whole) = (0, 0);
+    if A::NETWORK && is_x86_feature_detected!("avx2") {
+        left_whole = left.len() / lanes;
+        right_whole = right.len() / lanes;
+        let left_blocks = &left.data_m256()[..left_whole];
+        let right_blocks = &right.data_m256()[..right_whole];
+        let target = &mut out[..left_whole + right_whole];
+        if left_whole == 0 {
+            target.copy_from_slice(right_blocks);
+        } else if right_whole == 0 {
+            target.copy_from_slice(left_blocks);
+        } else {
+            unsafe { merge_runs::<A>(left_blocks, right_blocks, target) };
+        }
+    }
+    let merged = (left_whole + right_whole) * lanes;
+    let slice = unsafe { std::slice::from_raw_parts_mut(out.as_mut_ptr() as *mut A, len) };
+    let left_rest = &left[left_whole * lanes..];
+    let right_rest = &right[right_whole * lanes..];
+    let rest = &mut slice[merged..];
+    rest[..left_rest.len()].copy_from_slice(left_rest);
+    rest[left_rest.len()..].copy_from_slice(right_rest);
+    merge_tail(rest, left_rest.len());
+    merge_tail(slice, merged);
+    SimdVec::from_blocks(out, len)
+}
+
+/// Merge any number of sorted arrays into a new vector, keeping duplicates.
+///
+/// Runs are merged pairwise, halving their number each round.
+pub(crate) fn merge_many<A>(runs: &[&SimdVec<A>]) -> SimdVec<A>
+where
+    A: SortKey + DefaultZero,
+{
+    let mut level: Vec<SimdVec<A>> = runs
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => merge(*left, *right),
+            [run] => (*run).clone(),
+            _ => unreachable!(),
+        })
+        .collect();
+    while level.len() > 1 {
+        let mut next = Vec::with_capacity(level.len().div_ceil(2));
+        let mut runs = level.into_iter();
+        while let Some(left) = runs.next() {
+            next.push(match runs.next() {
+                Some(right) => merge(&left, &right),
+                None => left,
+            });
+        }
+        level = next;
+    }
+    level.pop().unwrap_or_default()
+}
@@ -0,0 +1,146 @@
+use std::iter::FromIterator;
+use std::ops::Range;
+
+use crate::array_ops::rank_in;
+use crate::SimdVec;
+
+const PREFIX_BYTES: usize = 8;
+
+/// Read up to the first 8 bytes of `bytes` as a big-endian `u64`, padding
+/// with `fill`.
+fn prefix_value(bytes: &[u8], fill: u8) -> u64 {
+    let mut buffer = [fill; PREFIX_BYTES];
+    let len = std::cmp::min(bytes.len(), PREFIX_BYTES);
+    buffer[..len].copy_from_slice(&bytes[..len]);
+    u64::from_be_bytes(buffer)
+}
+
+/// A sorted table of byte strings with a SIMD searchable prefix column.
+///
+/// The first 8 bytes of each string are stored as a big-endian `u64`, zero
+/// padded, in a `SimdVec`. Comparing these prefixes as integers orders them
+/// the same way as comparing the strings byte by byte, so a lookup searches
+/// the prefix column first and only compares whole strings within the
+/// range of equal prefixes.
+///
+/// Strings are any type which can be viewed as bytes, such as `String`,
+/// `&str` or `Vec<u8>`.
+#[derive(Clone, Debug)]
+pub struct SimdStrIndex<S = String> {
+    prefixes: SimdVec<u64>,
+    strings: Vec<S>,
+}
+
+impl<S> SimdStrIndex<S>
+where
+    S: AsRef<[u8]>,
+{
+    /// Build an index over a collection of strings, sorting them by their
+    /// bytes.
+    pub fn new(mut strings: Vec<S>) -> Self {
+        strings.sort_by(|left, right| left.as_ref().cmp(right.as_ref()));
+        let mut prefixes = SimdVec::with_capacity(strings.len());
+        prefixes.extend(
+            strings
+                .iter()
+                .map(|string| prefix_value(string.as_ref(), 0)),
+        );
+        SimdStrIndex { prefixes, strings }
+    }
+
+    /// Get the number of strings in the index.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    /// Test if the index is empty.
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Get the string at a given index.
+    ///
+    /// Returns `None` if the index is out of bounds.
+    pub fn get(&self, index: usize) -> Option<&S> {
+        self.strings.get(index)
+    }
+
+    /// Get the strings, in sorted order.
+    pub fn strings(&self) -> &[S] {
+        &self.strings
+    }
+
+    /// Get the column of big-endian string prefixes.
+    pub fn prefixes(&self) -> &SimdVec<u64> {
+        &self.prefixes
+    }
+
+    /// Find the range of strings whose prefixes lie between `low` and
+    /// `high` inclusive.
+    fn prefix_bounds(&self, low: u64, high: u64) -> Range<usize> {
+        let all = 0..self.len();
+        let start = rank_in(&self.prefixes, low, false, all.clone());
+        let end = rank_in(&self.prefixes, high, true, start..all.end);
+        start..end
+    }
+
+    /// Find the index of the first string not less than `key`.
+    pub fn lower_bound<K>(&self, key: &K) -> usize
+    where
+        K: AsRef<[u8]> + ?Sized,
+    {
+        let key = key.as_ref();
+        let value = prefix_value(key, 0);
+        let range = self.prefix_bounds(value, value);
+        range.start + self.strings[range].partition_point(|string| string.as_ref() < key)
+    }
+
+    /// Search for a string.
+    ///
+    /// Returns the index of the string, or the index where it would be
+    /// inserted. If the string occurs more than once, any of its indices may
+    /// be returned.
+    pub fn search<K>(&self, key: &K) -> Result<usize, usize>
+    where
+        K: AsRef<[u8]> + ?Sized,
+    {
+        let key = key.as_ref();
+        let value = prefix_value(key, 0);
+        let range = self.prefix_bounds(value, value);
+        let start = range.start;
+        match self.strings[range].binary_search_by(|string| string.as_ref().cmp(key)) {
+            Ok(index) => Ok(start + index),
+            Err(index) => Err(start + index),
+        }
+    }
+
+    /// Find the range of strings which start with `prefix`.
+    pub fn prefix_range<K>(&self, prefix: &K) -> Range<usize>
+    where
+        K: AsRef<[u8]> + ?Sized,
+    {
+        let prefix = prefix.as_ref();
+        let range = self.prefix_bounds(prefix_value(prefix, 0), prefix_value(prefix, 0xff));
+        let strings = &self.strings[range.clone()];
+        // Strings shorter than the prefix can share its padded value when
+        // it ends in zero bytes, so check the edges of the range in full.
+        let start = strings.partition_point(|string| string.as_ref() < prefix);
+        let end = strings.partition_point(|string| {
+            let string = string.as_ref();
+            string < prefix || string.starts_with(prefix)
+        });
+        range.start + start..range.start + end
+    }
+}
+
+impl<S> FromIterator<S> for SimdStrIndex<S>
+where
+    S: AsRef<[u8]>,
+{
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+    {
+        Self::new(iter.into_iter().collect())
+    }
+}
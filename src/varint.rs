@@ -0,0 +1,365 @@
+use std::arch::x86_64 as arch;
+use std::arch::x86_64::__m128i;
+use std::fmt::{Display, Formatter};
+
+use crate::{DefaultZero, SimdOps, SimdVec};
+
+/// An error decoding variable length integers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VarintError {
+    /// The input ended inside the value starting at byte `offset`.
+    Truncated { offset: usize },
+    /// The value starting at byte `offset` doesn't fit the integer type.
+    Overflow { offset: usize },
+}
+
+impl Display for VarintError {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
+        match self {
+            VarintError::Truncated { offset } => {
+                write!(f, "input ends inside the varint at byte {}", offset)
+            }
+            VarintError::Overflow { offset } => {
+                write!(f, "the varint at byte {} overflows its type", offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for VarintError {}
+
+/// For each control byte, the shuffle gathering its values' bytes into
+/// zero-extended lanes.
+#[repr(align(16))]
+pub(crate) struct ShuffleTable([[u8; 16]; 256]);
+
+/// Build the shuffle and data length tables for stream-vbyte control bytes
+/// describing `per_control` values of `width` bytes each.
+///
+/// Control bytes holding a length longer than `width` get a data length of
+/// zero, which no valid control byte has.
+const fn stream_tables(per_control: usize, width: usize) -> (ShuffleTable, [u8; 256]) {
+    let code_bits = 8 / per_control;
+    let mut shuffles = [[0x80; 16]; 256];
+    let mut lengths = [0; 256];
+    let mut control = 0;
+    while control < 256 {
+        let mut offset = 0;
+        let mut slot = 0;
+        let mut valid = true;
+        while slot < per_control {
+            let len = ((control >> (slot * code_bits)) & ((1 << code_bits) - 1)) + 1;
+            if len > width {
+                valid = false;
+            } else {
+                let mut byte = 0;
+                while byte < len {
+                    shuffles[control][slot * width + byte] = (offset + byte) as u8;
+                    byte += 1;
+                }
+            }
+            offset += len;
+            slot += 1;
+        }
+        if valid {
+            lengths[control] = offset as u8;
+        }
+        control += 1;
+    }
+    (ShuffleTable(shuffles), lengths)
+}
+
+static U32_TABLES: (ShuffleTable, [u8; 256]) = stream_tables(4, 4);
+static U64_TABLES: (ShuffleTable, [u8; 256]) = stream_tables(2, 8);
+
+/// An unsigned integer type with varint encodings.
+pub(crate) trait Varint: Copy + DefaultZero {
+    const BITS: u32;
+    /// The number of values whose lengths share a stream-vbyte control byte.
+    const PER_CONTROL: usize;
+
+    fn from_u64(value: u64) -> Self;
+    fn into_u64(self) -> u64;
+    fn stream_tables() -> &'static (ShuffleTable, [u8; 256]);
+
+    /// The number of bits in each length code of a control byte.
+    fn code_bits() -> usize {
+        8 / Self::PER_CONTROL
+    }
+
+    /// Get the encoded length of the value in `slot` of a control byte, or
+    /// `None` if it's longer than the type.
+    fn stream_len(control: u8, slot: usize) -> Option<usize> {
+        let code = usize::from(control) >> (slot * Self::code_bits());
+        let len = (code & ((1 << Self::code_bits()) - 1)) + 1;
+        if len > Self::BITS as usize / 8 {
+            None
+        } else {
+            Some(len)
+        }
+    }
+}
+
+impl Varint for u32 {
+    const BITS: u32 = 32;
+    const PER_CONTROL: usize = 4;
+
+    fn from_u64(value: u64) -> Self {
+        value as u32
+    }
+
+    fn into_u64(self) -> u64 {
+        u64::from(self)
+    }
+
+    fn stream_tables() -> &'static (ShuffleTable, [u8; 256]) {
+        &U32_TABLES
+    }
+}
+
+impl Varint for u64 {
+    const BITS: u32 = 64;
+    const PER_CONTROL: usize = 2;
+
+    fn from_u64(value: u64) -> Self {
+        value
+    }
+
+    fn into_u64(self) -> u64 {
+        self
+    }
+
+    fn stream_tables() -> &'static (ShuffleTable, [u8; 256]) {
+        &U64_TABLES
+    }
+}
+
+/// The fewest little endian bytes holding `value`, and at least one.
+fn byte_len(value: u64) -> usize {
+    std::cmp::max(1, (64 - value.leading_zeros() as usize).div_ceil(8))
+}
+
+/// Encode values in the stream-vbyte format: the byte lengths of the values,
+/// two bits each for `u32` or four for `u64`, packed into control bytes
+/// first, then the little endian bytes of each value.
+pub(crate) fn encode_stream_vbyte<A: Varint>(values: &[A]) -> Vec<u8> {
+    let mut out = vec![0; values.len().div_ceil(A::PER_CONTROL)];
+    out.reserve(std::mem::size_of_val(values));
+    for (index, value) in values.iter().enumerate() {
+        let value = value.into_u64();
+        let len = byte_len(value);
+        let slot = index % A::PER_CONTROL;
+        out[index / A::PER_CONTROL] |= ((len - 1) << (slot * A::code_bits())) as u8;
+        out.extend_from_slice(&value.to_le_bytes()[..len]);
+    }
+    out
+}
+
+/// Decode whole control bytes with one shuffle each: the table entry for a
+/// control byte moves every value's bytes into its own lane and zeroes the
+/// rest, and the length table says how far to step through the data.
+///
+/// Stops at the first invalid control byte, or once a 16-byte load would
+/// read past the data, and returns the number of control bytes and data
+/// bytes consumed.
+#[target_feature(enable = "ssse3")]
+unsafe fn decode_stream_vbyte_ssse3<A: Varint>(
+    controls: &[u8],
+    data: &[u8],
+    out: &mut [A],
+) -> (usize, usize) {
+    let (shuffles, lengths) = A::stream_tables();
+    let mut position = 0;
+    for (group, control) in controls.iter().enumerate() {
+        let len = usize::from(lengths[usize::from(*control)]);
+        if len == 0 || position + 16 > data.len() {
+            return (group, position);
+        }
+        let bytes = arch::_mm_loadu_si128(data.as_ptr().add(position) as *const __m128i);
+        let shuffle =
+            arch::_mm_load_si128(shuffles.0[usize::from(*control)].as_ptr() as *const __m128i);
+        arch::_mm_storeu_si128(
+            out.as_mut_ptr().add(group * A::PER_CONTROL) as *mut __m128i,
+            arch::_mm_shuffle_epi8(bytes, shuffle),
+        );
+        position += len;
+    }
+    (controls.len(), position)
+}
+
+/// Decode `len` stream-vbyte encoded values, using SSSE3 shuffles if
+/// `ssse3` is set and finishing in scalar code. Bytes past the last value
+/// are ignored.
+pub(crate) fn decode_stream_vbyte<A: Varint>(
+    bytes: &[u8],
+    len: usize,
+    ssse3: bool,
+) -> Result<SimdVec<A>, VarintError> {
+    let control_len = len.div_ceil(A::PER_CONTROL);
+    if bytes.len() < control_len {
+        return Err(VarintError::Truncated {
+            offset: bytes.len(),
+        });
+    }
+    let (controls, data) = bytes.split_at(control_len);
+    let mut out = SimdVec::with_capacity(len);
+    out.resize_blocks(len);
+    let (mut group, mut position) = (0, 0);
+    if ssse3 {
+        // Only control bytes describing a full set of values are decoded
+        // with shuffles, so every 16-byte store lands inside the vector.
+        let full = len / A::PER_CONTROL;
+        let (decoded, consumed) =
+            unsafe { decode_stream_vbyte_ssse3(&controls[..full], data, &mut out) };
+        group = decoded;
+        position = consumed;
+    }
+    for index in group * A::PER_CONTROL..len {
+        let offset = control_len + position;
+        let value_len = A::stream_len(controls[index / A::PER_CONTROL], index % A::PER_CONTROL)
+            .ok_or(VarintError::Overflow { offset })?;
+        let value = data
+            .get(position..position + value_len)
+            .ok_or(VarintError::Truncated { offset })?;
+        let mut word = [0; 8];
+        word[..value_len].copy_from_slice(value);
+        out[index] = A::from_u64(u64::from_le_bytes(word));
+        position += value_len;
+    }
+    Ok(out)
+}
+
+/// Encode values as unsigned LEB128 varints: seven bits a byte, least
+/// significant first, with the high bit set on every byte but the last.
+pub(crate) fn encode_leb128<A: Varint>(values: &[A]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(values.len());
+    for value in values {
+        let mut value = value.into_u64();
+        while value >= 0x80 {
+            out.push(value as u8 | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+    out
+}
+
+/// Assemble the bytes of a single LEB128 value starting at `offset`.
+fn leb128_value<A: Varint>(bytes: &[u8], offset: usize) -> Result<A, VarintError> {
+    let max_len = A::BITS.div_ceil(7) as usize;
+    if bytes.len() > max_len {
+        return Err(VarintError::Overflow { offset });
+    }
+    let mut value = 0;
+    for (index, byte) in bytes.iter().enumerate() {
+        value |= u64::from(byte & 0x7f) << (7 * index);
+    }
+    // The last byte of a longest value has room for bits past the type.
+    if bytes.len() == max_len
+        && (bytes[max_len - 1] & 0x7f) >> (A::BITS as usize - 7 * (max_len - 1)) != 0
+    {
+        return Err(VarintError::Overflow { offset });
+    }
+    Ok(A::from_u64(value))
+}
+
+/// Decode LEB128 varints until the input runs out.
+///
+/// Each 16-byte chunk is tested for continuation bits with one
+/// `SimdOps::cmp_gt` on `i8` lanes, since the high bit is the sign bit. A
+/// chunk without any is 16 single byte values, copied straight out, and
+/// otherwise the clear bits of the mask mark where values end.
+pub(crate) fn decode_leb128<A: Varint>(bytes: &[u8]) -> Result<SimdVec<A>, VarintError> {
+    let mut out = SimdVec::with_capacity(bytes.len() / 2);
+    let mut position = 0;
+    while position + 16 <= bytes.len() {
+        // SSE2 is part of the x86_64 baseline.
+        let continues = unsafe {
+            let chunk = arch::_mm_loadu_si128(bytes.as_ptr().add(position) as *const __m128i);
+            <i8 as SimdOps<__m128i>>::cmp_gt(<i8 as SimdOps<__m128i>>::set(0), chunk)
+        }
+        .into_value();
+        if continues == 0 {
+            out.extend(
+                bytes[position..position + 16]
+                    .iter()
+                    .map(|byte| A::from_u64(u64::from(*byte))),
+            );
+            position += 16;
+            continue;
+        }
+        let mut ends = !continues;
+        let mut start = 0;
+        while ends != 0 {
+            let end = ends.trailing_zeros() as usize;
+            out.push(leb128_value(
+                &bytes[position + start..=position + end],
+                position + start,
+            )?);
+            start = end + 1;
+            ends &= ends - 1;
+        }
+        if start == 0 {
+            // Sixteen bytes without an end is longer than any value.
+            return Err(VarintError::Overflow { offset: position });
+        }
+        position += start;
+    }
+    while position < bytes.len() {
+        match bytes[position..].iter().position(|byte| byte & 0x80 == 0) {
+            Some(end) => {
+                out.push(leb128_value(&bytes[position..=position + end], position)?);
+                position += end + 1;
+            }
+            None if bytes.len() - position > A::BITS.div_ceil(7) as usize => {
+                return Err(VarintError::Overflow { offset: position })
+            }
+            None => return Err(VarintError::Truncated { offset: position }),
+        }
+    }
+    Ok(out)
+}
+
+macro_rules! varint_vec {
+    ($type:ty) => {
+        impl SimdVec<$type> {
+            /// Decode a vector from a run of unsigned LEB128 varints.
+            ///
+            /// Continuation bits are found 16 bytes at a time with SIMD
+            /// compares. Fails if the input ends inside a value, or a value
+            /// is too large for the type.
+            pub fn from_varint_bytes(bytes: &[u8]) -> Result<Self, VarintError> {
+                decode_leb128(bytes)
+            }
+
+            /// Encode the vector as a run of unsigned LEB128 varints.
+            pub fn to_varint_bytes(&self) -> Vec<u8> {
+                encode_leb128(self)
+            }
+
+            /// Decode `len` values in the stream-vbyte format, as written
+            /// by `to_stream_vbyte`.
+            ///
+            /// Lengths are kept apart from the data in control bytes, so a
+            /// group of values is decoded with a single SSSE3 shuffle
+            /// looked up from its control byte. See ['Stream VByte: Faster
+            /// Byte-Oriented Integer Compression,' Lemire, Kurz and Rupp,
+            /// 2017](https://arxiv.org/abs/1709.08990). Bytes past the last
+            /// value are ignored.
+            pub fn from_stream_vbyte(bytes: &[u8], len: usize) -> Result<Self, VarintError> {
+                decode_stream_vbyte(bytes, len, is_x86_feature_detected!("ssse3"))
+            }
+
+            /// Encode the vector in the stream-vbyte format.
+            ///
+            /// The length isn't stored, and must be passed to
+            /// `from_stream_vbyte`.
+            pub fn to_stream_vbyte(&self) -> Vec<u8> {
+                encode_stream_vbyte(self)
+            }
+        }
+    };
+}
+
+varint_vec!(u32);
+varint_vec!(u64);
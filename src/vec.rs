@@ -5,7 +5,7 @@ use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
 use std::ops::{Deref, DerefMut};
 
-use crate::{DefaultZero, SimdArrayOps};
+use crate::{DefaultZero, RadixKey, SimdArrayOps, SortKey};
 
 /// A heap allocated SIMD aligned vector.
 pub struct SimdVec<A> {
@@ -32,7 +32,7 @@ where
         SimdVec {
             phantom: PhantomData,
             size: 0,
-            vec: Vec::with_capacity((capacity + (Self::block_size() - 1)) / Self::block_size()),
+            vec: Vec::with_capacity(capacity.div_ceil(Self::block_size())),
         }
     }
 
@@ -58,6 +58,15 @@ where
         }
     }
 
+    /// Resize the vector to `len` elements, leaving their values unspecified.
+    pub(crate) fn resize_blocks(&mut self, len: usize) {
+        self.vec.resize(
+            len.div_ceil(Self::block_size()),
+            arch::__m256i::default_zero(),
+        );
+        self.size = len;
+    }
+
     /// Push a value to the end of the vector.
     pub fn push(&mut self, value: A) {
         if self.at_capacity() {
@@ -82,6 +91,90 @@ where
     }
 }
 
+impl<A> SimdVec<A> {
+    /// Construct a vector from 32-byte blocks holding `size` elements.
+    pub(crate) fn from_blocks(vec: Vec<arch::__m256i>, size: usize) -> Self {
+        debug_assert!(
+            size <= vec.len() * (std::mem::size_of::<arch::__m256i>() / std::mem::size_of::<A>())
+        );
+        SimdVec {
+            phantom: PhantomData,
+            size,
+            vec,
+        }
+    }
+
+    /// Take the vector's 32-byte blocks, including any padding.
+    pub(crate) fn into_blocks(self) -> Vec<arch::__m256i> {
+        self.vec
+    }
+}
+
+impl<A> SimdVec<A>
+where
+    A: SortKey,
+{
+    /// Sort the vector.
+    ///
+    /// This uses in-register bitonic sorting networks followed by vectorised
+    /// merging of the sorted runs when AVX2 is available, and falls back to
+    /// `sort_unstable` otherwise, and for types without a network, such as
+    /// 128-bit integers. The sort is not stable.
+    pub fn sort(&mut self) {
+        crate::sort::sort::<A>(&mut self.vec, self.size)
+    }
+
+    /// Merge two sorted vectors into a new sorted vector, keeping
+    /// duplicates.
+    ///
+    /// Whole registers are merged with a bitonic merge network when AVX2 is
+    /// available, writing straight into the new vector's blocks. Types
+    /// without a network, such as 128-bit integers, are merged in scalar
+    /// code.
+    pub fn merge_sorted(left: &SimdVec<A>, right: &SimdVec<A>) -> SimdVec<A>
+    where
+        A: DefaultZero,
+    {
+        crate::sort::merge(left, right)
+    }
+
+    /// Merge any number of sorted vectors into a new sorted vector, keeping
+    /// duplicates.
+    ///
+    /// The vectors are merged pairwise with `merge_sorted`, so the total work
+    /// grows with the logarithm of the number of vectors.
+    pub fn merge_sorted_many(runs: &[&SimdVec<A>]) -> SimdVec<A>
+    where
+        A: DefaultZero,
+    {
+        crate::sort::merge_many(runs)
+    }
+}
+
+impl<A> SimdVec<A>
+where
+    A: RadixKey + DefaultZero,
+{
+    /// Sort the vector with an LSD radix sort.
+    ///
+    /// This allocates a scratch buffer the size of the vector. Use
+    /// `radix_sort_with_buffer` to reuse one across sorts.
+    pub fn radix_sort(&mut self) {
+        self.radix_sort_with_buffer(&mut SimdVec::new())
+    }
+
+    /// Sort the vector with an LSD radix sort, using `buffer` as scratch
+    /// space.
+    ///
+    /// The buffer is resized to the length of the vector, reusing its
+    /// allocation where possible, and its contents are unspecified
+    /// afterwards.
+    pub fn radix_sort_with_buffer(&mut self, buffer: &mut SimdVec<A>) {
+        buffer.resize_blocks(self.size);
+        crate::radix::radix_sort::<A>(&mut self.vec, &mut buffer.vec, self.size)
+    }
+}
+
 impl<A> SimdArrayOps<A> for SimdVec<A>
 where
     A: Ord,
@@ -110,6 +203,16 @@ where
     }
 }
 
+impl<A> Clone for SimdVec<A> {
+    fn clone(&self) -> Self {
+        SimdVec {
+            phantom: PhantomData,
+            size: self.size,
+            vec: self.vec.clone(),
+        }
+    }
+}
+
 impl<A> Deref for SimdVec<A> {
     type Target = [A];
 